@@ -0,0 +1,86 @@
+use chip8_core::chip8::vram::{HEIGHT, WIDTH};
+
+/* INFO:
+    The help overlay fills the screen with the keypad, one 16x8 cell per key.
+    Each cell shows the CHIP-8 key and the qwerty key bound to it. Keys the
+    game uses are drawn inverted.
+
+    1 1  2 2  3 3  C 4
+    4 Q  5 W  6 E  D R
+    7 A  8 S  9 D  E F
+    A Z  0 X  B C  F V
+*/
+
+const KEYPAD: [[usize; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xC],
+    [0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE],
+    [0xA, 0x0, 0xB, 0xF],
+];
+const CELL_WIDTH: usize = WIDTH / 4;
+const CELL_HEIGHT: usize = HEIGHT / 4;
+
+// 3x5 glyphs, one row per byte with the leftmost pixel in bit 2.
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b101, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        _ => [0; 5],
+    }
+}
+
+fn draw_glyph(pixels: &mut [bool], c: char, x: usize, y: usize) {
+    for (row, bits) in glyph(c).iter().enumerate() {
+        for column in 0..3 {
+            if bits & (0b100 >> column) != 0 {
+                pixels[(y + row) * WIDTH + x + column] ^= true;
+            }
+        }
+    }
+}
+
+// `labels` is the qwerty key bound to each CHIP-8 key.
+pub fn keypad(used: &[bool; 0x10], labels: &[&str; 0x10], on: [u8; 3], off: [u8; 3]) -> Vec<u8> {
+    let mut pixels = vec![false; WIDTH * HEIGHT];
+    for (row, keys) in KEYPAD.iter().enumerate() {
+        for (column, &key) in keys.iter().enumerate() {
+            let (x, y) = (column * CELL_WIDTH, row * CELL_HEIGHT);
+            if used[key] {
+                for line in pixels[y * WIDTH..(y + CELL_HEIGHT - 1) * WIDTH].chunks_mut(WIDTH) {
+                    for pixel in &mut line[x..x + CELL_WIDTH - 1] {
+                        *pixel = true;
+                    }
+                }
+            }
+            let hex = format!("{:X}", key).chars().next().unwrap();
+            let qwerty = labels[key].chars().next().unwrap_or(' ');
+            draw_glyph(&mut pixels, hex, x + 3, y + 1);
+            draw_glyph(&mut pixels, qwerty, x + 9, y + 1);
+        }
+    }
+    pixels
+        .iter()
+        .flat_map(|&pixel| if pixel { on } else { off })
+        .collect()
+}
@@ -1,20 +1,83 @@
 use chip8_core::chip8::audio::{AudioEvent, AudioSink};
 use chip8_core::chip8::keyboard::{HexKey, Keyboard};
+use chip8_core::chip8::phosphor::{Persistence, Phosphor};
+use chip8_core::chip8::recording::MAX_SCALE;
+use chip8_core::chip8::vip::CYCLES_PER_FRAME;
 use chip8_core::chip8::vram::{VideoSink, HEIGHT, WIDTH};
-use chip8_core::chip8::Chip8;
+use chip8_core::chip8::{Backend, Chip8, MachineCode};
+use chip8_core::database::{RomDatabase, RomInfo};
+use chip8_core::notes::{FileName, Notes};
 use chip8_core::program::Program;
 use fb_now::glutin::{
     ElementState, Event, KeyboardInput, VirtualKeyCode, WindowBuilder, WindowEvent,
 };
-use fb_now::FbNow;
+use fb_now::{preset, FbNow, Pass, Region, ScaleMode, DEFAULT_SCALE, PASSES};
+use palette::Palette;
+use record::{Format, Recorder};
 use rodio::default_output_device;
 use rodio::source::SineWave;
 use rodio::source::Source;
 use rodio::Sink;
 use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::thread;
 use std::time::Duration;
 
+mod help;
+mod palette;
+mod record;
+
+const DEFAULT_CYCLES_PER_FRAME: usize = 10;
+// The qwerty key bound to each CHIP-8 key, see the layout in `main`.
+const QWERTY: [&str; 0x10] = [
+    "X", "1", "2", "3", "Q", "W", "E", "A", "S", "D", "Z", "C", "4", "R", "F", "V",
+];
+
+fn credit(title: &str, authors: &[String], release: Option<&String>) {
+    let mut credits = authors.join(", ");
+    if let Some(release) = release {
+        if !credits.is_empty() {
+            credits.push_str(", ");
+        }
+        credits.push_str(release);
+    }
+    if credits.is_empty() {
+        println!("{}", title);
+    } else {
+        println!("{} ({})", title, credits);
+    }
+}
+
+fn describe(info: &RomInfo) {
+    credit(&info.title, &info.authors, info.release.as_ref());
+    for &(ref action, key) in &info.keys {
+        println!(
+            "  {:<12} {:X} ({})",
+            action,
+            key,
+            QWERTY[key as usize & 0xF]
+        );
+    }
+}
+
+// The key of a key press, ignoring releases.
+fn key_pressed(event: &WindowEvent) -> Option<VirtualKeyCode> {
+    match *event {
+        WindowEvent::KeyboardInput {
+            input:
+                KeyboardInput {
+                    virtual_keycode,
+                    state: ElementState::Pressed,
+                    ..
+                },
+            ..
+        } => virtual_keycode,
+        _ => None,
+    }
+}
+
 macro_rules! keyboard_update {
     ($event:ident, $keyboard:ident, $chip8_keycode:path, $keycode:path) => {
         if let WindowEvent::KeyboardInput {
@@ -32,17 +95,255 @@ macro_rules! keyboard_update {
     };
 }
 
+// A file holding a shader, else a preset by name.
+fn shader_passes(shader: &str) -> Option<Vec<Pass>> {
+    let path = Path::new(shader);
+    if path.is_file() {
+        match Pass::from_file(path) {
+            Ok(pass) => Some(vec![pass]),
+            Err(error) => {
+                eprintln!("Cannot load {}: {:?}", shader, error);
+                None
+            }
+        }
+    } else {
+        preset(shader)
+    }
+}
+
+// Claims the first free `<name>-<n>.<extension>` in the current directory
+// by creating it empty.
+fn free_file(name: &str, extension: &str) -> io::Result<(PathBuf, File)> {
+    for number in 1.. {
+        let path = PathBuf::from(format!("{}-{}.{}", name, number, extension));
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(ref error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    unreachable!()
+}
+
+fn stop_recording(recording: Recorder) {
+    match recording.finish() {
+        Ok(path) => println!("Saved {}", path.display()),
+        Err(error) => eprintln!("Cannot save the recording: {}", error),
+    }
+}
+
+fn save_screenshot(name: &str, png: &[u8]) -> io::Result<PathBuf> {
+    let (path, mut file) = free_file(name, "png")?;
+    file.write_all(png).map(|_| path)
+}
+
 fn main() {
-    if env::args().len() != 2 {
-        eprintln!("chip8-client [CHIP8 FILE]");
+    // With --vip the program runs on an emulated VIP under its original
+    // interpreter, read from the given image.
+    let mut interpreter_file = None;
+    let mut program_file = None;
+    let mut scale = DEFAULT_SCALE;
+    let mut scale_mode = ScaleMode::default();
+    let mut palette_name = None;
+    let mut palettes_file = palette::config_path();
+    let mut persistence = Persistence::Off;
+    let mut vblank_only = false;
+    let mut shaders = Vec::new();
+    let mut backend = fb_now::Backend::OpenGl;
+    let mut screenshot_scale = None;
+    let mut record_format = Format::Gif;
+    let mut record_scale = None;
+    let mut args = env::args().skip(1);
+    let mut usage = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--vip" => interpreter_file = args.next(),
+            "--palette" => palette_name = args.next(),
+            "--palettes" => palettes_file = args.next().map(PathBuf::from),
+            "--scale" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) if value > 0 => scale = value,
+                _ => usage = true,
+            },
+            "--blend" => persistence = Persistence::Blend,
+            "--decay" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(half_life) => persistence = Persistence::Decay { half_life },
+                None => usage = true,
+            },
+            "--vblank" => vblank_only = true,
+            "--software" => backend = fb_now::Backend::Software,
+            "--record" => match args.next().and_then(|name| Format::from_name(&name)) {
+                Some(format) => record_format = format,
+                None => usage = true,
+            },
+            "--record-scale" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) if value > 0 && value <= MAX_SCALE => record_scale = Some(value),
+                _ => usage = true,
+            },
+            "--screenshot-scale" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) if value > 0 => screenshot_scale = Some(value),
+                _ => usage = true,
+            },
+            "--shader" => match args.next() {
+                Some(shader) => shaders.push(shader),
+                None => usage = true,
+            },
+            "--mode" => match args.next().and_then(|name| ScaleMode::from_name(&name)) {
+                Some(mode) => scale_mode = mode,
+                None => usage = true,
+            },
+            _ if program_file.is_none() => program_file = Some(arg),
+            _ => usage = true,
+        }
+    }
+    let program_file = match program_file {
+        Some(ref program_file) if !usage => program_file.clone(),
+        _ => {
+            eprintln!(
+                "chip8-client [--vip INTERPRETER] [--scale N] [--mode stretch|fit|integer|fill] \
+                 [--palette NAME] [--palettes FILE] [--blend | --decay FRAMES] [--vblank] \
+                 [--shader NAME|FILE]... [--software] [--screenshot-scale N] \
+                 [--record gif|mp4] [--record-scale N] [CHIP8 FILE]"
+            );
+            return;
+        }
+    };
+    if backend == fb_now::Backend::Software && !cfg!(feature = "software") {
+        eprintln!("--software needs chip8_client built with the software feature");
         return;
     }
-    let program_file = env::args().nth(1).unwrap();
-    let mut fb = FbNow::new(
-        WindowBuilder::new().with_title("chip8-rs"),
+    let interpreter = match interpreter_file {
+        Some(ref interpreter_file) => match fs::read(interpreter_file) {
+            Ok(interpreter) => Some(interpreter),
+            Err(error) => {
+                eprintln!("Cannot load {}: {:?}", interpreter_file, error);
+                return;
+            }
+        },
+        None => None,
+    };
+    let mut program = match Program::from_path(&program_file) {
+        Ok(program) => program,
+        Err(error) => {
+            eprintln!("Cannot load {}: {:?}", program_file, error);
+            return;
+        }
+    };
+    let info = RomDatabase::bundled().lookup(&program);
+    if let Some(ref info) = info {
+        program = match info.lay_out(program) {
+            Ok(program) => program,
+            Err(error) => {
+                eprintln!("Cannot load {}: {:?}", program_file, error);
+                return;
+            }
+        };
+    }
+    let notes = Notes::find(&program_file);
+    let file_name = Path::new(&program_file)
+        .file_name()
+        .map(|name| FileName::parse(&name.to_string_lossy()))
+        .unwrap_or_default();
+    let mut title = format!("chip8-rs - {}", file_name.title);
+    let mut cycles_per_frame = DEFAULT_CYCLES_PER_FRAME;
+    let mut palettes = match palettes_file {
+        Some(ref path) => palette::load(path).unwrap_or_else(|error| {
+            eprintln!("Ignoring the palettes in {}: {}", path.display(), error);
+            palette::builtin()
+        }),
+        None => palette::builtin(),
+    };
+    // The keys the game uses, highlighted in the help overlay.
+    let mut used = [false; 0x10];
+    if let Some(ref info) = info {
+        describe(info);
+        for &(_, key) in &info.keys {
+            used[key as usize & 0xF] = true;
+        }
+        title = format!("chip8-rs - {}", info.title);
+        cycles_per_frame = info.cycles_per_frame().unwrap_or(cycles_per_frame);
+        // The game's own colors come first, so they are picked by default.
+        // XO-CHIP games may list up to 16; only the first plane's two show.
+        if let Some(ref colors) = info.colors {
+            if let Some(pixels) = colors.pixels.get(..2) {
+                palettes.insert(0, Palette::new("game", pixels).unwrap());
+            }
+        }
+    } else {
+        credit(
+            &file_name.title,
+            &file_name.authors,
+            file_name.year.as_ref(),
+        );
+    }
+    if let Some(ref notes) = notes {
+        for key in &notes.keys {
+            used[*key as usize] = true;
+        }
+        for sentence in &notes.controls {
+            println!("  {}", sentence);
+        }
+    }
+    let mut palette = match palette_name {
+        Some(ref name) => match palettes.iter().position(|palette| &palette.name == name) {
+            Some(index) => index,
+            None => {
+                let names: Vec<&str> = palettes
+                    .iter()
+                    .map(|palette| palette.name.as_str())
+                    .collect();
+                eprintln!("Unknown palette {}, try one of: {}", name, names.join(", "));
+                return;
+            }
+        },
+        None => 0,
+    };
+    // The shaders asked for come first, then none and every preset.
+    let mut chains = Vec::new();
+    if !shaders.is_empty() {
+        let mut chain = Vec::new();
+        for shader in &shaders {
+            match shader_passes(shader) {
+                Some(passes) => chain.extend(passes),
+                None => {
+                    if !Path::new(shader).is_file() {
+                        eprintln!(
+                            "Unknown shader {}, try a file or one of: {}, vip",
+                            shader,
+                            PASSES.join(", ")
+                        );
+                    }
+                    return;
+                }
+            }
+        }
+        chains.push((shaders.join(" + "), chain));
+    }
+    chains.push(("none".to_string(), Vec::new()));
+    for name in PASSES.iter().chain(&["vip"]) {
+        chains.push((name.to_string(), preset(name).unwrap()));
+    }
+    let mut chain = 0;
+    println!(
+        "Press F1 to show the keypad, F2 to change the scaling, F3 to change the colors, \
+         F4 to change the shaders, F9 to start and stop recording, F11 for fullscreen \
+         and F12 for a screenshot."
+    );
+    // Screenshots default to the size the window opens at.
+    let screenshot_scale = screenshot_scale.unwrap_or(scale as usize);
+    let record_scale = record_scale.unwrap_or(scale as usize);
+    let mut recorder: Option<Recorder> = None;
+    let mut fb = FbNow::with_backend(
+        WindowBuilder::new().with_title(title),
         WIDTH as u32,
         HEIGHT as u32,
+        scale,
+        backend,
     );
+    fb.set_scale_mode(scale_mode);
+    if let Err(error) = fb.set_passes(&chains[chain].1) {
+        eprintln!("Cannot compile the {} shader: {}", error.pass, error.error);
+        return;
+    }
     let mut window_open = true;
     let mut video_sink = VideoSink::new();
     let mut audio_sink = AudioSink::new();
@@ -51,16 +352,65 @@ fn main() {
     let source = SineWave::new(440).delay(Duration::from_millis(10));
     sink.append(source);
     sink.pause();
-    let mut chip8 = Chip8::new();
-    let program = Program::new(&program_file);
+    let mut chip8 = match interpreter {
+        Some(ref interpreter) => {
+            let mut chip8 = Chip8::with_backend(Backend::Vip);
+            if let Err(error) = chip8.set_vip_interpreter(interpreter) {
+                eprintln!("Cannot use the VIP interpreter: {:?}", error);
+                return;
+            }
+            // The VIP keeps its own time.
+            cycles_per_frame = CYCLES_PER_FRAME;
+            chip8
+        }
+        None => Chip8::new(),
+    };
+    chip8.set_machine_code(MachineCode::Cdp1802);
+    if let Some(quirks) = info.as_ref().and_then(|info| info.quirks) {
+        chip8.set_quirks(quirks);
+    }
     chip8.load_program(&program);
     let mut keyboard = Keyboard::new();
+    let mut help = false;
+    let mut beeping = false;
+    // Also kept to redraw the screen when the palette changes.
+    let mut phosphor = Phosphor::new(persistence);
+    phosphor.set_vblank_only(vblank_only);
+    video_sink.push(chip8.pixel_buffer());
+    phosphor.update(&mut video_sink);
+    phosphor.frame();
+    phosphor.take_dirty();
+    let mut screen_size = (WIDTH, HEIGHT);
+    let colors = &palettes[palette];
+    fb.update_buffer(&phosphor.to_rgb(colors.on(), colors.off()))
+        .unwrap();
     while window_open {
+        let mut toggle_help = false;
+        let mut next_scale_mode = false;
+        let mut next_palette = false;
+        let mut next_chain = false;
+        let mut toggle_fullscreen = false;
+        let mut screenshot = false;
+        let mut toggle_recording = false;
+        let mut resized = None;
         fb.events_loop.poll_events(|event| {
             if let Event::WindowEvent { event, .. } = event {
                 if let WindowEvent::CloseRequested = event {
                     window_open = false;
                 }
+                if let WindowEvent::Resized(size) = event {
+                    resized = Some(size);
+                }
+                match key_pressed(&event) {
+                    Some(VirtualKeyCode::F1) => toggle_help = true,
+                    Some(VirtualKeyCode::F2) => next_scale_mode = true,
+                    Some(VirtualKeyCode::F3) => next_palette = true,
+                    Some(VirtualKeyCode::F4) => next_chain = true,
+                    Some(VirtualKeyCode::F11) => toggle_fullscreen = true,
+                    Some(VirtualKeyCode::F9) => toggle_recording = true,
+                    Some(VirtualKeyCode::F12) => screenshot = true,
+                    _ => {}
+                }
                 /* INFO:
                     chip8      qwerty
                     1 2 3 C    1 2 3 4
@@ -86,27 +436,162 @@ fn main() {
                 keyboard_update!(event, keyboard, HexKey::Xf, VirtualKeyCode::V);
             }
         });
-        chip8.step(&keyboard, &mut video_sink, &mut audio_sink);
+        // Nothing more is drawn once the window is closed, only the
+        // recording finished below.
+        if !window_open {
+            break;
+        }
+        if let Some(size) = resized {
+            fb.resize_window(size);
+        }
+        if next_scale_mode {
+            let mode = fb.scale_mode().next();
+            println!("Scaling: {}", mode.name());
+            fb.set_scale_mode(mode);
+        }
+        if next_chain {
+            chain = (chain + 1) % chains.len();
+            println!("Shaders: {}", chains[chain].0);
+            // Only the first chain can come from a file, and it already
+            // compiled at startup.
+            fb.set_passes(&chains[chain].1).unwrap();
+        }
+        if toggle_fullscreen {
+            fb.toggle_fullscreen();
+        }
+        let name = if file_name.title.is_empty() {
+            "chip8"
+        } else {
+            &file_name.title
+        };
+        // The screen as the core has it, without the phosphor.
+        if screenshot {
+            let colors = &palettes[palette];
+            let png = chip8
+                .pixel_buffer()
+                .to_png(screenshot_scale, colors.on(), colors.off());
+            match save_screenshot(name, &png) {
+                Ok(path) => println!("Saved {}", path.display()),
+                Err(error) => eprintln!("Cannot save the screenshot: {}", error),
+            }
+        }
+        // Recordings keep the colors they started with.
+        if toggle_recording {
+            match recorder.take() {
+                Some(recording) => stop_recording(recording),
+                None => {
+                    let colors = &palettes[palette];
+                    let started =
+                        free_file(name, record_format.extension()).and_then(|(path, _)| {
+                            Recorder::start(
+                                record_format,
+                                path.clone(),
+                                &chip8.pixel_buffer(),
+                                record_scale,
+                                colors.on(),
+                                colors.off(),
+                            )
+                            .inspect_err(|_| {
+                                // Unclaimed, as nothing will be written to it.
+                                let _ = fs::remove_file(&path);
+                            })
+                        });
+                    match started {
+                        Ok(started) => {
+                            println!("Recording");
+                            recorder = Some(started);
+                        }
+                        Err(error) => eprintln!("Cannot record: {}", error),
+                    }
+                }
+            }
+        }
+        if next_palette {
+            palette = (palette + 1) % palettes.len();
+            println!("Palette: {}", palettes[palette].name);
+            let colors = &palettes[palette];
+            if help {
+                fb.update_buffer(&help::keypad(&used, &QWERTY, colors.on(), colors.off()))
+                    .unwrap();
+            } else {
+                fb.update_buffer(&phosphor.to_rgb(colors.on(), colors.off()))
+                    .unwrap();
+            }
+        }
+        // The game is paused while the keypad is shown.
+        if toggle_help {
+            help = !help;
+            if help {
+                sink.pause();
+                fb.resize_buffer(WIDTH as u32, HEIGHT as u32);
+                let colors = &palettes[palette];
+                fb.update_buffer(&help::keypad(&used, &QWERTY, colors.on(), colors.off()))
+                    .unwrap();
+            } else {
+                if beeping {
+                    sink.play();
+                }
+                fb.resize_buffer(screen_size.0 as u32, screen_size.1 as u32);
+                let colors = &palettes[palette];
+                fb.update_buffer(&phosphor.to_rgb(colors.on(), colors.off()))
+                    .unwrap();
+            }
+        }
+        if help {
+            fb.present();
+            thread::sleep(Duration::from_millis(16));
+            continue;
+        }
+        chip8.run(
+            cycles_per_frame,
+            &keyboard,
+            &mut video_sink,
+            &mut audio_sink,
+        );
+        chip8.tick_timers(&mut audio_sink);
         match audio_sink.event.take() {
-            Some(AudioEvent::Play) => sink.play(),
-            Some(AudioEvent::Stop) => sink.pause(),
+            Some(AudioEvent::Play) => {
+                beeping = true;
+                sink.play()
+            }
+            Some(AudioEvent::Stop) => {
+                beeping = false;
+                sink.pause()
+            }
             None => {}
         }
-        if let Some(sink_buffer) = video_sink.get() {
-            let buffer = sink_buffer
-                .data
-                .iter()
-                .flat_map(|p| {
-                    if *p {
-                        vec![10u8, 250, 10]
-                    } else {
-                        vec![10u8, 10, 10]
-                    }
-                })
-                .collect();
-            fb.update_buffer(buffer);
-            // TODO: This needs to be smarter
+        // One frame per tick, as the core has it.
+        if let Some(ref mut recording) = recorder {
+            if let Err(error) = recording.frame(&chip8.pixel_buffer(), beeping) {
+                eprintln!("Stopped recording: {}", error);
+                recorder = None;
+            }
         }
-        thread::sleep(Duration::from_millis(7))
+        phosphor.update(&mut video_sink);
+        if phosphor.frame() {
+            // Hi-res ROMs switch to 64x64 after they start.
+            if (phosphor.width(), phosphor.height()) != screen_size {
+                screen_size = (phosphor.width(), phosphor.height());
+                fb.resize_buffer(screen_size.0 as u32, screen_size.1 as u32);
+            }
+            // Only what changed is uploaded; after a resize that is all of it.
+            let colors = &palettes[palette];
+            for rect in phosphor.take_dirty() {
+                let region = Region {
+                    x: rect.x as u32,
+                    y: rect.y as u32,
+                    width: rect.width as u32,
+                    height: rect.height as u32,
+                };
+                let pixels = phosphor.rect_to_rgb(rect, colors.on(), colors.off());
+                fb.update_region(region, &pixels).unwrap();
+            }
+        }
+        fb.present();
+        thread::sleep(Duration::from_millis(16))
+    }
+    // Closing the window stops the recording.
+    if let Some(recording) = recorder {
+        stop_recording(recording);
     }
 }
@@ -0,0 +1,174 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/* INFO:
+    A palette is the background and the foreground. The core draws a single
+    plane, so XO-CHIP's colors for the second plane have nothing to show and
+    are not accepted.
+
+    User palettes live one per line in `palettes` under the config
+    directory, replacing a built-in one of the same name:
+
+    ; name = background foreground
+    paper = #f0f0e8 #202020
+*/
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    pub name: String,
+    colors: Vec<[u8; 3]>,
+}
+
+impl Palette {
+    // Two colors, background first.
+    pub fn new(name: &str, colors: &[[u8; 3]]) -> Option<Palette> {
+        if colors.len() != 2 {
+            return None;
+        }
+        Some(Palette {
+            name: name.to_string(),
+            colors: colors.to_vec(),
+        })
+    }
+    pub fn off(&self) -> [u8; 3] {
+        self.colors[0]
+    }
+    pub fn on(&self) -> [u8; 3] {
+        self.colors[1]
+    }
+}
+
+fn color(text: &str) -> Option<[u8; 3]> {
+    let digits = text.trim_start_matches('#');
+    if digits.len() != 6 {
+        return None;
+    }
+    let value = u32::from_str_radix(digits, 16).ok()?;
+    Some([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+}
+
+pub fn builtin() -> Vec<Palette> {
+    let palettes: [(&str, &[&str]); 5] = [
+        ("green", &["#0a0a0a", "#0afa0a"]),
+        ("amber", &["#140c00", "#ffb000"]),
+        ("white", &["#000000", "#ffffff"]),
+        ("octo", &["#996600", "#ffcc00"]),
+        ("gameboy", &["#9bbc0f", "#0f380f"]),
+    ];
+    palettes
+        .iter()
+        .map(|&(name, colors)| {
+            let colors: Vec<[u8; 3]> = colors.iter().map(|hex| color(hex).unwrap()).collect();
+            Palette::new(name, &colors).unwrap()
+        })
+        .collect()
+}
+
+// Reports the first bad line by number.
+pub fn parse(text: &str) -> Result<Vec<Palette>, String> {
+    let mut palettes = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        let bad = || format!("line {}: {}", number + 1, line);
+        let mut parts = line.splitn(2, '=');
+        let name = parts.next().unwrap().trim();
+        let colors = parts.next().ok_or_else(bad)?;
+        let colors: Option<Vec<[u8; 3]>> = colors.split_whitespace().map(color).collect();
+        let palette = colors
+            .and_then(|colors| Palette::new(name, &colors))
+            .filter(|_| !name.is_empty())
+            .ok_or_else(bad)?;
+        palettes.push(palette);
+    }
+    Ok(palettes)
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("chip8-rs").join("palettes"))
+}
+
+// The built-in palettes with the user's from `path` merged in. A missing
+// file is not an error.
+pub fn load(path: &Path) -> Result<Vec<Palette>, String> {
+    let mut palettes = builtin();
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => return Ok(palettes),
+        Err(error) => return Err(error.to_string()),
+    };
+    for palette in parse(&text)? {
+        match palettes.iter().position(|known| known.name == palette.name) {
+            Some(index) => palettes[index] = palette,
+            None => palettes.push(palette),
+        }
+    }
+    Ok(palettes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_palettes_and_skips_comments() {
+        let palettes = parse("; mine\n\n  paper = #f0f0e8 #202020  \nink=000000 ffffff\n").unwrap();
+        assert_eq!(
+            palettes,
+            vec![
+                Palette::new("paper", &[[0xF0, 0xF0, 0xE8], [0x20, 0x20, 0x20]]).unwrap(),
+                Palette::new("ink", &[[0, 0, 0], [0xFF, 0xFF, 0xFF]]).unwrap(),
+            ]
+        );
+        assert_eq!(palettes[0].off(), [0xF0, 0xF0, 0xE8]);
+        assert_eq!(palettes[0].on(), [0x20, 0x20, 0x20]);
+    }
+
+    #[test]
+    fn reports_the_first_bad_line() {
+        let bad = |text: &str| parse(text).unwrap_err();
+        assert_eq!(bad("paper = #f0f0e8 #202020\npaper"), "line 2: paper");
+        assert_eq!(bad("= #000000 #ffffff"), "line 1: = #000000 #ffffff");
+        assert_eq!(bad("one = #000000"), "line 1: one = #000000");
+        assert_eq!(
+            bad("hex = #00000g #ffffff"),
+            "line 1: hex = #00000g #ffffff"
+        );
+        assert_eq!(bad("short = #000 #fff"), "line 1: short = #000 #fff");
+        assert_eq!(
+            bad("xo = #000000 #ffffff #aa4400 #ffaa00"),
+            "line 1: xo = #000000 #ffffff #aa4400 #ffaa00"
+        );
+    }
+
+    #[test]
+    fn user_palettes_replace_built_in_ones() {
+        let path = env::temp_dir().join(format!("chip8-rs-palettes-{}", std::process::id()));
+        fs::write(&path, "green = #000000 #00ff00\npaper = #f0f0e8 #202020\n").unwrap();
+        let palettes = load(&path);
+        fs::remove_file(&path).unwrap();
+        let palettes = palettes.unwrap();
+        assert_eq!(palettes.len(), builtin().len() + 1);
+        assert_eq!(palettes[0].name, "green");
+        assert_eq!(palettes[0].on(), [0, 0xFF, 0]);
+        assert_eq!(palettes.last().unwrap().name, "paper");
+
+        fs::write(&path, "broken").unwrap();
+        let error = load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(error, Err("line 1: broken".to_string()));
+    }
+
+    #[test]
+    fn a_missing_file_leaves_the_built_in_palettes() {
+        let path = env::temp_dir().join("chip8-rs-no-such-palettes");
+        assert_eq!(load(&path), Ok(builtin()));
+    }
+}
@@ -0,0 +1,222 @@
+use chip8_core::chip8::image::scale_rgb;
+use chip8_core::chip8::recording::Recording;
+use chip8_core::chip8::vram::PixelBuffer;
+use std::f32::consts::PI;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+/* INFO:
+    MP4s are made by a local ffmpeg. The frames are piped to it as they come
+    while the beeps are only noted; when the recording stops they become a
+    WAV, and a second ffmpeg run puts the two together.
+*/
+
+const SAMPLE_RATE: u32 = 44100;
+const SAMPLES_PER_TICK: u32 = SAMPLE_RATE / 60;
+// The client's beep.
+const TONE: f32 = 440.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Gif,
+    Mp4,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "gif" => Some(Format::Gif),
+            "mp4" => Some(Format::Mp4),
+            _ => None,
+        }
+    }
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            Format::Gif => "gif",
+            Format::Mp4 => "mp4",
+        }
+    }
+}
+
+pub struct Video {
+    ffmpeg: Child,
+    path: PathBuf,
+    scale: usize,
+    on: [u8; 3],
+    off: [u8; 3],
+    size: (usize, usize),
+    // Whether the beep sounded, one per tick.
+    beeps: Vec<bool>,
+}
+
+// `path` with `.extension` added, for the files made along the way.
+fn beside(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+fn ffmpeg(args: &[&str]) -> Command {
+    let mut command = Command::new("ffmpeg");
+    command.args(["-y", "-loglevel", "error"]).args(args);
+    command
+}
+
+impl Video {
+    // The size of the first frame is kept for the whole video.
+    pub fn start(
+        path: PathBuf,
+        first: &PixelBuffer,
+        scale: usize,
+        on: [u8; 3],
+        off: [u8; 3],
+    ) -> io::Result<Video> {
+        let size = (first.width * scale, first.height * scale);
+        let video = beside(&path, "video.mp4");
+        let ffmpeg = ffmpeg(&[
+            "-f",
+            "rawvideo",
+            "-pixel_format",
+            "rgb24",
+            "-video_size",
+            &format!("{}x{}", size.0, size.1),
+            "-framerate",
+            "60",
+            "-i",
+            "-",
+            "-c:v",
+            "libx264",
+            "-pix_fmt",
+            "yuv420p",
+            &video.to_string_lossy(),
+        ])
+        .stdin(Stdio::piped())
+        .spawn()?;
+        Ok(Video {
+            ffmpeg,
+            path,
+            scale,
+            on,
+            off,
+            size,
+            beeps: Vec::new(),
+        })
+    }
+    // The screen for this tick. Frames of another size than the first are
+    // replaced by black.
+    pub fn frame(&mut self, buffer: &PixelBuffer, beeping: bool) -> io::Result<()> {
+        let rgb = if (buffer.width * self.scale, buffer.height * self.scale) == self.size {
+            scale_rgb(&buffer.to_rgb(self.on, self.off), buffer.width, self.scale)
+        } else {
+            vec![0; self.size.0 * self.size.1 * 3]
+        };
+        self.beeps.push(beeping);
+        self.ffmpeg.stdin.as_mut().unwrap().write_all(&rgb)
+    }
+    pub fn finish(mut self) -> io::Result<PathBuf> {
+        drop(self.ffmpeg.stdin.take());
+        if !self.ffmpeg.wait()?.success() {
+            return Err(io::Error::other("ffmpeg could not encode the frames"));
+        }
+        let video = beside(&self.path, "video.mp4");
+        let audio = beside(&self.path, "wav");
+        write_wav(&audio, &self.beeps)?;
+        let status = ffmpeg(&[
+            "-i",
+            &video.to_string_lossy(),
+            "-i",
+            &audio.to_string_lossy(),
+            "-c:v",
+            "copy",
+            "-c:a",
+            "aac",
+            &self.path.to_string_lossy(),
+        ])
+        .status()?;
+        fs::remove_file(&video)?;
+        fs::remove_file(&audio)?;
+        if status.success() {
+            Ok(self.path)
+        } else {
+            Err(io::Error::other("ffmpeg could not add the sound"))
+        }
+    }
+}
+
+// 16-bit mono, the tone wherever the beep sounded and silence elsewhere.
+fn write_wav(path: &Path, beeps: &[bool]) -> io::Result<()> {
+    let samples = beeps.len() as u32 * SAMPLES_PER_TICK;
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + samples * 2).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    // PCM, one channel
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&SAMPLE_RATE.to_le_bytes())?;
+    out.write_all(&(SAMPLE_RATE * 2).to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&(samples * 2).to_le_bytes())?;
+    for (tick, &beeping) in beeps.iter().enumerate() {
+        for sample in 0..SAMPLES_PER_TICK {
+            let time = (tick as u32 * SAMPLES_PER_TICK + sample) as f32 / SAMPLE_RATE as f32;
+            let level = if beeping {
+                ((time * TONE * 2.0 * PI).sin() * 0x2000 as f32) as i16
+            } else {
+                0
+            };
+            out.write_all(&level.to_le_bytes())?;
+        }
+    }
+    out.flush()
+}
+
+pub enum Recorder {
+    Gif(Recording, PathBuf),
+    Mp4(Video),
+}
+
+impl Recorder {
+    pub fn start(
+        format: Format,
+        path: PathBuf,
+        first: &PixelBuffer,
+        scale: usize,
+        on: [u8; 3],
+        off: [u8; 3],
+    ) -> io::Result<Recorder> {
+        match format {
+            Format::Gif => Recording::new(scale, on, off)
+                .map(|recording| Recorder::Gif(recording, path))
+                .ok_or_else(|| io::Error::other("the scale is too large for a GIF")),
+            Format::Mp4 => Video::start(path, first, scale, on, off).map(Recorder::Mp4),
+        }
+    }
+    pub fn frame(&mut self, buffer: &PixelBuffer, beeping: bool) -> io::Result<()> {
+        match *self {
+            Recorder::Gif(ref mut recording, _) => {
+                recording.frame(buffer);
+                Ok(())
+            }
+            Recorder::Mp4(ref mut video) => video.frame(buffer, beeping),
+        }
+    }
+    pub fn finish(self) -> io::Result<PathBuf> {
+        match self {
+            Recorder::Gif(recording, path) => {
+                let gif = recording
+                    .finish()
+                    .ok_or_else(|| io::Error::other("no frames were recorded"))?;
+                fs::write(&path, gif)?;
+                Ok(path)
+            }
+            Recorder::Mp4(video) => video.finish(),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+#[macro_use]
+extern crate criterion;
+extern crate chip8_core;
+
+use chip8_core::chip8::audio::AudioSink;
+use chip8_core::chip8::keyboard::Keyboard;
+use chip8_core::chip8::vram::VideoSink;
+use chip8_core::chip8::Chip8;
+use chip8_core::program::Program;
+use criterion::Criterion;
+
+const CYCLES: usize = 10_000;
+const ROMS: [&str; 3] = [
+    "Particle Demo [zeroZshadow, 2008].ch8",
+    "Sierpinski [Sergey Naydenov, 2010].ch8",
+    "Maze [David Winter, 199x].ch8",
+];
+
+fn run(program: &Program, decode_cache: bool) {
+    let mut chip8 = Chip8::new();
+    chip8.set_decode_cache(decode_cache);
+    chip8.load_program(program);
+    let keyboard = Keyboard::new();
+    let mut video_sink = VideoSink::new();
+    let mut audio_sink = AudioSink::new();
+    for _ in 0..CYCLES {
+        chip8.step(&keyboard, &mut video_sink, &mut audio_sink);
+    }
+}
+
+fn interpreter(c: &mut Criterion) {
+    for rom in ROMS.iter() {
+        let path = format!("{}/../programs/{}", env!("CARGO_MANIFEST_DIR"), rom);
+        let program = Program::from_path(&path).unwrap();
+        let mut group = c.benchmark_group(*rom);
+        group.bench_function("uncached", |b| b.iter(|| run(&program, false)));
+        group.bench_function("cached", |b| b.iter(|| run(&program, true)));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, interpreter);
+criterion_main!(benches);
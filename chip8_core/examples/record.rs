@@ -0,0 +1,85 @@
+extern crate chip8_core;
+
+use chip8_core::chip8::audio::AudioSink;
+use chip8_core::chip8::keyboard::Keyboard;
+use chip8_core::chip8::recording::{Recording, MAX_SCALE};
+use chip8_core::chip8::vram::VideoSink;
+use chip8_core::chip8::Chip8;
+use chip8_core::database::RomDatabase;
+use chip8_core::program::Program;
+use std::env;
+use std::fs;
+
+const DEFAULT_CYCLES_PER_FRAME: usize = 10;
+const ON: [u8; 3] = [0x33, 0xFF, 0x66];
+const OFF: [u8; 3] = [0x00, 0x11, 0x00];
+
+// Runs a ROM without a window, nobody pressing keys, and records it as a GIF:
+//
+//     cargo run -p chip8_core --example record -- [--frames N] [--scale N] ROM GIF
+fn main() {
+    let mut frames = 600;
+    let mut scale = 4;
+    let mut files = Vec::new();
+    let mut usage = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--frames" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => frames = value,
+                None => usage = true,
+            },
+            "--scale" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) if value > 0 && value <= MAX_SCALE => scale = value,
+                _ => usage = true,
+            },
+            _ => files.push(arg),
+        }
+    }
+    if usage || files.len() != 2 {
+        eprintln!("record [--frames N] [--scale N] ROM GIF");
+        return;
+    }
+    let program = match Program::from_path(&files[0]) {
+        Ok(program) => program,
+        Err(error) => {
+            eprintln!("Cannot load {}: {:?}", files[0], error);
+            return;
+        }
+    };
+    let mut chip8 = Chip8::new();
+    let mut cycles_per_frame = DEFAULT_CYCLES_PER_FRAME;
+    if let Some(info) = RomDatabase::bundled().lookup(&program) {
+        if let Some(quirks) = info.quirks {
+            chip8.set_quirks(quirks);
+        }
+        cycles_per_frame = info.cycles_per_frame().unwrap_or(cycles_per_frame);
+    }
+    chip8.load_program(&program);
+    let keyboard = Keyboard::new();
+    let mut video_sink = VideoSink::new();
+    let mut audio_sink = AudioSink::new();
+    let mut recording = Recording::new(scale, ON, OFF).unwrap();
+    let mut screen = chip8.pixel_buffer();
+    for _ in 0..frames {
+        chip8.run(
+            cycles_per_frame,
+            &keyboard,
+            &mut video_sink,
+            &mut audio_sink,
+        );
+        chip8.tick_timers(&mut audio_sink);
+        if let Some(buffer) = video_sink.get() {
+            screen = buffer;
+        }
+        recording.frame(&screen);
+    }
+    match recording.finish() {
+        Some(gif) => {
+            if let Err(error) = fs::write(&files[1], gif) {
+                eprintln!("Cannot write {}: {}", files[1], error);
+            }
+        }
+        None => eprintln!("Nothing was recorded"),
+    }
+}
@@ -0,0 +1,27 @@
+#![no_main]
+
+use chip8_core::chip8::audio::AudioSink;
+use chip8_core::chip8::keyboard::Keyboard;
+use chip8_core::chip8::random::XorShift;
+use chip8_core::chip8::vram::VideoSink;
+use chip8_core::chip8::Chip8;
+use libfuzzer_sys::fuzz_target;
+
+const CYCLES: usize = 1000;
+
+// Snapshots come from disk or over FFI, so restoring any byte string must
+// either fail cleanly or leave a machine that can keep running.
+fuzz_target!(|data: &[u8]| {
+    let mut chip8 = Chip8::new();
+    chip8.set_random_source(Box::new(XorShift::new(0)));
+    if chip8.load_state(data).is_ok() {
+        chip8.run(
+            CYCLES,
+            &Keyboard::new(),
+            &mut VideoSink::new(),
+            &mut AudioSink::new(),
+        );
+        let state = chip8.save_state();
+        assert_eq!(chip8.load_state(&state), Ok(()));
+    }
+});
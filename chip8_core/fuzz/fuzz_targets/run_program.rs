@@ -0,0 +1,48 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use chip8_core::chip8::audio::AudioSink;
+use chip8_core::chip8::keyboard::{HexKey, Keyboard};
+use chip8_core::chip8::random::XorShift;
+use chip8_core::chip8::vram::VideoSink;
+use chip8_core::chip8::{Backend, Chip8};
+use chip8_core::program::Program;
+use libfuzzer_sys::fuzz_target;
+
+const CYCLES_PER_FRAME: usize = 10;
+const MAX_FRAMES: usize = 1000;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    rom: Vec<u8>,
+    // One bitmask of held keys per frame.
+    keys: Vec<u16>,
+    // Run the threaded backend checked against the interpreter.
+    lockstep: bool,
+}
+
+fuzz_target!(|input: Input| {
+    let backend = if input.lockstep {
+        Backend::Lockstep
+    } else {
+        Backend::Interpreter
+    };
+    let mut chip8 = Chip8::with_backend(backend);
+    chip8.set_random_source(Box::new(XorShift::new(0)));
+    chip8.load_program(&Program::from(&input.rom[..]));
+    let mut keyboard = Keyboard::new();
+    let mut video_sink = VideoSink::new();
+    let mut audio_sink = AudioSink::new();
+    for keys in input.keys.iter().take(MAX_FRAMES) {
+        for key in 0..0x10 {
+            keyboard.update_key(HexKey::from(key), keys & (1 << key) != 0);
+        }
+        chip8.run(
+            CYCLES_PER_FRAME,
+            &keyboard,
+            &mut video_sink,
+            &mut audio_sink,
+        );
+        chip8.tick_timers(&mut audio_sink);
+    }
+});
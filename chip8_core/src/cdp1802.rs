@@ -0,0 +1,264 @@
+/* INFO:
+    The RCA CDP1802, the CPU of the COSMAC VIP.
+
+    Sixteen 16-bit registers R0-RF, any of which can be the program counter
+    (picked by P) or the data pointer (picked by X). D is the accumulator and
+    DF its carry. Every instruction takes two machine cycles of eight clocks,
+    except the long branches and skips (Cx) which take three.
+
+    Memory, the I/O lines (OUT 1-7, INP 1-7) and the EF1-EF4 flags are on
+    the other side of `Bus`.
+*/
+
+pub trait Bus {
+    fn read(&mut self, address: u16) -> u8;
+    fn write(&mut self, address: u16, value: u8);
+    fn output(&mut self, _port: u8, _value: u8) {}
+    fn input(&mut self, _port: u8) -> u8 {
+        0
+    }
+    // EF1 to EF4, `flag` is 1 to 4.
+    fn ef(&mut self, _flag: u8) -> bool {
+        false
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cdp1802 {
+    pub r: [u16; 0x10],
+    pub p: u8,
+    pub x: u8,
+    pub d: u8,
+    pub df: bool,
+    pub t: u8,
+    pub ie: bool,
+    pub q: bool,
+    // Set by IDL until an interrupt or DMA request wakes the CPU up.
+    pub idle: bool,
+}
+
+impl Cdp1802 {
+    // The state after a reset: P, X and R0 are 0 and interrupts are enabled.
+    pub fn new() -> Cdp1802 {
+        Cdp1802 {
+            ie: true,
+            ..Cdp1802::default()
+        }
+    }
+    pub fn pc(&self) -> u16 {
+        self.r[self.p as usize]
+    }
+    // Taken only when interrupts are enabled: T saves X and P, and the CPU
+    // carries on with P = 1 and X = 2. The response takes one machine cycle.
+    pub fn interrupt(&mut self) -> bool {
+        if !self.ie {
+            return false;
+        }
+        self.t = self.x << 4 | self.p;
+        self.p = 1;
+        self.x = 2;
+        self.ie = false;
+        self.idle = false;
+        true
+    }
+    // A DMA out cycle: the byte at R0 goes to the device and R0 moves on.
+    pub fn dma_out<B: Bus>(&mut self, bus: &mut B) -> u8 {
+        let value = bus.read(self.r[0]);
+        self.r[0] = self.r[0].wrapping_add(1);
+        self.idle = false;
+        value
+    }
+    // Runs one instruction and returns the machine cycles it took.
+    pub fn step<B: Bus>(&mut self, bus: &mut B) -> u32 {
+        if self.idle {
+            return 1;
+        }
+        let opcode = self.fetch(bus);
+        let (i, n) = (opcode >> 4, (opcode & 0xF) as usize);
+        let x = self.x as usize;
+        match i {
+            0x0 if n == 0 => self.idle = true,
+            0x0 => self.d = bus.read(self.r[n]),
+            0x1 => self.r[n] = self.r[n].wrapping_add(1),
+            0x2 => self.r[n] = self.r[n].wrapping_sub(1),
+            0x3 => {
+                let taken = self.condition(n, bus);
+                self.short_branch(taken, bus);
+            }
+            0x4 => {
+                self.d = bus.read(self.r[n]);
+                self.r[n] = self.r[n].wrapping_add(1);
+            }
+            0x5 => bus.write(self.r[n], self.d),
+            0x6 => match n {
+                0x0 => self.r[x] = self.r[x].wrapping_add(1),
+                // 68 is only an instruction on the 1804 and later.
+                0x8 => {}
+                0x1..=0x7 => {
+                    let value = bus.read(self.r[x]);
+                    bus.output(n as u8, value);
+                    self.r[x] = self.r[x].wrapping_add(1);
+                }
+                _ => {
+                    let value = bus.input(n as u8 - 8);
+                    bus.write(self.r[x], value);
+                    self.d = value;
+                }
+            },
+            0x7 => self.execute_7(n, bus),
+            0x8 => self.d = self.r[n] as u8,
+            0x9 => self.d = (self.r[n] >> 8) as u8,
+            0xA => self.r[n] = self.r[n] & 0xFF00 | self.d as u16,
+            0xB => self.r[n] = self.r[n] & 0x00FF | (self.d as u16) << 8,
+            0xC => {
+                self.long_branch(n, bus);
+                return 3;
+            }
+            0xD => self.p = n as u8,
+            0xE => self.x = n as u8,
+            _ => {
+                // F8-FF take their operand from the program instead of M(RX)
+                let operand = match n {
+                    0x6 | 0xE => 0,
+                    0x0..=0x7 => bus.read(self.r[x]),
+                    _ => self.fetch(bus),
+                };
+                match n & 0x7 {
+                    0x0 => self.d = operand,
+                    0x1 => self.d |= operand,
+                    0x2 => self.d &= operand,
+                    0x3 => self.d ^= operand,
+                    0x4 => self.add(operand, self.d, false),
+                    0x5 => self.add(operand, !self.d, true),
+                    0x6 if n == 0x6 => {
+                        self.df = self.d & 1 == 1;
+                        self.d >>= 1;
+                    }
+                    0x6 => {
+                        self.df = self.d & 0x80 != 0;
+                        self.d <<= 1;
+                    }
+                    _ => self.add(self.d, !operand, true),
+                }
+            }
+        }
+        2
+    }
+    fn execute_7<B: Bus>(&mut self, n: usize, bus: &mut B) {
+        let x = self.x as usize;
+        match n {
+            0x0 | 0x1 => {
+                let xp = bus.read(self.r[x]);
+                self.r[x] = self.r[x].wrapping_add(1);
+                self.x = xp >> 4;
+                self.p = xp & 0xF;
+                self.ie = n == 0x0;
+            }
+            0x2 => {
+                self.d = bus.read(self.r[x]);
+                self.r[x] = self.r[x].wrapping_add(1);
+            }
+            0x3 => {
+                bus.write(self.r[x], self.d);
+                self.r[x] = self.r[x].wrapping_sub(1);
+            }
+            0x6 => {
+                let carry = self.df;
+                self.df = self.d & 1 == 1;
+                self.d = self.d >> 1 | (carry as u8) << 7;
+            }
+            0xE => {
+                let carry = self.df;
+                self.df = self.d & 0x80 != 0;
+                self.d = self.d << 1 | carry as u8;
+            }
+            0x8 => bus.write(self.r[x], self.t),
+            0x9 => {
+                self.t = self.x << 4 | self.p;
+                bus.write(self.r[2], self.t);
+                self.x = self.p;
+                self.r[2] = self.r[2].wrapping_sub(1);
+            }
+            0xA => self.q = false,
+            0xB => self.q = true,
+            _ => {
+                // 74, 75 and 77 with M(RX), 7C, 7D and 7F with an immediate
+                let operand = if n & 0x8 == 0 {
+                    bus.read(self.r[x])
+                } else {
+                    self.fetch(bus)
+                };
+                let carry = self.df;
+                match n & 0x7 {
+                    0x4 => self.add(operand, self.d, carry),
+                    0x5 => self.add(operand, !self.d, carry),
+                    _ => self.add(self.d, !operand, carry),
+                }
+            }
+        }
+    }
+    fn fetch<B: Bus>(&mut self, bus: &mut B) -> u8 {
+        let p = self.p as usize;
+        let value = bus.read(self.r[p]);
+        self.r[p] = self.r[p].wrapping_add(1);
+        value
+    }
+    // Subtraction is addition of the complement, leaving DF set when there
+    // was no borrow.
+    fn add(&mut self, a: u8, b: u8, carry: bool) {
+        let sum = a as u16 + b as u16 + carry as u16;
+        self.d = sum as u8;
+        self.df = sum > 0xFF;
+    }
+    // For the short branches 30-3F and the long branches C0-C3 and C8-CB,
+    // where the second half tests the opposite of the first.
+    fn condition<B: Bus>(&mut self, n: usize, bus: &mut B) -> bool {
+        let taken = match n & 0x7 {
+            0x0 => true,
+            0x1 => self.q,
+            0x2 => self.d == 0,
+            0x3 => self.df,
+            flag => bus.ef(flag as u8 - 3),
+        };
+        taken != (n & 0x8 != 0)
+    }
+    fn short_branch<B: Bus>(&mut self, taken: bool, bus: &mut B) {
+        let p = self.p as usize;
+        if taken {
+            let low = bus.read(self.r[p]);
+            self.r[p] = self.r[p] & 0xFF00 | low as u16;
+        } else {
+            self.r[p] = self.r[p].wrapping_add(1);
+        }
+    }
+    // C0-C3 and C8-CB branch, C5-C7 and CC-CF skip, C4 is NOP.
+    fn long_branch<B: Bus>(&mut self, n: usize, bus: &mut B) {
+        let p = self.p as usize;
+        match n {
+            0x4 => {}
+            0x0..=0x3 | 0x8..=0xB => {
+                if self.condition(n, bus) {
+                    let high = bus.read(self.r[p]);
+                    let low = bus.read(self.r[p].wrapping_add(1));
+                    self.r[p] = (high as u16) << 8 | low as u16;
+                } else {
+                    self.r[p] = self.r[p].wrapping_add(2);
+                }
+            }
+            _ => {
+                let skip = match n {
+                    0x5 => !self.q,
+                    0x6 => self.d != 0,
+                    0x7 => !self.df,
+                    0xC => self.ie,
+                    0xD => self.q,
+                    0xE => self.d == 0,
+                    _ => self.df,
+                };
+                if skip {
+                    self.r[p] = self.r[p].wrapping_add(2);
+                }
+            }
+        }
+    }
+}
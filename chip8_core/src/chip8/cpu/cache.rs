@@ -0,0 +1,45 @@
+use super::instruction::OpCode;
+use alloc::vec::Vec;
+use chip8::memory::MEMORY_SIZE;
+use chip8::Address;
+
+#[derive(Clone)]
+pub struct DecodeCache {
+    entries: Vec<Option<OpCode>>,
+    enabled: bool,
+}
+
+impl DecodeCache {
+    pub fn new() -> DecodeCache {
+        DecodeCache {
+            entries: vec![None; MEMORY_SIZE],
+            enabled: true,
+        }
+    }
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.clear();
+    }
+    pub fn get(&self, address: Address) -> Option<OpCode> {
+        if !self.enabled {
+            return None;
+        }
+        self.entries[address as usize % MEMORY_SIZE]
+    }
+    pub fn insert(&mut self, address: Address, opcode: OpCode) {
+        if self.enabled {
+            self.entries[address as usize % MEMORY_SIZE] = Some(opcode);
+        }
+    }
+    pub fn invalidate(&mut self, address: Address) {
+        // An instruction is two bytes wide, so the one starting a byte earlier
+        // also covers the written address.
+        self.entries[address as usize % MEMORY_SIZE] = None;
+        self.entries[address.wrapping_sub(1) as usize % MEMORY_SIZE] = None;
+    }
+    pub fn clear(&mut self) {
+        for entry in self.entries.iter_mut() {
+            *entry = None;
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use super::instruction::Instruction;
+use super::Cpu;
+use chip8::memory::Memory;
+use chip8::{Address, Register};
+
+/* INFO:
+    CHIP-8E adds these to CHIP-8:
+
+    00ED  stop
+    00F2  no operation
+    0151  wait until the delay timer reaches 0
+    0188  skip the next instruction
+    5xy1  skip if Vx > Vy
+    5xy2  store Vx to Vy at I, leaving I past them
+    5xy3  load Vx to Vy from I, leaving I past them
+    BBnn  jump back nn bytes from the next instruction
+    BFnn  jump forward nn bytes from the next instruction
+    Fx03  send Vx to port 3
+    Fx1B  skip Vx bytes
+    Fx4F  set the delay timer to Vx and wait until it reaches 0
+    FxE3  wait for the port 3 strobe and read port 3 into Vx
+    FxE7  read port 3 into Vx
+
+    Nothing is connected to port 3: output is dropped and input reads 0.
+*/
+
+#[derive(Clone, Copy, Debug)]
+pub enum Chip8EOp {
+    Stop(),
+    Nop(),
+    WaitDelay(),
+    Skip(),
+    JgtVy(Register, Register),
+    StoreRange(Register, Register),
+    LoadRange(Register, Register),
+    JmpBack(u8),
+    JmpForward(u8),
+    Output(),
+    SkipBytes(Register),
+    Delay(Register),
+    Input(Register),
+}
+
+pub fn decode(instruction: &Instruction) -> Option<Chip8EOp> {
+    let (vx, vy) = (instruction.get_vx(), instruction.get_vy());
+    let op = match instruction.value() >> 12 {
+        0x0 => match instruction.get_address() {
+            0x0ED => Chip8EOp::Stop(),
+            0x0F2 => Chip8EOp::Nop(),
+            0x151 => Chip8EOp::WaitDelay(),
+            0x188 => Chip8EOp::Skip(),
+            _ => return None,
+        },
+        0x5 => match instruction.get_4bconst() {
+            0x1 => Chip8EOp::JgtVy(vx, vy),
+            0x2 => Chip8EOp::StoreRange(vx, vy),
+            0x3 => Chip8EOp::LoadRange(vx, vy),
+            _ => return None,
+        },
+        0xB => match vx {
+            0xB => Chip8EOp::JmpBack(instruction.get_8bconst()),
+            0xF => Chip8EOp::JmpForward(instruction.get_8bconst()),
+            _ => return None,
+        },
+        0xF => match instruction.get_8bconst() {
+            0x03 => Chip8EOp::Output(),
+            0x1B => Chip8EOp::SkipBytes(vx),
+            0x4F => Chip8EOp::Delay(vx),
+            0xE3 | 0xE7 => Chip8EOp::Input(vx),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(op)
+}
+
+impl Cpu {
+    pub fn execute_chip8e(&mut self, op: Chip8EOp, memory: &mut Memory) {
+        let next = self.pc.wrapping_add(2);
+        match op {
+            Chip8EOp::Stop() => {}
+            Chip8EOp::Nop() | Chip8EOp::Output() => self.pc = next,
+            Chip8EOp::WaitDelay() => {
+                if self.delay_timer == 0 {
+                    self.pc = next;
+                }
+            }
+            Chip8EOp::Skip() => self.pc = next.wrapping_add(2),
+            Chip8EOp::JgtVy(vx, vy) => {
+                self.pc = next;
+                if self.v[vx as usize] > self.v[vy as usize] {
+                    self.pc += 2;
+                }
+            }
+            Chip8EOp::StoreRange(vx, vy) => {
+                for (offset, r) in range(vx, vy).enumerate() {
+                    let addr = self.i.wrapping_add(offset as Address);
+                    self.write(memory, addr, self.v[r as usize]);
+                }
+                self.i = self.i.wrapping_add(range(vx, vy).count() as Address);
+                self.pc = next;
+            }
+            Chip8EOp::LoadRange(vx, vy) => {
+                for (offset, r) in range(vx, vy).enumerate() {
+                    self.v[r as usize] = memory.read(self.i.wrapping_add(offset as Address));
+                }
+                self.i = self.i.wrapping_add(range(vx, vy).count() as Address);
+                self.pc = next;
+            }
+            Chip8EOp::JmpBack(bytes) => self.pc = next.wrapping_sub(bytes as Address),
+            Chip8EOp::JmpForward(bytes) => self.pc = next.wrapping_add(bytes as Address),
+            Chip8EOp::SkipBytes(vx) => self.pc = next.wrapping_add(self.v[vx as usize] as Address),
+            // The delay timer ticks down between executions of Fx4F, so
+            // `delaying` remembers that it has already been set.
+            Chip8EOp::Delay(vx) => {
+                if !self.delaying {
+                    self.delay_timer = self.v[vx as usize];
+                    self.delaying = true;
+                }
+                if self.delay_timer == 0 {
+                    self.delaying = false;
+                    self.pc = next;
+                }
+            }
+            Chip8EOp::Input(vx) => {
+                self.v[vx as usize] = 0;
+                self.pc = next;
+            }
+        }
+    }
+}
+
+// Vx to Vy, counting down when y < x.
+fn range(vx: Register, vy: Register) -> impl Iterator<Item = Register> {
+    let (low, high) = if vx <= vy { (vx, vy) } else { (vy, vx) };
+    let reversed = vx > vy;
+    (low..=high).map(move |r| if reversed { high + low - r } else { r })
+}
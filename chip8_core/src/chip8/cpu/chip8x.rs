@@ -0,0 +1,98 @@
+use super::instruction::Instruction;
+use super::Cpu;
+use chip8::vram::{Color, VideoSink, Vram, ZONE_WIDTH};
+use chip8::Register;
+
+/* INFO:
+    CHIP-8X adds these to CHIP-8, taking over all of Bnnn:
+
+    02A0  step the background color
+    5xy1  add Vy to Vx nibble by nibble, each nibble modulo 8
+    Bxy0  color zones with Vy; the low nibbles of Vx and Vx+1 pick the left
+          zone and the top block of 4 rows, the high nibbles how many more
+    BxyN  color N rows from row Vy of the zone under column Vx with Vx+1
+    ExF2  skip if the key in Vx is down on the second keypad
+    ExF5  skip if it is up
+    FxF8  send Vx to the tone generator
+    FxFB  wait for the input port and read it into Vx
+
+    The second keypad, tone generator and input port are not emulated: the
+    keypad reads as released, tones are dropped and the port reads 0.
+*/
+
+#[derive(Clone, Copy, Debug)]
+pub enum Chip8XOp {
+    CycleBackground(),
+    AddNibbles(Register, Register),
+    ColorBlocks(Register, Register),
+    ColorRows(Register, Register, u8),
+    // Nothing is wired to these, so their operands are not kept.
+    JmpK2(),
+    JmpNK2(),
+    Tone(),
+    Input(Register),
+}
+
+pub fn decode(instruction: &Instruction) -> Option<Chip8XOp> {
+    let (vx, vy) = (instruction.get_vx(), instruction.get_vy());
+    let op = match instruction.value() >> 12 {
+        0x0 if instruction.get_address() == 0x2A0 => Chip8XOp::CycleBackground(),
+        0x5 if instruction.get_4bconst() == 0x1 => Chip8XOp::AddNibbles(vx, vy),
+        0xB => match instruction.get_4bconst() {
+            0 => Chip8XOp::ColorBlocks(vx, vy),
+            rows => Chip8XOp::ColorRows(vx, vy, rows),
+        },
+        0xE => match instruction.get_8bconst() {
+            0xF2 => Chip8XOp::JmpK2(),
+            0xF5 => Chip8XOp::JmpNK2(),
+            _ => return None,
+        },
+        0xF => match instruction.get_8bconst() {
+            0xF8 => Chip8XOp::Tone(),
+            0xFB => Chip8XOp::Input(vx),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(op)
+}
+
+impl Cpu {
+    pub fn execute_chip8x(&mut self, op: Chip8XOp, vram: &mut Vram, video_sink: &mut VideoSink) {
+        match op {
+            Chip8XOp::CycleBackground() => vram.cycle_background(video_sink),
+            Chip8XOp::AddNibbles(vx, vy) => {
+                let (x, y) = (self.v[vx as usize], self.v[vy as usize]);
+                let high = ((x >> 4) + (y >> 4)) & 0x7;
+                let low = ((x & 0xF) + (y & 0xF)) & 0x7;
+                self.v[vx as usize] = high << 4 | low;
+            }
+            Chip8XOp::ColorBlocks(vx, vy) => {
+                let across = self.v[vx as usize];
+                let down = self.v[(vx as usize + 1) & 0xF];
+                vram.color_zones(
+                    (across & 0xF) as usize,
+                    (down & 0xF) as usize * 4,
+                    (across >> 4) as usize + 1,
+                    ((down >> 4) as usize + 1) * 4,
+                    Color::from_bits(self.v[vy as usize]),
+                    video_sink,
+                );
+            }
+            Chip8XOp::ColorRows(vx, vy, rows) => {
+                vram.color_zones(
+                    self.v[vx as usize] as usize / ZONE_WIDTH,
+                    self.v[vy as usize] as usize,
+                    1,
+                    rows as usize,
+                    Color::from_bits(self.v[(vx as usize + 1) & 0xF]),
+                    video_sink,
+                );
+            }
+            Chip8XOp::JmpK2() | Chip8XOp::Tone() => {}
+            Chip8XOp::JmpNK2() => self.pc += 2,
+            Chip8XOp::Input(vx) => self.v[vx as usize] = 0,
+        }
+        self.pc += 2;
+    }
+}
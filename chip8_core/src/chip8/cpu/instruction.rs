@@ -1,12 +1,16 @@
-use chip8::{Address, Register};
-use std::fmt;
+use super::chip8e::{self, Chip8EOp};
+use super::chip8x::{self, Chip8XOp};
+use chip8::{Address, DWord, Register};
+use core::fmt;
+use program::Variant;
 
 macro_rules! no_opcode {
     ($x: expr) => {
-        panic!(format!("No OpCode for 0x{:04X} yet!", $x));
+        OpCode::Unknown($x)
     };
 }
 
+#[derive(Clone, Copy)]
 pub enum OpCode {
     Set(Register, u8),
     Copy(Register, Register),
@@ -39,9 +43,14 @@ pub enum OpCode {
     DrawSprite(Register, Register, u8),
     Font(Register),
     ClearScreen(),
+    HiresClear(),
+    MachineCall(Address),
     BCD(Register),
     Random(Register, u8),
     WaitForKey(Register),
+    Chip8X(Chip8XOp),
+    Chip8E(Chip8EOp),
+    Unknown(DWord),
 }
 
 impl fmt::Debug for OpCode {
@@ -79,10 +88,15 @@ impl fmt::Debug for OpCode {
             }
             OpCode::Font(vx) => write!(f, "Font({:1x})", vx),
             OpCode::ClearScreen() => write!(f, "ClearScreen()"),
+            OpCode::HiresClear() => write!(f, "HiresClear()"),
+            OpCode::MachineCall(address) => write!(f, "MachineCall({:3x})", address),
             OpCode::AddIVx(vx) => write!(f, "AddIVx({:1x})", vx),
             OpCode::BCD(vx) => write!(f, "BCD({:1x})", vx),
             OpCode::Random(vx, value) => write!(f, "Random({:1x},{:2x})", vx, value),
             OpCode::WaitForKey(vx) => write!(f, "WaitForKey({:1x})", vx),
+            OpCode::Chip8X(op) => write!(f, "Chip8X::{:?}", op),
+            OpCode::Chip8E(op) => write!(f, "Chip8E::{:?}", op),
+            OpCode::Unknown(value) => write!(f, "Unknown({:04x})", value),
             // _ => write!(f, ""),
         }
     }
@@ -96,12 +110,23 @@ impl Instruction {
     pub fn new(value: u16) -> Instruction {
         Instruction { value }
     }
-    pub fn decode(&self) -> OpCode {
+    // The variant's own instructions take precedence over the CHIP-8 ones
+    // sharing their encoding.
+    pub fn decode(&self, variant: Variant) -> OpCode {
+        let extension = match variant {
+            Variant::Chip8X => chip8x::decode(self).map(OpCode::Chip8X),
+            Variant::Chip8E => chip8e::decode(self).map(OpCode::Chip8E),
+            _ => None,
+        };
+        extension.unwrap_or_else(|| self.decode_chip8())
+    }
+    fn decode_chip8(&self) -> OpCode {
         match (self.value & 0xF000) >> 12 {
             0x0 => match self.value & 0x0FFF {
                 0x0E0 => OpCode::ClearScreen(),
                 0x0EE => OpCode::Return(),
-                _ => no_opcode!(self.value),
+                0x230 => OpCode::HiresClear(),
+                address => OpCode::MachineCall(address),
             },
             0x1 => OpCode::Jmp(self.get_address()),
             0x2 => OpCode::Call(self.get_address()),
@@ -147,6 +172,9 @@ impl Instruction {
             _ => no_opcode!(self.value),
         }
     }
+    pub fn value(&self) -> DWord {
+        self.value
+    }
     pub fn get_address(&self) -> Address {
         self.value & 0x0FFF
     }
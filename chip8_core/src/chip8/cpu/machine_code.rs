@@ -0,0 +1,132 @@
+use super::cache::DecodeCache;
+use super::threaded::BlockCache;
+use super::Cpu;
+use cdp1802::{Bus, Cdp1802};
+use chip8::keyboard::{HexKey, Keyboard};
+use chip8::memory::Memory;
+use chip8::vram::{VideoSink, Vram, HEIGHT};
+use chip8::Address;
+use program::DEFAULT_LOAD_ADDRESS;
+
+/* INFO:
+    On the VIP, 0nnn hands the 1802 to the machine code at nnn, which returns
+    to the interpreter with D4 (SEP R4). Routines expect the interpreter's
+    registers and memory layout:
+
+    R2  the 1802 stack, growing down from 0xECF
+    R5  the CHIP-8 PC, already past the 0nnn
+    R6  VX, where X is the second nibble of the 0nnn
+    R7  VY, where Y is the third nibble
+    R8  the delay timer in the high byte, the sound timer in the low byte
+    RA  I
+    RB  the display page at 0xF00
+
+    V0-VF live at 0xEF0-0xEFF and the 64x32 screen at 0xF00-0xFFF, so they are
+    copied there for the call and back out after it. The interpreter itself
+    sits below 0x200, which we don't have, so calls into it are skipped.
+*/
+const STACK: Address = 0xECF;
+const REGISTERS: Address = 0xEF0;
+const DISPLAY: Address = 0xF00;
+// About a second of VIP time. A routine that has not returned by then is
+// abandoned and the program carries on after the 0nnn.
+const MAX_CYCLES: u32 = 220_000;
+
+struct VipBus<'a> {
+    memory: &'a mut Memory,
+    cache: &'a mut DecodeCache,
+    blocks: &'a mut BlockCache,
+    keyboard: &'a Keyboard,
+    key: u8,
+}
+
+impl<'a> Bus for VipBus<'a> {
+    fn read(&mut self, address: u16) -> u8 {
+        self.memory.read(address)
+    }
+    fn write(&mut self, address: u16, value: u8) {
+        self.memory.write(address, value);
+        self.cache.invalidate(address);
+        self.blocks.invalidate(address);
+    }
+    // OUT 2 latches the key that EF3 reports on.
+    fn output(&mut self, port: u8, value: u8) {
+        if port == 2 {
+            self.key = value;
+        }
+    }
+    fn ef(&mut self, flag: u8) -> bool {
+        flag == 3 && self.keyboard.get_pressed(HexKey::from(self.key))
+    }
+}
+
+impl Cpu {
+    pub fn call_machine_code(
+        &mut self,
+        address: Address,
+        memory: &mut Memory,
+        vram: &mut Vram,
+        keyboard: &Keyboard,
+        video_sink: &mut VideoSink,
+    ) {
+        let next = self.pc.wrapping_add(2);
+        if address < DEFAULT_LOAD_ADDRESS {
+            self.pc = next;
+            return;
+        }
+        let lores = vram.height() == HEIGHT;
+        let screen = vram.to_bytes();
+        let v = self.v;
+        for (offset, &value) in v.iter().enumerate() {
+            self.write_changed(memory, REGISTERS + offset as Address, value);
+        }
+        if lores {
+            for (offset, &value) in screen.iter().enumerate() {
+                self.write_changed(memory, DISPLAY + offset as Address, value);
+            }
+        }
+
+        let opcode = memory.read_dword(self.pc);
+        let mut cdp1802 = Cdp1802::new();
+        cdp1802.p = 3;
+        cdp1802.x = 2;
+        cdp1802.r[0x2] = STACK;
+        cdp1802.r[0x3] = address;
+        cdp1802.r[0x5] = next;
+        cdp1802.r[0x6] = REGISTERS + (opcode >> 8 & 0xF);
+        cdp1802.r[0x7] = REGISTERS + (opcode >> 4 & 0xF);
+        cdp1802.r[0x8] = (self.delay_timer as u16) << 8 | self.sound_timer as u16;
+        cdp1802.r[0xA] = self.i;
+        cdp1802.r[0xB] = DISPLAY;
+        let mut bus = VipBus {
+            memory,
+            cache: &mut self.cache,
+            blocks: &mut self.blocks,
+            keyboard,
+            key: 0,
+        };
+        let mut cycles = 0;
+        while cdp1802.p != 4 && cycles < MAX_CYCLES {
+            cycles += cdp1802.step(&mut bus);
+        }
+
+        for (offset, value) in self.v.iter_mut().enumerate() {
+            *value = memory.read(REGISTERS + offset as Address);
+        }
+        self.i = cdp1802.r[0xA];
+        self.delay_timer = (cdp1802.r[0x8] >> 8) as u8;
+        self.sound_timer = cdp1802.r[0x8] as u8;
+        self.pc = if cdp1802.p == 4 { cdp1802.r[0x5] } else { next };
+        if lores {
+            let display = DISPLAY as usize;
+            let page = &memory.as_slice()[display..display + screen.len()];
+            vram.load_bytes(page);
+            vram.flush(video_sink);
+        }
+    }
+    fn write_changed(&mut self, memory: &mut Memory, address: Address, value: u8) {
+        if memory.read(address) != value {
+            self.write(memory, address, value);
+        }
+    }
+}
@@ -1,15 +1,40 @@
+mod cache;
+mod chip8e;
+mod chip8x;
 mod instruction;
+mod machine_code;
+mod threaded;
+use self::cache::DecodeCache;
 use self::instruction::{Instruction, OpCode};
+use self::threaded::BlockCache;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
 use chip8::audio::{AudioEvent, AudioSink};
 use chip8::keyboard::{HexKey, Keyboard};
-use chip8::memory::Memory;
+use chip8::memory::{Memory, MEMORY_SIZE};
+use chip8::quirks::Quirks;
+use chip8::random::RandomSource;
 use chip8::stack::Stack;
-use chip8::vram::{VideoSink, Vram};
+use chip8::state::{write_u16, Reader, StateError};
+use chip8::vram::{VideoSink, Vram, HIRES_HEIGHT};
 use chip8::Address;
+use chip8::Register;
+use chip8::Registers;
 use chip8::DWord;
-use rand::{thread_rng, Rng};
-use std::fmt;
+use chip8::MachineCode;
+use core::fmt;
+use program::{Variant, DEFAULT_LOAD_ADDRESS};
 
+/* INFO:
+    ROMs for the COSMAC VIP two-page hi-res interpreter start with `1260`.
+    On the VIP the code at 0x260 switches the display to 64x64 and carries on
+    at 0x2C0, and `0230` calls the routine that clears the 64x64 screen.
+    Like other emulators we catch the jump instead of running that code.
+*/
+const HIRES_ENTRY: Address = 0x260;
+const HIRES_START: Address = 0x2C0;
+
+#[derive(Clone)]
 pub struct Cpu {
     v: [u8; 0x10],
     i: u16,
@@ -17,6 +42,27 @@ pub struct Cpu {
     sound_timer: u8,
     pc: u16,
     stack: Stack,
+    rng: Box<dyn RandomSource>,
+    quirks: Quirks,
+    variant: Variant,
+    machine_code: MachineCode,
+    // Set while a CHIP-8E Fx4F waits for the delay timer.
+    delaying: bool,
+    cache: DecodeCache,
+    blocks: BlockCache,
+}
+
+impl PartialEq for Cpu {
+    fn eq(&self, other: &Cpu) -> bool {
+        self.v == other.v
+            && self.i == other.i
+            && self.delay_timer == other.delay_timer
+            && self.sound_timer == other.sound_timer
+            && self.pc == other.pc
+            && self.stack == other.stack
+            && self.variant == other.variant
+            && self.delaying == other.delaying
+    }
 }
 
 impl fmt::Debug for Cpu {
@@ -50,16 +96,83 @@ impl fmt::Debug for Cpu {
 }
 
 impl Cpu {
-    pub fn new() -> Cpu {
+    pub fn new(rng: Box<dyn RandomSource>) -> Cpu {
         Cpu {
             v: [0; 0x10],
             i: 0,
             delay_timer: 0,
             sound_timer: 0,
-            pc: 0x200,
+            pc: DEFAULT_LOAD_ADDRESS,
             stack: Stack::new(),
+            rng,
+            quirks: Quirks::default(),
+            variant: Variant::default(),
+            machine_code: MachineCode::default(),
+            delaying: false,
+            cache: DecodeCache::new(),
+            blocks: BlockCache::new(),
         }
     }
+    pub fn registers(&self) -> Registers {
+        Registers {
+            v: self.v,
+            i: self.i,
+            pc: self.pc,
+            sp: self.stack.sp(),
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+        }
+    }
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+    pub fn save_state(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.v);
+        write_u16(out, self.i);
+        out.push(self.delay_timer);
+        out.push(self.sound_timer);
+        write_u16(out, self.pc);
+        self.stack.save_state(out);
+        out.push(self.variant as u8);
+        out.push(self.delaying as u8);
+    }
+    pub fn load_state(&mut self, reader: &mut Reader) -> Result<(), StateError> {
+        self.v.copy_from_slice(reader.bytes(0x10)?);
+        self.i = reader.u16()?;
+        self.delay_timer = reader.u8()?;
+        self.sound_timer = reader.u8()?;
+        self.pc = reader.u16()? % MEMORY_SIZE as Address;
+        self.stack.load_state(reader)?;
+        let variant = reader.u8()?;
+        self.variant = Variant::from_index(variant).ok_or(StateError::BadVariant(variant))?;
+        self.delaying = reader.u8()? != 0;
+        self.invalidate_decode_cache();
+        Ok(())
+    }
+    pub fn jump(&mut self, address: Address) {
+        self.pc = address % MEMORY_SIZE as Address;
+    }
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+        self.delaying = false;
+        self.invalidate_decode_cache();
+    }
+    pub fn set_machine_code(&mut self, machine_code: MachineCode) {
+        self.machine_code = machine_code;
+    }
+    pub fn set_random_source(&mut self, rng: Box<dyn RandomSource>) {
+        self.rng = rng;
+    }
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        self.cache.set_enabled(enabled);
+    }
+    pub fn invalidate_decode_cache(&mut self) {
+        self.cache.clear();
+        self.blocks.clear();
+    }
     pub fn tick(
         &mut self,
         memory: &mut Memory,
@@ -70,13 +183,30 @@ impl Cpu {
     ) {
         // println!(" ? {:?}", self);
         // println!(" ? {:?}", self.stack);
-        let data = self.fetch(memory);
-        let opcode = self.decode(data);
+        let pc = self.pc;
+        let opcode = match self.cache.get(pc) {
+            Some(opcode) => opcode,
+            None => {
+                let opcode = self.decode(self.fetch(memory));
+                self.cache.insert(pc, opcode);
+                opcode
+            }
+        };
         // println!("> {:?}", opcode);
         let old_sound_timer = self.sound_timer;
         self.execute(&opcode, memory, vram, keyboard, video_sink);
+        self.pc %= MEMORY_SIZE as Address;
+        self.report_sound(old_sound_timer, audio_sink);
+    }
+    // Once per 60 Hz frame, however many instructions ran in it.
+    pub fn tick_timers(&mut self, audio_sink: &mut AudioSink) {
+        let old_sound_timer = self.sound_timer;
         self.delay_timer = self.delay_timer.saturating_sub(1);
         self.sound_timer = self.sound_timer.saturating_sub(1);
+        self.report_sound(old_sound_timer, audio_sink);
+    }
+    // Like the VIP, the beep only starts for a sound timer of 2 or more.
+    fn report_sound(&self, old_sound_timer: u8, audio_sink: &mut AudioSink) {
         if old_sound_timer == 0 && self.sound_timer > 1 {
             audio_sink.event = Some(AudioEvent::Play);
         } else if old_sound_timer > 0 && self.sound_timer == 0 {
@@ -88,7 +218,7 @@ impl Cpu {
     }
     pub fn decode(&self, data: DWord) -> OpCode {
         let instruction = Instruction::new(data);
-        instruction.decode()
+        instruction.decode(self.variant)
     }
     pub fn execute(
         &mut self,
@@ -125,47 +255,63 @@ impl Cpu {
                 let value_x = self.v[vx as usize];
                 let value_y = self.v[vy as usize];
                 self.v[0xF as usize] = if value_x > value_y { 0 } else { 1 };
-                self.v[vx as usize] = value_y.saturating_sub(value_x);
+                self.v[vx as usize] = value_y.wrapping_sub(value_x);
                 self.pc += 2;
             }
             OpCode::SubVy(vx, vy) => {
                 let value_x = self.v[vx as usize];
                 let value_y = self.v[vy as usize];
                 self.v[0xF as usize] = if value_y > value_x { 0 } else { 1 };
-                self.v[vx as usize] = value_x.saturating_sub(value_y);
+                self.v[vx as usize] = value_x.wrapping_sub(value_y);
                 self.pc += 2;
             }
             OpCode::And(vx, vy) => {
                 self.v[vx as usize] &= self.v[vy as usize];
+                if self.quirks.logic {
+                    self.v[0xF] = 0;
+                }
                 self.pc += 2;
             }
             OpCode::Or(vx, vy) => {
                 self.v[vx as usize] |= self.v[vy as usize];
+                if self.quirks.logic {
+                    self.v[0xF] = 0;
+                }
                 self.pc += 2;
             }
             OpCode::Xor(vx, vy) => {
                 self.v[vx as usize] ^= self.v[vy as usize];
+                if self.quirks.logic {
+                    self.v[0xF] = 0;
+                }
                 self.pc += 2;
             }
-            OpCode::ShiftRight(vx, _vy) => {
-                let value = self.v[vx as usize];
+            OpCode::ShiftRight(vx, vy) => {
+                let source = if self.quirks.shift { vx } else { vy };
+                let value = self.v[source as usize];
                 self.v[0xF as usize] = value & 0x1;
-                // self.v[vy as usize] = value >> 1;
                 self.v[vx as usize] = value >> 1;
                 self.pc += 2;
             }
-            OpCode::ShiftLeft(vx, _vy) => {
-                let value = self.v[vx as usize];
+            OpCode::ShiftLeft(vx, vy) => {
+                let source = if self.quirks.shift { vx } else { vy };
+                let value = self.v[source as usize];
                 self.v[0xF as usize] = value >> 7;
-                // self.v[vy as usize] = value << 1;
                 self.v[vx as usize] = value << 1;
                 self.pc += 2;
             }
             OpCode::Jmp(address) => {
-                self.pc = address;
+                if self.pc == DEFAULT_LOAD_ADDRESS && address == HIRES_ENTRY {
+                    vram.set_hires(true);
+                    vram.flush(video_sink);
+                    self.pc = HIRES_START;
+                } else {
+                    self.pc = address;
+                }
             }
             OpCode::JmpV0(address) => {
-                self.pc = address + self.v[0] as Address;
+                let vx = if self.quirks.jump { address >> 8 } else { 0 };
+                self.pc = address + self.v[vx as usize] as Address;
             }
             OpCode::Jeq(vx, value) => {
                 if self.v[vx as usize] == value {
@@ -217,21 +363,18 @@ impl Cpu {
                 }
             }
             OpCode::Store(vx) => {
-                let mut r = 0;
-                for addr in self.i..self.i + (vx as u16) + 1 {
-                    memory.write(addr, self.v[r as usize]);
-                    r += 1;
+                for r in 0..=vx {
+                    let addr = self.i.wrapping_add(r as Address);
+                    self.write(memory, addr, self.v[r as usize]);
                 }
-                self.i += r;
+                self.increment_i(vx);
                 self.pc += 2;
             }
             OpCode::Load(vx) => {
-                let mut r = 0;
-                for addr in self.i..self.i + (vx as u16) + 1 {
-                    self.v[r as usize] = memory.read(addr);
-                    r += 1;
+                for r in 0..=vx {
+                    self.v[r as usize] = memory.read(self.i.wrapping_add(r as Address));
                 }
-                self.i += r;
+                self.increment_i(vx);
                 self.pc += 2;
             }
             OpCode::Call(address) => {
@@ -240,8 +383,7 @@ impl Cpu {
             }
             OpCode::Return() => {
                 let addr = self.stack.pop();
-                self.pc = addr;
-                self.pc += 2;
+                self.pc = addr.wrapping_add(2);
             }
             OpCode::SetDelayTimer(vx) => {
                 self.delay_timer = self.v[vx as usize];
@@ -263,20 +405,25 @@ impl Cpu {
             }
             OpCode::AddIVx(vx) => {
                 let value_x = self.v[vx as usize];
-                self.v[0xF as usize] = if self.i + value_x as u16 > 0xFFF {
+                self.v[0xF as usize] = if self.i as usize + value_x as usize > 0xFFF {
                     1
                 } else {
                     0
                 };
                 //TODO, wrap around 0xFFF
-                self.i += self.v[vx as usize] as Address;
+                self.i = self.i.wrapping_add(value_x as Address);
                 self.pc += 2;
             }
             OpCode::DrawSprite(vx, vy, value) => {
                 let value_x = self.v[vx as usize];
                 let value_y = self.v[vy as usize];
+                let mut sprite = [0; 0x10];
+                for (row, byte) in sprite.iter_mut().enumerate().take(value as usize) {
+                    *byte = memory.read(self.i.wrapping_add(row as Address));
+                }
+                let sprite = &sprite[..value as usize];
                 self.v[0xF as usize] =
-                    if vram.draw_sprite(memory, self.i, value_x, value_y, value, video_sink) {
+                    if vram.draw_sprite(sprite, value_x, value_y, self.quirks.wrap, video_sink) {
                         1
                     } else {
                         0
@@ -284,11 +431,18 @@ impl Cpu {
                 self.pc += 2;
             }
             OpCode::Font(vx) => {
-                self.i = (self.v[vx as usize] * 5) as Address;
+                self.i = (self.v[vx as usize] & 0xF) as Address * 5;
                 self.pc += 2;
             }
             OpCode::ClearScreen() => {
-                vram.clear();
+                vram.clear(video_sink);
+                self.pc += 2;
+            }
+            // A machine code call like any other outside of hi-res mode.
+            OpCode::HiresClear() => {
+                if vram.height() == HIRES_HEIGHT {
+                    vram.clear(video_sink);
+                }
                 self.pc += 2;
             }
             OpCode::BCD(vx) => {
@@ -300,17 +454,39 @@ impl Cpu {
                     x /= 10;
                 }
                 let i = self.i;
-                memory.write(i, digits[0]);
-                memory.write(i + 1, digits[1]);
-                memory.write(i + 2, digits[2]);
+                self.write(memory, i, digits[0]);
+                self.write(memory, i.wrapping_add(1), digits[1]);
+                self.write(memory, i.wrapping_add(2), digits[2]);
                 self.pc += 2;
             }
             OpCode::Random(vx, mask) => {
-                let mut rng = thread_rng();
-                let random = rng.gen::<u8>();
+                let random = self.rng.next_u8();
                 self.v[vx as usize] = random & mask;
                 self.pc += 2;
             }
+            OpCode::Chip8X(op) => self.execute_chip8x(op, vram, video_sink),
+            OpCode::Chip8E(op) => self.execute_chip8e(op, memory),
+            OpCode::MachineCall(address) => match self.machine_code {
+                MachineCode::Skip => self.pc += 2,
+                MachineCode::Cdp1802 => {
+                    self.call_machine_code(address, memory, vram, keyboard, video_sink)
+                }
+            },
+            // Unknown instructions are skipped.
+            OpCode::Unknown(_) => {
+                self.pc += 2;
+            }
+        }
+    }
+    fn increment_i(&mut self, vx: Register) {
+        if !self.quirks.memory_leave_i_unchanged {
+            let step = if self.quirks.memory_increment_by_x { vx } else { vx + 1 };
+            self.i = self.i.wrapping_add(step as Address);
         }
     }
+    pub fn write(&mut self, memory: &mut Memory, address: Address, value: u8) {
+        memory.write(address, value);
+        self.cache.invalidate(address);
+        self.blocks.invalidate(address);
+    }
 }
@@ -0,0 +1,188 @@
+use super::instruction::{Instruction, OpCode};
+use super::Cpu;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use chip8::audio::AudioSink;
+use chip8::keyboard::Keyboard;
+use chip8::memory::{Memory, MEMORY_SIZE};
+use chip8::vram::{VideoSink, Vram};
+use chip8::Address;
+use program::Variant;
+
+const MAX_BLOCK_LENGTH: usize = 32;
+
+pub struct Bus<'a> {
+    pub memory: &'a mut Memory,
+    pub vram: &'a mut Vram,
+    pub keyboard: &'a Keyboard,
+    pub video_sink: &'a mut VideoSink,
+}
+
+type Op = Box<dyn Fn(&mut Cpu, &mut Bus) + Send + Sync>;
+
+// A straight-line run of instructions translated into closures. The last op
+// is the only one allowed to change control flow or write to memory.
+pub struct Block {
+    ops: Vec<Op>,
+}
+
+impl Block {
+    fn translate(memory: &Memory, start: Address, variant: Variant) -> Block {
+        let mut ops = Vec::new();
+        let mut address = start as usize;
+        loop {
+            let opcode = Instruction::new(memory.read_dword(address as Address)).decode(variant);
+            ops.push(compile(opcode));
+            address += 2;
+            if ends_block(&opcode) || ops.len() == MAX_BLOCK_LENGTH || address + 1 >= MEMORY_SIZE {
+                break;
+            }
+        }
+        Block { ops }
+    }
+}
+
+#[derive(Clone)]
+pub struct BlockCache {
+    blocks: Vec<Option<Arc<Block>>>,
+}
+
+impl BlockCache {
+    pub fn new() -> BlockCache {
+        BlockCache {
+            blocks: vec![None; MEMORY_SIZE],
+        }
+    }
+    pub fn get(&mut self, memory: &Memory, address: Address, variant: Variant) -> Arc<Block> {
+        let entry = &mut self.blocks[address as usize % MEMORY_SIZE];
+        if let Some(ref block) = *entry {
+            return Arc::clone(block);
+        }
+        let block = Arc::new(Block::translate(memory, address, variant));
+        *entry = Some(Arc::clone(&block));
+        block
+    }
+    pub fn invalidate(&mut self, address: Address) {
+        // Any block starting up to MAX_BLOCK_LENGTH instructions before the
+        // written byte may contain it.
+        let address = address as usize % MEMORY_SIZE;
+        let first = address.saturating_sub(MAX_BLOCK_LENGTH * 2 - 1);
+        for entry in &mut self.blocks[first..=address] {
+            *entry = None;
+        }
+    }
+    pub fn clear(&mut self) {
+        for entry in self.blocks.iter_mut() {
+            *entry = None;
+        }
+    }
+}
+
+impl Cpu {
+    pub fn run_threaded(
+        &mut self,
+        cycles: usize,
+        memory: &mut Memory,
+        vram: &mut Vram,
+        keyboard: &Keyboard,
+        video_sink: &mut VideoSink,
+        audio_sink: &mut AudioSink,
+    ) {
+        let mut bus = Bus {
+            memory,
+            vram,
+            keyboard,
+            video_sink,
+        };
+        let mut remaining = cycles;
+        while remaining > 0 {
+            let block = self.blocks.get(bus.memory, self.pc, self.variant);
+            for op in block.ops.iter().take(remaining) {
+                let old_sound_timer = self.sound_timer;
+                op(self, &mut bus);
+                self.pc %= MEMORY_SIZE as Address;
+                self.report_sound(old_sound_timer, audio_sink);
+                remaining -= 1;
+            }
+        }
+    }
+}
+
+fn ends_block(opcode: &OpCode) -> bool {
+    matches!(
+        *opcode,
+        OpCode::Jmp(_)
+            | OpCode::JmpV0(_)
+            | OpCode::Jeq(_, _)
+            | OpCode::JeqVy(_, _)
+            | OpCode::Jneq(_, _)
+            | OpCode::JneqVy(_, _)
+            | OpCode::JmpK(_)
+            | OpCode::JmpNK(_)
+            | OpCode::WaitForKey(_)
+            | OpCode::Call(_)
+            | OpCode::Return()
+            | OpCode::Store(_)
+            | OpCode::BCD(_)
+            | OpCode::DrawSprite(_, _, _)
+            | OpCode::ClearScreen()
+            | OpCode::HiresClear()
+            | OpCode::MachineCall(_)
+            | OpCode::Chip8X(_)
+            | OpCode::Chip8E(_)
+    )
+}
+
+fn compile(opcode: OpCode) -> Op {
+    match opcode {
+        OpCode::Set(vx, value) => Box::new(move |cpu, _| {
+            cpu.v[vx as usize] = value;
+            cpu.pc += 2;
+        }),
+        OpCode::Copy(vx, vy) => Box::new(move |cpu, _| {
+            cpu.v[vx as usize] = cpu.v[vy as usize];
+            cpu.pc += 2;
+        }),
+        OpCode::And(vx, vy) => Box::new(move |cpu, _| {
+            cpu.v[vx as usize] &= cpu.v[vy as usize];
+            if cpu.quirks.logic {
+                cpu.v[0xF] = 0;
+            }
+            cpu.pc += 2;
+        }),
+        OpCode::Or(vx, vy) => Box::new(move |cpu, _| {
+            cpu.v[vx as usize] |= cpu.v[vy as usize];
+            if cpu.quirks.logic {
+                cpu.v[0xF] = 0;
+            }
+            cpu.pc += 2;
+        }),
+        OpCode::Xor(vx, vy) => Box::new(move |cpu, _| {
+            cpu.v[vx as usize] ^= cpu.v[vy as usize];
+            if cpu.quirks.logic {
+                cpu.v[0xF] = 0;
+            }
+            cpu.pc += 2;
+        }),
+        OpCode::SetI(address) => Box::new(move |cpu, _| {
+            cpu.i = address;
+            cpu.pc += 2;
+        }),
+        OpCode::Font(vx) => Box::new(move |cpu, _| {
+            cpu.i = (cpu.v[vx as usize] & 0xF) as Address * 5;
+            cpu.pc += 2;
+        }),
+        OpCode::SetDelayTimer(vx) => Box::new(move |cpu, _| {
+            cpu.delay_timer = cpu.v[vx as usize];
+            cpu.pc += 2;
+        }),
+        OpCode::LdDelayTimer(vx) => Box::new(move |cpu, _| {
+            cpu.v[vx as usize] = cpu.delay_timer;
+            cpu.pc += 2;
+        }),
+        _ => Box::new(move |cpu, bus| {
+            cpu.execute(&opcode, bus.memory, bus.vram, bus.keyboard, bus.video_sink)
+        }),
+    }
+}
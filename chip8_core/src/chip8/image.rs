@@ -0,0 +1,137 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use chip8::vram::PixelBuffer;
+
+/* INFO:
+    Screenshots of a `PixelBuffer`. The PNG is written without compressing,
+    as stored deflate blocks, so it needs nothing beyond `alloc` and is the
+    same bytes every time for the same screen. A CHIP-8 screen is small
+    enough that the size doesn't matter.
+*/
+
+// Deflate stored blocks hold at most this many bytes.
+const STORED_BLOCK: usize = 0xFFFF;
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+// A zlib stream of stored blocks.
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(blocks.peek().is_none() as u8);
+        let length = block.len() as u16;
+        out.extend_from_slice(&length.to_le_bytes());
+        out.extend_from_slice(&(!length).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+// An 8-bit RGB PNG of `rgb`, three bytes per pixel row by row from the top.
+pub fn png(width: usize, height: usize, rgb: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    let mut header = Vec::new();
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits per channel, RGB, then the standard compression, filtering and
+    // no interlacing
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    chunk(&mut out, b"IHDR", &header);
+    // Every row starts with filter type 0, none.
+    let mut scanlines = Vec::with_capacity((width * 3 + 1) * height);
+    for row in rgb.chunks(width * 3).take(height) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+    chunk(&mut out, b"IDAT", &zlib(&scanlines));
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+// Every pixel of `rgb`, `width` pixels across, `scale` times across and
+// down.
+pub fn scale_rgb(rgb: &[u8], width: usize, scale: usize) -> Vec<u8> {
+    let mut scaled = Vec::with_capacity(rgb.len() * scale * scale);
+    for row in rgb.chunks(width * 3) {
+        let mut line = Vec::with_capacity(row.len() * scale);
+        for pixel in row.chunks(3) {
+            for _ in 0..scale {
+                line.extend_from_slice(pixel);
+            }
+        }
+        for _ in 0..scale {
+            scaled.extend_from_slice(&line);
+        }
+    }
+    scaled
+}
+
+impl PixelBuffer {
+    // Every pixel `scale` times across and down, `on` and `off` standing in
+    // for the colors when there is no color board.
+    pub fn to_png(&self, scale: usize, on: [u8; 3], off: [u8; 3]) -> Vec<u8> {
+        let scale = scale.max(1);
+        let scaled = scale_rgb(&self.to_rgb(on, off), self.width, scale);
+        png(self.width * scale, self.height * scale, &scaled)
+    }
+    // A binary (P4) PBM, where 1 is black ink: lit pixels come out black on
+    // white.
+    pub fn to_pbm(&self) -> Vec<u8> {
+        let mut out = format!("P4\n{} {}\n", self.width, self.height).into_bytes();
+        for row in self.data.chunks(self.width) {
+            for pixels in row.chunks(8) {
+                let byte = pixels
+                    .iter()
+                    .enumerate()
+                    .fold(0, |byte, (bit, &pixel)| byte | (pixel as u8) << (7 - bit));
+                out.push(byte);
+            }
+        }
+        out
+    }
+    // A `#` for every lit pixel and a space for the others, one line per row.
+    pub fn to_ascii(&self) -> String {
+        let mut out = String::with_capacity((self.width + 1) * self.height);
+        for row in self.data.chunks(self.width) {
+            out.extend(row.iter().map(|&pixel| if pixel { '#' } else { ' ' }));
+            out.push('\n');
+        }
+        out
+    }
+}
@@ -1,5 +1,5 @@
-use std::convert::From;
-use std::fmt;
+use core::convert::From;
+use core::fmt;
 /* INFO:
     1 2 3 C 
     4 5 6 D 
@@ -27,8 +27,9 @@ pub enum HexKey {
 }
 
 impl From<u8> for HexKey {
+    // Like the VIP keypad latch, only the low nibble selects a key.
     fn from(index: u8) -> Self {
-        match index {
+        match index & 0xF {
             0x0 => HexKey::X0,
             0x1 => HexKey::X1,
             0x2 => HexKey::X2,
@@ -44,8 +45,7 @@ impl From<u8> for HexKey {
             0xc => HexKey::Xc,
             0xd => HexKey::Xd,
             0xe => HexKey::Xe,
-            0xf => HexKey::Xf,
-            _ => panic!(format!("Not a valid keyboard key, {}.", index)),
+            _ => HexKey::Xf,
         }
     }
 }
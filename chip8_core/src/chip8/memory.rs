@@ -1,8 +1,11 @@
 use chip8::{Address, DWord, Word};
+use alloc::vec::Vec;
+use chip8::state::{Reader, StateError};
 use program::Program;
 
-const MEMORY_SIZE: usize = 4096;
+pub const MEMORY_SIZE: usize = 4096;
 
+#[derive(Clone, PartialEq)]
 pub struct Memory {
     data: [u8; MEMORY_SIZE],
 }
@@ -18,7 +21,7 @@ impl Memory {
         Self::default()
     }
     pub fn load_program(&mut self, program: &Program) {
-        let data: &mut Vec<u8> = &mut vec![
+        let font = [
             0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
             0x20, 0x60, 0x20, 0x20, 0x70, // 1
             0xF0, 0x10, 0xf0, 0x80, 0xF0, // 2
@@ -36,24 +39,34 @@ impl Memory {
             0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
             0xF0, 0x80, 0xF0, 0x80, 0x80, // F
         ];
-        data.append(&mut vec![0; 0x1B0]);
-        data.append(&mut program.data.clone());
-        for (index, &byte) in data.iter().enumerate() {
-            self.data[index] = byte;
+        self.data = [0; MEMORY_SIZE];
+        self.data[..font.len()].copy_from_slice(&font);
+        // Anything past the end of memory is dropped.
+        let start = program.load_address as usize % MEMORY_SIZE;
+        for (cell, &byte) in self.data[start..].iter_mut().zip(program.data.iter()) {
+            *cell = byte;
         }
     }
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+    // Addresses wrap around the 4K address space instead of faulting.
     pub fn read(&self, address: Address) -> Word {
-        if address as usize > MEMORY_SIZE {
-            panic!(format!("Cannot access memory at {}!", address));
-        }
-        self.data[address as usize]
+        self.data[address as usize % MEMORY_SIZE]
     }
     pub fn read_dword(&self, address: Address) -> DWord {
         let high = self.read(address);
-        let low = self.read(address + 1);
+        let low = self.read(address.wrapping_add(1));
         (high as u16) << 8 | (low as u16)
     }
     pub fn write(&mut self, address: Address, value: u8) {
-        self.data[address as usize] = value;
+        self.data[address as usize % MEMORY_SIZE] = value;
+    }
+    pub fn save_state(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.data);
+    }
+    pub fn load_state(&mut self, reader: &mut Reader) -> Result<(), StateError> {
+        self.data.copy_from_slice(reader.bytes(MEMORY_SIZE)?);
+        Ok(())
     }
 }
@@ -1,36 +1,82 @@
 pub mod audio;
 mod cpu;
+pub mod image;
 pub mod keyboard;
-mod memory;
+pub(crate) mod memory;
+pub mod phosphor;
+pub mod quirks;
+pub mod random;
+pub mod recording;
 mod stack;
+pub mod state;
+pub mod vip;
 pub mod vram;
 
 use self::audio::AudioSink;
 use self::cpu::Cpu;
 use self::keyboard::Keyboard;
 use self::memory::Memory;
-use self::vram::VideoSink;
+use self::quirks::Quirks;
+use self::random::{RandomSource, XorShift};
+use self::state::{Reader, StateError, MAGIC, VERSION};
+use self::vip::Vip;
 use self::vram::Vram;
-use program::Program;
+use self::vram::{PixelBuffer, VideoSink};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use program::{Program, ProgramError, Variant};
 
 pub type Address = u16;
 pub type Word = u8;
 pub type DWord = u16;
 pub type Register = u8;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Registers {
+    pub v: [u8; 0x10],
+    pub i: Address,
+    pub pc: Address,
+    pub sp: u8,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Backend {
+    #[default]
+    Interpreter,
+    Threaded,
+    // Runs the threaded backend and checks it against the interpreter after
+    // every call, panicking as soon as the two disagree.
+    Lockstep,
+    // Runs the VIP's own interpreter, set with `set_vip_interpreter`, on an
+    // emulated VIP. Quirks, machine code and the random source are whatever
+    // that interpreter does, and `run` counts fifteenths of a frame.
+    Vip,
+}
+
+// What 0nnn does. On the VIP it called 1802 machine code at nnn.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MachineCode {
+    // Skipped, like most interpreters written after the VIP.
+    #[default]
+    Skip,
+    // Run on an emulated CDP1802 against the VIP memory layout.
+    Cdp1802,
+}
+
 pub struct Chip8 {
+    backend: Backend,
     cpu: Cpu,
     memory: Memory,
     vram: Vram,
+    shadow: Option<Box<(Cpu, Memory, Vram)>>,
+    vip: Option<Box<Vip>>,
 }
 
 impl Default for Chip8 {
     fn default() -> Self {
-        Chip8 {
-            cpu: Cpu::new(),
-            memory: Memory::new(),
-            vram: Vram::new(),
-        }
+        Chip8::with_backend(Backend::default())
     }
 }
 
@@ -38,8 +84,135 @@ impl Chip8 {
     pub fn new() -> Chip8 {
         Self::default()
     }
+    pub fn with_backend(backend: Backend) -> Chip8 {
+        let mut chip8 = Chip8 {
+            backend,
+            cpu: Cpu::new(default_random_source()),
+            memory: Memory::new(),
+            vram: Vram::new(),
+            shadow: None,
+            vip: None,
+        };
+        if backend == Backend::Vip {
+            chip8.vip = Some(Box::new(Vip::new()));
+        }
+        chip8.sync_shadow();
+        chip8
+    }
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+    // Takes effect when the next program is loaded. The other backends
+    // have their own interpreter and ignore it.
+    pub fn set_vip_interpreter(&mut self, image: &[u8]) -> Result<(), ProgramError> {
+        match self.vip {
+            Some(ref mut vip) => vip.set_interpreter(image),
+            None => Ok(()),
+        }
+    }
     pub fn load_program(&mut self, program: &Program) {
+        if let Some(ref mut vip) = self.vip {
+            vip.load_program(program);
+        }
         self.memory.load_program(&program);
+        self.vram.set_hires(false);
+        self.vram
+            .set_color_board(program.variant == Variant::Chip8X);
+        self.cpu.set_variant(program.variant);
+        self.cpu.jump(program.load_address);
+        self.cpu.invalidate_decode_cache();
+        self.sync_shadow();
+    }
+    pub fn variant(&self) -> Variant {
+        self.cpu.variant()
+    }
+    pub fn registers(&self) -> Registers {
+        match self.vip {
+            Some(ref vip) => vip.registers(),
+            None => self.cpu.registers(),
+        }
+    }
+    pub fn memory(&self) -> &[u8] {
+        match self.vip {
+            Some(ref vip) => vip.memory(),
+            None => self.memory.as_slice(),
+        }
+    }
+    pub fn write_memory(&mut self, address: Address, value: Word) {
+        if let Some(ref mut vip) = self.vip {
+            vip.write_memory(address, value);
+            return;
+        }
+        self.cpu.write(&mut self.memory, address, value);
+        self.sync_shadow();
+    }
+    pub fn pixel_buffer(&self) -> PixelBuffer {
+        match self.vip {
+            Some(ref vip) => vip.vram().pixel_buffer(),
+            None => self.vram.pixel_buffer(),
+        }
+    }
+    // The random source is not part of the snapshot, so `Random` results may
+    // differ after restoring unless the host reseeds it.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        if let Some(ref vip) = self.vip {
+            out.push(1);
+            vip.save_state(&mut out);
+            return out;
+        }
+        out.push(0);
+        self.cpu.save_state(&mut out);
+        self.memory.save_state(&mut out);
+        self.vram.save_state(&mut out);
+        out
+    }
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut reader = Reader::new(data);
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(StateError::BadMagic);
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+        if (reader.u8()? == 1) != self.vip.is_some() {
+            return Err(StateError::WrongBackend);
+        }
+        if let Some(ref mut vip) = self.vip {
+            let mut restored = vip.clone();
+            restored.load_state(&mut reader)?;
+            *vip = restored;
+            return Ok(());
+        }
+        let mut cpu = self.cpu.clone();
+        let mut memory = self.memory.clone();
+        let mut vram = self.vram.clone();
+        cpu.load_state(&mut reader)?;
+        memory.load_state(&mut reader)?;
+        vram.load_state(&mut reader)?;
+        self.cpu = cpu;
+        self.memory = memory;
+        self.vram = vram;
+        self.sync_shadow();
+        Ok(())
+    }
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.cpu.set_quirks(quirks);
+        self.sync_shadow();
+    }
+    pub fn set_machine_code(&mut self, machine_code: MachineCode) {
+        self.cpu.set_machine_code(machine_code);
+        self.sync_shadow();
+    }
+    pub fn set_random_source(&mut self, source: Box<dyn RandomSource>) {
+        self.cpu.set_random_source(source);
+        self.sync_shadow();
+    }
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        self.cpu.set_decode_cache(enabled);
     }
     pub fn step(
         &mut self,
@@ -47,12 +220,91 @@ impl Chip8 {
         video_sink: &mut VideoSink,
         audio_sink: &mut AudioSink,
     ) {
-        self.cpu.tick(
-            &mut self.memory,
-            &mut self.vram,
-            keyboard,
-            video_sink,
-            audio_sink,
-        );
+        self.run(1, keyboard, video_sink, audio_sink);
     }
+    pub fn run(
+        &mut self,
+        cycles: usize,
+        keyboard: &Keyboard,
+        video_sink: &mut VideoSink,
+        audio_sink: &mut AudioSink,
+    ) {
+        // Changes made between runs, by loading a program or a state.
+        if self.backend != Backend::Vip {
+            self.vram.flush(video_sink);
+        }
+        match self.backend {
+            Backend::Interpreter => {
+                for _ in 0..cycles {
+                    self.cpu.tick(
+                        &mut self.memory,
+                        &mut self.vram,
+                        keyboard,
+                        video_sink,
+                        audio_sink,
+                    );
+                }
+            }
+            Backend::Vip => {
+                if let Some(ref mut vip) = self.vip {
+                    vip.run(cycles, keyboard, video_sink, audio_sink);
+                }
+            }
+            Backend::Threaded | Backend::Lockstep => {
+                self.cpu.run_threaded(
+                    cycles,
+                    &mut self.memory,
+                    &mut self.vram,
+                    keyboard,
+                    video_sink,
+                    audio_sink,
+                );
+            }
+        }
+        if let Some(ref mut shadow) = self.shadow {
+            let (ref mut cpu, ref mut memory, ref mut vram) = **shadow;
+            let mut video_sink = VideoSink::new();
+            let mut audio_sink = AudioSink::new();
+            for _ in 0..cycles {
+                cpu.tick(memory, vram, keyboard, &mut video_sink, &mut audio_sink);
+            }
+            if *cpu != self.cpu || *memory != self.memory || *vram != self.vram {
+                panic!(
+                    "Threaded backend diverged from the interpreter!\n  threaded:    {:?}\n  interpreter: {:?}",
+                    self.cpu, cpu
+                );
+            }
+        }
+    }
+    // Counts the delay and sound timers down. Hosts call this once per 60 Hz
+    // frame next to `run`, whatever the number of cycles; the VIP keeps its
+    // own time.
+    pub fn tick_timers(&mut self, audio_sink: &mut AudioSink) {
+        if self.backend == Backend::Vip {
+            return;
+        }
+        self.cpu.tick_timers(audio_sink);
+        if let Some(ref mut shadow) = self.shadow {
+            shadow.0.tick_timers(&mut AudioSink::new());
+        }
+    }
+    fn sync_shadow(&mut self) {
+        if self.backend == Backend::Lockstep {
+            self.shadow = Some(Box::new((
+                self.cpu.clone(),
+                self.memory.clone(),
+                self.vram.clone(),
+            )));
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn default_random_source() -> Box<dyn RandomSource> {
+    Box::new(XorShift::from_entropy())
+}
+
+#[cfg(not(feature = "std"))]
+fn default_random_source() -> Box<dyn RandomSource> {
+    Box::new(XorShift::default())
 }
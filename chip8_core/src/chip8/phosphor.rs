@@ -0,0 +1,278 @@
+use alloc::vec::Vec;
+use chip8::vram::{add_rect, ColorMap, Rect, VideoSink};
+use core::mem;
+
+/* INFO:
+    CHIP-8 games move a sprite by drawing it again with XOR to erase it and
+    then drawing it at its new place. When a frame ends between the two, the
+    sprite is missing from that frame and flickers. The phosphor sits
+    between the core and the screen and smooths that over, the way the slow
+    phosphor of the VIP's TV did.
+
+    It turns every 60 Hz frame into a level per pixel, from 0 for off to 1
+    for fully lit. A host takes what the core drew with `update` as often as
+    it likes and calls `frame` once per frame:
+
+        chip8.run(cycles_per_frame, &keyboard, &mut video_sink, &mut audio_sink);
+        chip8.tick_timers(&mut audio_sink);
+        phosphor.update(&mut video_sink);
+        if phosphor.frame() {
+            show(phosphor.to_rgb(on, off));
+        }
+
+    `update` only copies the rectangles the sink says changed, and hosts
+    that redraw in parts can do the same with `take_dirty` and
+    `rect_to_rgb` in place of `to_rgb`. Blending and decay change the whole
+    screen every frame they fade, so they mostly leave one big rectangle.
+*/
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Persistence {
+    // Pixels go dark as soon as they are erased.
+    #[default]
+    Off,
+    // Every frame is mixed half and half with the one before.
+    Blend,
+    // Erased pixels fade out, losing half their brightness every
+    // `half_life` frames.
+    Decay {
+        half_life: u32,
+    },
+}
+
+// Levels below this count as dark, so a fade ends.
+const DARK: f32 = 1.0 / 256.0;
+
+// The factor that halves a level after `half_life` frames. `powf` needs std,
+// so the root is found by bisection.
+fn decay_factor(half_life: u32) -> f32 {
+    if half_life == 0 {
+        return 0.0;
+    }
+    let (mut low, mut high) = (0.5f32, 1.0f32);
+    for _ in 0..32 {
+        let middle = (low + high) / 2.0;
+        let level = (0..half_life).fold(1.0, |level, _| level * middle);
+        if level < 0.5 {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    low
+}
+
+pub struct Phosphor {
+    persistence: Persistence,
+    vblank_only: bool,
+    factor: f32,
+    width: usize,
+    height: usize,
+    // The screen as the core last left it.
+    current: Vec<bool>,
+    // What this frame shows: the current screen, or with `vblank_only`
+    // every pixel lit since the frame started.
+    image: Vec<bool>,
+    previous: Vec<bool>,
+    // What is left of the frames before.
+    afterglow: Vec<f32>,
+    levels: Vec<f32>,
+    colors: Option<ColorMap>,
+    // Set when `update` changed the levels since the last frame.
+    changed: bool,
+    // Where the levels changed since the last `take_dirty`.
+    dirty: Vec<Rect>,
+}
+
+impl Phosphor {
+    pub fn new(persistence: Persistence) -> Phosphor {
+        let mut phosphor = Phosphor {
+            persistence,
+            vblank_only: false,
+            factor: 0.0,
+            width: 0,
+            height: 0,
+            current: Vec::new(),
+            image: Vec::new(),
+            previous: Vec::new(),
+            afterglow: Vec::new(),
+            levels: Vec::new(),
+            colors: None,
+            changed: false,
+            dirty: Vec::new(),
+        };
+        phosphor.set_persistence(persistence);
+        phosphor
+    }
+    pub fn persistence(&self) -> Persistence {
+        self.persistence
+    }
+    pub fn set_persistence(&mut self, persistence: Persistence) {
+        self.persistence = persistence;
+        self.factor = match persistence {
+            Persistence::Decay { half_life } => decay_factor(half_life),
+            _ => 0.0,
+        };
+    }
+    // With `vblank_only` the levels only change in `frame`, and a frame shows
+    // every pixel lit while it ran rather than the screen at its end, so a
+    // half-drawn screen is never shown.
+    pub fn set_vblank_only(&mut self, vblank_only: bool) {
+        self.vblank_only = vblank_only;
+        self.image = self.current.clone();
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    // `width * height` levels, row by row.
+    pub fn levels(&self) -> &[f32] {
+        &self.levels
+    }
+    // Takes what the core drew since the last call. Returns whether the
+    // levels changed, which never happens with `vblank_only`.
+    pub fn update(&mut self, sink: &mut VideoSink) -> bool {
+        let rects = sink.take_dirty();
+        if rects.is_empty() {
+            return false;
+        }
+        let lit = sink.take_lit();
+        let buffer = match sink.frame() {
+            Some(buffer) => buffer,
+            None => return false,
+        };
+        // Switching resolution clears the screen, afterglow and all.
+        if (buffer.width, buffer.height) != (self.width, self.height) {
+            let pixels = buffer.width * buffer.height;
+            self.width = buffer.width;
+            self.height = buffer.height;
+            self.current = vec![false; pixels];
+            self.image = vec![false; pixels];
+            self.previous = vec![false; pixels];
+            self.afterglow = vec![0.0; pixels];
+            self.levels = vec![0.0; pixels];
+            self.colors = None;
+            self.dirty = vec![self.whole()];
+        }
+        match (self.colors.as_mut(), buffer.colors.as_ref()) {
+            (Some(colors), Some(board)) => {
+                colors.background = board.background;
+                for rect in &rects {
+                    for y in rect.y..rect.y + rect.height {
+                        let start = y * self.width + rect.x;
+                        let pixels = start..start + rect.width;
+                        colors.foreground[pixels.clone()]
+                            .copy_from_slice(&board.foreground[pixels]);
+                    }
+                }
+            }
+            _ => self.colors = buffer.colors.clone(),
+        }
+        for rect in &rects {
+            for y in rect.y..rect.y + rect.height {
+                let start = y * self.width + rect.x;
+                let pixels = start..start + rect.width;
+                self.current[pixels.clone()].copy_from_slice(&buffer.data[pixels]);
+            }
+        }
+        if self.vblank_only {
+            for (index, pixel) in self.image.iter_mut().enumerate() {
+                *pixel |= self.current[index] || lit.get(index) == Some(&true);
+            }
+            return false;
+        }
+        self.image = self.current.clone();
+        let changed = self.shade();
+        if changed {
+            for &rect in &rects {
+                add_rect(&mut self.dirty, rect);
+            }
+        }
+        self.changed |= changed;
+        changed
+    }
+    // Ends a frame, returning whether the levels changed since the last one.
+    pub fn frame(&mut self) -> bool {
+        // Fading and `vblank_only` frames can change any pixel.
+        let shaded = self.shade();
+        if shaded {
+            let whole = self.whole();
+            add_rect(&mut self.dirty, whole);
+        }
+        let changed = shaded | mem::replace(&mut self.changed, false);
+        self.previous = self.image.clone();
+        for (afterglow, &level) in self.afterglow.iter_mut().zip(self.levels.iter()) {
+            *afterglow = level * self.factor;
+            if *afterglow < DARK {
+                *afterglow = 0.0;
+            }
+        }
+        self.image = self.current.clone();
+        changed
+    }
+    // The rectangles whose levels changed since the last call, together
+    // covering every pixel that did.
+    pub fn take_dirty(&mut self) -> Vec<Rect> {
+        mem::take(&mut self.dirty)
+    }
+    fn whole(&self) -> Rect {
+        Rect {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        }
+    }
+    fn shade(&mut self) -> bool {
+        let persistence = self.persistence;
+        let mut changed = false;
+        let pixels = self
+            .image
+            .iter()
+            .zip(self.previous.iter().zip(self.afterglow.iter()));
+        for (level, (&pixel, (&previous, &afterglow))) in self.levels.iter_mut().zip(pixels) {
+            let lit: f32 = if pixel { 1.0 } else { 0.0 };
+            let new = match persistence {
+                Persistence::Off => lit,
+                Persistence::Blend => (lit + if previous { 1.0 } else { 0.0 }) / 2.0,
+                Persistence::Decay { .. } => lit.max(afterglow),
+            };
+            changed |= new != *level;
+            *level = new;
+        }
+        changed
+    }
+    // Three bytes per pixel, each level mixing `off` into `on`. The CHIP-8X
+    // color board stands in for the colors when it is in use.
+    pub fn to_rgb(&self, on: [u8; 3], off: [u8; 3]) -> Vec<u8> {
+        self.rect_to_rgb(self.whole(), on, off)
+    }
+    // The same for the pixels in `rect` alone, row by row.
+    pub fn rect_to_rgb(&self, rect: Rect, on: [u8; 3], off: [u8; 3]) -> Vec<u8> {
+        let mix = |level: f32, on: [u8; 3], off: [u8; 3]| {
+            let mut rgb = [0; 3];
+            for channel in 0..3 {
+                let (on, off) = (on[channel] as f32, off[channel] as f32);
+                rgb[channel] = (off + (on - off) * level + 0.5) as u8;
+            }
+            rgb
+        };
+        let mut rgb = Vec::with_capacity(rect.width * rect.height * 3);
+        for y in rect.y..rect.y + rect.height {
+            for index in y * self.width + rect.x..y * self.width + rect.x + rect.width {
+                let level = self.levels[index];
+                rgb.extend_from_slice(&match self.colors {
+                    Some(ref colors) => mix(
+                        level,
+                        colors.foreground[index].rgb(),
+                        colors.background.rgb(),
+                    ),
+                    None => mix(level, on, off),
+                });
+            }
+        }
+        rgb
+    }
+}
@@ -0,0 +1,38 @@
+/* INFO:
+    Behaviours that differ between CHIP-8 interpreters. The names match the
+    quirks of the community chip-8-database.
+
+    shift                     8xy6/8xyE shift Vx in place instead of Vy into Vx
+    memory_increment_by_x     Fx55/Fx65 leave I at I + x instead of I + x + 1
+    memory_leave_i_unchanged  Fx55/Fx65 do not touch I
+    wrap                      sprites wrap around the screen edges instead of clipping
+    jump                      Bxnn jumps to xnn + Vx instead of xnn + V0
+    vblank                    Dxyn waits for the vertical blank (not emulated)
+    logic                     8xy1/8xy2/8xy3 reset VF
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quirks {
+    pub shift: bool,
+    pub memory_increment_by_x: bool,
+    pub memory_leave_i_unchanged: bool,
+    pub wrap: bool,
+    pub jump: bool,
+    pub vblank: bool,
+    pub logic: bool,
+}
+
+// What this interpreter has always done.
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks {
+            shift: true,
+            memory_increment_by_x: false,
+            memory_leave_i_unchanged: false,
+            wrap: true,
+            jump: false,
+            vblank: false,
+            logic: false,
+        }
+    }
+}
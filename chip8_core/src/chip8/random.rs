@@ -0,0 +1,52 @@
+use alloc::boxed::Box;
+
+pub trait RandomSource: Send {
+    fn next_u8(&mut self) -> u8;
+    fn box_clone(&self) -> Box<dyn RandomSource>;
+}
+
+impl Clone for Box<dyn RandomSource> {
+    fn clone(&self) -> Box<dyn RandomSource> {
+        self.box_clone()
+    }
+}
+
+// Marsaglia's xorshift32. Small enough for microcontrollers and fully
+// deterministic for a given seed.
+#[derive(Clone)]
+pub struct XorShift {
+    state: u32,
+}
+
+impl Default for XorShift {
+    fn default() -> Self {
+        XorShift::new(0x2545_F491)
+    }
+}
+
+impl XorShift {
+    pub fn new(seed: u32) -> XorShift {
+        XorShift {
+            state: if seed == 0 { 0x2545_F491 } else { seed },
+        }
+    }
+    #[cfg(feature = "std")]
+    pub fn from_entropy() -> XorShift {
+        use rand::{thread_rng, Rng};
+        XorShift::new(thread_rng().gen())
+    }
+}
+
+impl RandomSource for XorShift {
+    fn next_u8(&mut self) -> u8 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        (x >> 24) as u8
+    }
+    fn box_clone(&self) -> Box<dyn RandomSource> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,235 @@
+use alloc::vec::Vec;
+use chip8::vram::{Color, PixelBuffer, HIRES_HEIGHT};
+
+/* INFO:
+    Records the screen into an animated GIF, one `frame` call per 60 Hz
+    tick. GIF delays count hundredths of a second and most viewers slow down
+    anything shorter than two, so a screen that lasts less than that is left
+    out in favour of the one after it; the timing of the rest stays exact.
+    Ticks that change nothing only lengthen the frame before, and a frame
+    longer than the 65535 hundredths a GIF delay holds is repeated.
+*/
+
+// The last LZW code; the table is cleared when it runs out.
+const MAX_CODE: usize = 0xFFF;
+const MIN_DELAY: u64 = 2;
+const MAX_DELAY: u64 = 0xFFFF;
+// GIF sizes are 16 bits, and the largest screen is 64x64.
+pub const MAX_SCALE: usize = 0xFFFF / HIRES_HEIGHT;
+
+// Writes codes least significant bit first, in sub-blocks of up to 255 bytes.
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: usize, size: u32) {
+        self.bits |= (code as u32) << self.count;
+        self.count += size;
+        while self.count >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+    fn finish(mut self, out: &mut Vec<u8>) {
+        if self.count > 0 {
+            self.bytes.push(self.bits as u8);
+        }
+        for block in self.bytes.chunks(0xFF) {
+            out.push(block.len() as u8);
+            out.extend_from_slice(block);
+        }
+        out.push(0);
+    }
+}
+
+// `pixels` are indexes below `1 << min_size`.
+fn lzw(pixels: &[u8], min_size: u32, out: &mut Vec<u8>) {
+    let colors = 1 << min_size;
+    let (clear, end) = (colors, colors + 1);
+    // The code for a code followed by a color, or 0 when there is none yet.
+    let mut table = vec![0u16; (MAX_CODE + 1) * colors];
+    let mut next = end + 1;
+    let mut size = min_size + 1;
+    let mut writer = BitWriter {
+        bytes: Vec::new(),
+        bits: 0,
+        count: 0,
+    };
+    writer.write(clear, size);
+    let mut pixels = pixels.iter().map(|&pixel| pixel as usize);
+    if let Some(mut prefix) = pixels.next() {
+        for pixel in pixels {
+            let entry = prefix * colors + pixel;
+            if table[entry] != 0 {
+                prefix = table[entry] as usize;
+                continue;
+            }
+            writer.write(prefix, size);
+            if next <= MAX_CODE {
+                table[entry] = next as u16;
+                next += 1;
+                // The decoder adds each code one step later.
+                if next - 1 == 1 << size && size < 12 {
+                    size += 1;
+                }
+            } else {
+                writer.write(clear, size);
+                for code in table.iter_mut() {
+                    *code = 0;
+                }
+                next = end + 1;
+                size = min_size + 1;
+            }
+            prefix = pixel;
+        }
+        writer.write(prefix, size);
+    }
+    writer.write(end, size);
+    out.push(min_size as u8);
+    writer.finish(out);
+}
+
+pub struct Recording {
+    scale: usize,
+    // `off`, `on` and the color board's colors, in the global color table.
+    palette: [[u8; 3]; 16],
+    size: Option<(usize, usize)>,
+    out: Vec<u8>,
+    tick: u64,
+    // The frame waiting for its delay, as color indexes, and the tick it
+    // started on.
+    pending: Option<(Vec<u8>, u64)>,
+}
+
+impl Recording {
+    // `None` when `scale` is above `MAX_SCALE`.
+    pub fn new(scale: usize, on: [u8; 3], off: [u8; 3]) -> Option<Recording> {
+        if scale > MAX_SCALE {
+            return None;
+        }
+        let mut palette = [[0; 3]; 16];
+        palette[0] = off;
+        palette[1] = on;
+        for bits in 0..8 {
+            palette[2 + bits] = Color::from_bits(bits as u8).rgb();
+        }
+        Some(Recording {
+            scale: scale.max(1),
+            palette,
+            size: None,
+            out: Vec::new(),
+            tick: 0,
+            pending: None,
+        })
+    }
+    pub fn frames(&self) -> u64 {
+        self.tick
+    }
+    // The screen for this tick. The first one decides the size; later ones
+    // of another size, after a switch to hi-res, are left out, as are screens
+    // too large for a GIF.
+    pub fn frame(&mut self, buffer: &PixelBuffer) {
+        let (width, height) = (buffer.width * self.scale, buffer.height * self.scale);
+        if self.size.is_none() && width <= 0xFFFF && height <= 0xFFFF {
+            self.header(width, height);
+        }
+        if self.size == Some((width, height)) {
+            let indexes = self.indexes(buffer);
+            match self.pending.take() {
+                Some((pending, start)) if pending == indexes => {
+                    self.pending = Some((pending, start))
+                }
+                Some((pending, start)) => {
+                    if centiseconds(self.tick) - centiseconds(start) >= MIN_DELAY {
+                        self.image(&pending, start, self.tick);
+                        self.pending = Some((indexes, self.tick));
+                    } else {
+                        self.pending = Some((indexes, start));
+                    }
+                }
+                None => self.pending = Some((indexes, self.tick)),
+            }
+        }
+        self.tick += 1;
+    }
+    // The GIF, or `None` when no frame was recorded.
+    pub fn finish(mut self) -> Option<Vec<u8>> {
+        let (pending, start) = self.pending.take()?;
+        let end = self.tick.max(start + 1);
+        self.image(&pending, start, end);
+        self.out.push(0x3B);
+        Some(self.out)
+    }
+    fn header(&mut self, width: usize, height: usize) {
+        self.size = Some((width, height));
+        self.out.extend_from_slice(b"GIF89a");
+        self.out.extend_from_slice(&(width as u16).to_le_bytes());
+        self.out.extend_from_slice(&(height as u16).to_le_bytes());
+        // A global color table of 16 colors, background color 0
+        self.out.extend_from_slice(&[0xF3, 0, 0]);
+        for color in self.palette.iter() {
+            self.out.extend_from_slice(color);
+        }
+        // Loop forever
+        self.out
+            .extend_from_slice(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+    }
+    fn indexes(&self, buffer: &PixelBuffer) -> Vec<u8> {
+        let index = |color: Color| 2 + color as u8;
+        let row_indexes = |row: usize| -> Vec<u8> {
+            (0..buffer.width)
+                .map(|x| {
+                    let pixel = row * buffer.width + x;
+                    let lit = buffer.data[pixel];
+                    match buffer.colors {
+                        Some(ref colors) if lit => index(colors.foreground[pixel]),
+                        Some(ref colors) => index(colors.background),
+                        None => lit as u8,
+                    }
+                })
+                .flat_map(|index| core::iter::repeat_n(index, self.scale))
+                .collect()
+        };
+        let mut indexes = Vec::with_capacity(buffer.data.len() * self.scale * self.scale);
+        for row in 0..buffer.height {
+            let row = row_indexes(row);
+            for _ in 0..self.scale {
+                indexes.extend_from_slice(&row);
+            }
+        }
+        indexes
+    }
+    fn image(&mut self, indexes: &[u8], start: u64, end: u64) {
+        let (width, height) = self.size.unwrap();
+        let mut data = Vec::new();
+        lzw(indexes, 4, &mut data);
+        let mut left = centiseconds(end) - centiseconds(start);
+        loop {
+            let delay = left.min(MAX_DELAY);
+            left -= delay;
+            // Graphic control extension with the delay
+            self.out.extend_from_slice(&[0x21, 0xF9, 0x04, 0x00]);
+            self.out.extend_from_slice(&(delay as u16).to_le_bytes());
+            self.out.extend_from_slice(&[0x00, 0x00]);
+            // Image descriptor covering the whole screen
+            self.out.push(0x2C);
+            self.out.extend_from_slice(&[0, 0, 0, 0]);
+            self.out.extend_from_slice(&(width as u16).to_le_bytes());
+            self.out.extend_from_slice(&(height as u16).to_le_bytes());
+            self.out.push(0);
+            self.out.extend_from_slice(&data);
+            if left == 0 {
+                break;
+            }
+        }
+    }
+}
+
+// When `tick` starts, rounded to hundredths of a second.
+fn centiseconds(tick: u64) -> u64 {
+    (tick * 100 + 30) / 60
+}
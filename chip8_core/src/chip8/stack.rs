@@ -1,8 +1,11 @@
+use alloc::vec::Vec;
+use chip8::state::{write_u16, Reader, StateError};
 use chip8::Address;
-use std::fmt;
+use core::fmt;
 
 const FRAME_COUNT: usize = 16;
 
+#[derive(Clone, PartialEq)]
 pub struct Stack {
     frames: [u16; FRAME_COUNT],
     sp: u8,
@@ -26,12 +29,30 @@ impl Stack {
             sp: 0,
         }
     }
+    pub fn sp(&self) -> u8 {
+        self.sp
+    }
+    // The stack is a ring: pushing a 17th frame overwrites the oldest one
+    // and popping an empty stack returns whatever is in the last slot.
     pub fn push(&mut self, addr: Address) {
         self.frames[self.sp as usize] = addr;
-        self.sp += 1;
+        self.sp = (self.sp + 1) % FRAME_COUNT as u8;
     }
     pub fn pop(&mut self) -> Address {
-        self.sp -= 1;
+        self.sp = self.sp.checked_sub(1).unwrap_or(FRAME_COUNT as u8 - 1);
         self.frames[self.sp as usize]
     }
+    pub fn save_state(&self, out: &mut Vec<u8>) {
+        for frame in self.frames.iter() {
+            write_u16(out, *frame);
+        }
+        out.push(self.sp);
+    }
+    pub fn load_state(&mut self, reader: &mut Reader) -> Result<(), StateError> {
+        for frame in self.frames.iter_mut() {
+            *frame = reader.u16()?;
+        }
+        self.sp = reader.u8()? % FRAME_COUNT as u8;
+        Ok(())
+    }
 }
@@ -0,0 +1,44 @@
+use alloc::vec::Vec;
+
+pub const MAGIC: &[u8; 4] = b"C8ST";
+pub const VERSION: u8 = 5;
+
+#[derive(Debug, PartialEq)]
+pub enum StateError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    BadVariant(u8),
+    // A snapshot of the VIP loaded into a CHIP-8 backend, or the other way.
+    WrongBackend,
+    Truncated,
+}
+
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data }
+    }
+    pub fn bytes(&mut self, count: usize) -> Result<&'a [u8], StateError> {
+        if self.data.len() < count {
+            return Err(StateError::Truncated);
+        }
+        let (bytes, rest) = self.data.split_at(count);
+        self.data = rest;
+        Ok(bytes)
+    }
+    pub fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.bytes(1)?[0])
+    }
+    pub fn u16(&mut self) -> Result<u16, StateError> {
+        let bytes = self.bytes(2)?;
+        Ok((bytes[0] as u16) << 8 | (bytes[1] as u16))
+    }
+}
+
+pub fn write_u16(out: &mut Vec<u8>, value: u16) {
+    out.push((value >> 8) as u8);
+    out.push(value as u8);
+}
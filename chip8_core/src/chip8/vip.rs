@@ -0,0 +1,399 @@
+use alloc::vec::Vec;
+use cdp1802::{Bus, Cdp1802};
+use chip8::audio::{AudioEvent, AudioSink};
+use chip8::keyboard::{HexKey, Keyboard};
+use chip8::state::{write_u16, Reader, StateError};
+use chip8::vram::{VideoSink, Vram, HIRES_HEIGHT, WIDTH};
+use chip8::Registers;
+use program::{Program, ProgramError};
+
+/* INFO:
+    The whole COSMAC VIP: a CDP1802 at 1.76 MHz, 4K of RAM from 0x000, the
+    monitor ROM at 0x8000, the CDP1861 "Pixie" and the hex keypad. The CHIP-8
+    interpreter is the VIP's own 512 bytes at 0x000-0x1FF, supplied by the
+    user, with the program after it at 0x200.
+
+    The 1861 draws 262 lines of 14 machine cycles, 3668 cycles a frame. On
+    the 128 display lines from line 80 it takes 8 of those cycles for DMA,
+    fetching the 64 pixels of the line from R0, and leaves the CPU 6. It
+    interrupts two lines before the display starts and raises EF1 on the 4
+    lines before the display and the last 4 lines of it.
+
+    The interpreter points R1 at the monitor's interrupt routine at 0x8146,
+    which shows every display byte row on 4 lines and counts down the
+    timers in R8. The monitor itself is not ours to ship, so the ROM holds
+    our own routine at the same address, timed to the same lines:
+
+    8144  72        LDXA          restore D
+    8145  70        RET           restore X and P, enable interrupts
+    8146  22 78     DEC R2, SAV   the interrupt, saving T
+    8148  22 52     DEC R2, STR   and D
+    814A  C4 C4 C4  NOP x3        wait out lines 78 and 79
+    814D  E2        SEX R2
+    814E  9B B0     GHI RB, PHI   R0 to the display page
+    8150  F8 00 A0  LDI 00, PLO
+    8153  80 E2 E2  GLO R0        each line is 3 instructions then DMA,
+    8156  20 A0 E2  DEC R0, PLO   which moves R0 on 8 bytes; three of the
+    8159  20 A0 E2                four lines put it back
+    815C  20 A0
+    815E  3C 53     BN1 8153      until the last 4 lines
+    8160  98 32 66  GHI R8, BZ    the delay timer
+    8163  FF 01 B8  SMI 01, PHI
+    8166  88 32 70  GLO R8, BZ    the sound timer, with Q on while it runs
+    8169  FF 01 A8  SMI 01, PLO
+    816C  32 70     BZ 8170
+    816E  7B 38     SEQ, SKP
+    8170  7A        REQ
+    8171  30 44     BR 8144
+
+    Fx0A calls the monitor's keypad routine at 0x8195 with SEP RC, and takes
+    the key from D once it returns with SEP R3. Ours scans the keys through
+    the byte the interpreter frees on the stack, keeping the key in D since
+    an interrupt right after OUT writes over that byte:
+
+    8195  F8 00     LDI 00        key 0
+    8197  52 62 22  STR, OUT 2    latch the key
+                    DEC R2
+    819A  36 A2     B3 81A2       held?
+    819C  FC 01     ADI 01        or the next key
+    819E  FA 0F     ANI 0F
+    81A0  30 97     BR 8197
+    81A2  36 A2     B3 81A2       wait for it to be let go
+    81A4  D3        SEP R3
+*/
+pub const RAM_SIZE: usize = 0x1000;
+pub const INTERPRETER_SIZE: usize = 0x200;
+// `Chip8::run` counts VIP time in fifteenths of a frame, about as many CHIP-8
+// instructions as the VIP interpreter gets through in one.
+pub const CYCLES_PER_FRAME: usize = 15;
+
+const MONITOR: u16 = 0x8000;
+const MONITOR_SIZE: usize = 0x200;
+const INTERRUPT_ROUTINE: u16 = 0x144;
+const INTERRUPT: [u8; 0x2F] = [
+    0x72, 0x70, 0x22, 0x78, 0x22, 0x52, 0xC4, 0xC4, 0xC4, 0xE2, 0x9B, 0xB0, 0xF8, 0x00, 0xA0, 0x80,
+    0xE2, 0xE2, 0x20, 0xA0, 0xE2, 0x20, 0xA0, 0xE2, 0x20, 0xA0, 0x3C, 0x53, 0x98, 0x32, 0x66, 0xFF,
+    0x01, 0xB8, 0x88, 0x32, 0x70, 0xFF, 0x01, 0xA8, 0x32, 0x70, 0x7B, 0x38, 0x7A, 0x30, 0x44,
+];
+
+const KEYPAD_ROUTINE: u16 = 0x195;
+const KEYPAD: [u8; 0x10] = [
+    0xF8, 0x00, 0x52, 0x62, 0x22, 0x36, 0xA2, 0xFC, 0x01, 0xFA, 0x0F, 0x30, 0x97, 0x36, 0xA2, 0xD3,
+];
+
+const LINE_CYCLES: u32 = 14;
+const FRAME_LINES: u16 = 262;
+const INTERRUPT_LINE: u16 = 78;
+const FIRST_LINE: u16 = 80;
+const DISPLAY_LINES: u16 = 128;
+const DMA_CYCLES: u32 = 8;
+// Where the interpreter keeps V0-VF and starts its stack, with 4K of RAM.
+const REGISTERS: usize = 0xEF0;
+const STACK: u16 = 0xECF;
+
+struct VipBus<'a> {
+    ram: &'a mut [u8],
+    monitor: &'a [u8],
+    keyboard: &'a Keyboard,
+    key: &'a mut u8,
+    display: &'a mut bool,
+    ef1: bool,
+}
+
+impl<'a> Bus for VipBus<'a> {
+    // RAM repeats up to the monitor, which repeats to the top of memory.
+    fn read(&mut self, address: u16) -> u8 {
+        if address < MONITOR {
+            self.ram[address as usize % RAM_SIZE]
+        } else {
+            self.monitor[address as usize % MONITOR_SIZE]
+        }
+    }
+    fn write(&mut self, address: u16, value: u8) {
+        if address < MONITOR {
+            self.ram[address as usize % RAM_SIZE] = value;
+        }
+    }
+    // OUT 1 turns the display off and OUT 2 latches the key that EF3
+    // reports on.
+    fn output(&mut self, port: u8, value: u8) {
+        match port {
+            1 => *self.display = false,
+            2 => *self.key = value & 0xF,
+            _ => {}
+        }
+    }
+    // INP 1 turns the display on.
+    fn input(&mut self, port: u8) -> u8 {
+        if port == 1 {
+            *self.display = true;
+        }
+        0
+    }
+    fn ef(&mut self, flag: u8) -> bool {
+        match flag {
+            1 => self.ef1,
+            3 => self.keyboard.get_pressed(HexKey::from(*self.key)),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Vip {
+    cpu: Cdp1802,
+    ram: Vec<u8>,
+    monitor: Vec<u8>,
+    interpreter: Vec<u8>,
+    display: bool,
+    key: u8,
+    interrupt: bool,
+    // Whether the display was on for this frame's interrupt. A display
+    // turned on later draws from wherever R0 happens to point.
+    synced: bool,
+    line: u16,
+    // Machine cycles the CPU has already run into the current line.
+    cycle: u32,
+    // VIP time owed by `run`, in fifteenths of a machine cycle.
+    credit: u32,
+    // The bytes DMA fetched for each display line this frame.
+    lines: Vec<u8>,
+    // Set once a frame shows rows on 2 lines instead of 4, and kept until
+    // the next program is loaded.
+    hires: bool,
+    vram: Vram,
+}
+
+impl Default for Vip {
+    fn default() -> Self {
+        let mut monitor = vec![0; MONITOR_SIZE];
+        let start = INTERRUPT_ROUTINE as usize;
+        monitor[start..start + INTERRUPT.len()].copy_from_slice(&INTERRUPT);
+        let start = KEYPAD_ROUTINE as usize;
+        monitor[start..start + KEYPAD.len()].copy_from_slice(&KEYPAD);
+        let mut vip = Vip {
+            cpu: Cdp1802::new(),
+            ram: vec![0; RAM_SIZE],
+            monitor,
+            interpreter: Vec::new(),
+            display: false,
+            key: 0,
+            interrupt: false,
+            synced: false,
+            line: 0,
+            cycle: 0,
+            credit: 0,
+            lines: vec![0; DISPLAY_LINES as usize * WIDTH / 8],
+            hires: false,
+            vram: Vram::new(),
+        };
+        vip.reset();
+        vip
+    }
+}
+
+impl Vip {
+    pub fn new() -> Vip {
+        Self::default()
+    }
+    pub fn set_interpreter(&mut self, image: &[u8]) -> Result<(), ProgramError> {
+        if image.len() > INTERPRETER_SIZE {
+            return Err(ProgramError::TooLarge {
+                size: image.len(),
+                max: INTERPRETER_SIZE,
+            });
+        }
+        self.interpreter = image.to_vec();
+        Ok(())
+    }
+    pub fn load_program(&mut self, program: &Program) {
+        self.reset();
+        let start = program.load_address as usize % RAM_SIZE;
+        for (cell, &byte) in self.ram[start..].iter_mut().zip(program.data.iter()) {
+            *cell = byte;
+        }
+    }
+    // Starts the interpreter the way the monitor does when RUN is switched
+    // on: from 0x000, with the last RAM page in R1.1.
+    fn reset(&mut self) {
+        self.cpu = Cdp1802::new();
+        self.cpu.r[1] = ((RAM_SIZE >> 8) as u16 - 1) << 8;
+        self.ram = vec![0; RAM_SIZE];
+        self.ram[..self.interpreter.len()].copy_from_slice(&self.interpreter);
+        self.display = false;
+        self.key = 0;
+        self.interrupt = false;
+        self.synced = false;
+        self.line = 0;
+        self.cycle = 0;
+        self.credit = 0;
+        self.lines = vec![0; self.lines.len()];
+        self.hires = false;
+        self.vram = Vram::new();
+    }
+    pub fn memory(&self) -> &[u8] {
+        &self.ram
+    }
+    pub fn write_memory(&mut self, address: u16, value: u8) {
+        self.ram[address as usize % RAM_SIZE] = value;
+    }
+    pub fn vram(&self) -> &Vram {
+        &self.vram
+    }
+    // Read back from where the interpreter keeps them.
+    pub fn registers(&self) -> Registers {
+        let mut v = [0; 0x10];
+        v.copy_from_slice(&self.ram[REGISTERS..REGISTERS + 0x10]);
+        let r = &self.cpu.r;
+        Registers {
+            v,
+            i: r[0xA],
+            pc: r[0x5],
+            sp: (STACK.saturating_sub(r[0x2]) / 2) as u8,
+            delay_timer: (r[0x8] >> 8) as u8,
+            sound_timer: r[0x8] as u8,
+        }
+    }
+    pub fn run(
+        &mut self,
+        cycles: usize,
+        keyboard: &Keyboard,
+        video_sink: &mut VideoSink,
+        audio_sink: &mut AudioSink,
+    ) {
+        let q = self.cpu.q;
+        let frame_cycles = LINE_CYCLES * FRAME_LINES as u32;
+        let line_credit = LINE_CYCLES * CYCLES_PER_FRAME as u32;
+        for _ in 0..cycles {
+            self.credit += frame_cycles;
+            while self.credit >= line_credit {
+                self.credit -= line_credit;
+                self.run_line(keyboard, video_sink);
+            }
+        }
+        if self.cpu.q != q {
+            audio_sink.event = Some(if self.cpu.q {
+                AudioEvent::Play
+            } else {
+                AudioEvent::Stop
+            });
+        }
+    }
+    // The CPU runs whole instructions, so one that crosses the end of its
+    // share of a line pushes back the DMA and eats into the next line.
+    fn run_line(&mut self, keyboard: &Keyboard, video_sink: &mut VideoSink) {
+        let line = self.line;
+        let display = self.display && (FIRST_LINE..FIRST_LINE + DISPLAY_LINES).contains(&line);
+        if line == INTERRUPT_LINE {
+            self.interrupt = self.display;
+            self.synced = self.display;
+        } else if line == FIRST_LINE {
+            self.interrupt = false;
+        }
+        let last = FIRST_LINE + DISPLAY_LINES - 4;
+        let budget = if display {
+            LINE_CYCLES - DMA_CYCLES
+        } else {
+            LINE_CYCLES
+        };
+        let mut bus = VipBus {
+            ram: &mut self.ram,
+            monitor: &self.monitor,
+            keyboard,
+            key: &mut self.key,
+            display: &mut self.display,
+            ef1: (FIRST_LINE - 4..FIRST_LINE).contains(&line) || (line >= last && display),
+        };
+        while self.cycle < budget {
+            if self.interrupt && self.cpu.interrupt() {
+                self.interrupt = false;
+                self.cycle += 1;
+            } else {
+                self.cycle += self.cpu.step(&mut bus);
+            }
+        }
+        if display {
+            let start = (line - FIRST_LINE) as usize * WIDTH / 8;
+            for byte in self.lines[start..start + WIDTH / 8].iter_mut() {
+                *byte = self.cpu.dma_out(&mut bus);
+            }
+            self.cycle += DMA_CYCLES;
+        }
+        self.cycle -= LINE_CYCLES;
+        self.line += 1;
+        if self.line == FRAME_LINES {
+            self.line = 0;
+            self.end_frame(video_sink);
+        }
+    }
+    // Samples the first line of every row into the vram. The last row is
+    // left out of the hi-res check, as its lines can run past the page when
+    // the interrupt comes in late.
+    fn end_frame(&mut self, video_sink: &mut VideoSink) {
+        let bytes = WIDTH / 8;
+        let lines = &self.lines;
+        let line = |index: usize| &lines[index * bytes..(index + 1) * bytes];
+        let rows = DISPLAY_LINES as usize / 4;
+        self.hires = self.hires
+            || self.synced && (0..rows - 1).any(|row| line(row * 4) != line(row * 4 + 2));
+        let (rows, repeat) = if self.hires {
+            (HIRES_HEIGHT, 2)
+        } else {
+            (rows, 4)
+        };
+        let screen: Vec<u8> = (0..rows)
+            .flat_map(|row| line(row * repeat).iter().cloned())
+            .collect();
+        if self.vram.height() != rows {
+            self.vram.set_hires(self.hires);
+        }
+        self.vram.load_bytes(&screen);
+        self.vram.flush(video_sink);
+        for byte in self.lines.iter_mut() {
+            *byte = 0;
+        }
+    }
+    pub fn save_state(&self, out: &mut Vec<u8>) {
+        let cpu = &self.cpu;
+        for &register in cpu.r.iter() {
+            write_u16(out, register);
+        }
+        out.extend_from_slice(&[cpu.p, cpu.x, cpu.d, cpu.df as u8, cpu.t]);
+        out.extend_from_slice(&[cpu.ie as u8, cpu.q as u8, cpu.idle as u8]);
+        out.extend_from_slice(&self.ram);
+        out.extend_from_slice(&[self.display as u8, self.key]);
+        out.extend_from_slice(&[self.interrupt as u8, self.synced as u8]);
+        write_u16(out, self.line);
+        out.push(self.cycle as u8);
+        write_u16(out, self.credit as u16);
+        out.push(self.hires as u8);
+        out.extend_from_slice(&self.lines);
+        self.vram.save_state(out);
+    }
+    pub fn load_state(&mut self, reader: &mut Reader) -> Result<(), StateError> {
+        let cpu = &mut self.cpu;
+        for register in cpu.r.iter_mut() {
+            *register = reader.u16()?;
+        }
+        cpu.p = reader.u8()? & 0xF;
+        cpu.x = reader.u8()? & 0xF;
+        cpu.d = reader.u8()?;
+        cpu.df = reader.u8()? != 0;
+        cpu.t = reader.u8()?;
+        cpu.ie = reader.u8()? != 0;
+        cpu.q = reader.u8()? != 0;
+        cpu.idle = reader.u8()? != 0;
+        self.ram.copy_from_slice(reader.bytes(RAM_SIZE)?);
+        self.display = reader.u8()? != 0;
+        self.key = reader.u8()? & 0xF;
+        self.interrupt = reader.u8()? != 0;
+        self.synced = reader.u8()? != 0;
+        self.line = reader.u16()? % FRAME_LINES;
+        self.cycle = reader.u8()? as u32;
+        self.credit = reader.u16()? as u32;
+        self.hires = reader.u8()? != 0;
+        let len = self.lines.len();
+        self.lines.copy_from_slice(reader.bytes(len)?);
+        self.vram.load_state(reader)
+    }
+}
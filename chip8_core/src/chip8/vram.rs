@@ -1,37 +1,326 @@
-use chip8::memory::Memory;
-use chip8::Address;
-use std::fmt;
+use alloc::vec::Vec;
+use chip8::state::{Reader, StateError};
+use core::fmt;
+use core::mem;
 
 pub const WIDTH: usize = 64;
 pub const HEIGHT: usize = 32;
+// The COSMAC VIP two-page hi-res mode doubles the number of rows.
+pub const HIRES_HEIGHT: usize = 64;
 const SPRITE_WIDTH: usize = 8;
+
+/* INFO:
+    CHIP-8X runs on a VIP fitted with the CDP1862 color board. The board
+    colors the screen in zones one byte wide and one row high, and has a
+    single background color that 02A0 steps through blue, black, green, red.
+    Zones start out red on a blue background.
+*/
+pub const ZONE_WIDTH: usize = 8;
+pub const BACKGROUNDS: [Color; 4] = [Color::Blue, Color::Black, Color::Green, Color::Red];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+    Black,
+    Red,
+    Blue,
+    Violet,
+    Green,
+    Yellow,
+    Aqua,
+    White,
+}
+
+impl Color {
+    // Bit 0 drives red, bit 1 blue and bit 2 green.
+    pub fn from_bits(bits: u8) -> Color {
+        match bits & 0x7 {
+            0 => Color::Black,
+            1 => Color::Red,
+            2 => Color::Blue,
+            3 => Color::Violet,
+            4 => Color::Green,
+            5 => Color::Yellow,
+            6 => Color::Aqua,
+            _ => Color::White,
+        }
+    }
+    pub fn rgb(&self) -> [u8; 3] {
+        let bits = *self as u8;
+        let level = |bit: u8| if bits & bit != 0 { 0xFF } else { 0 };
+        [level(1), level(4), level(2)]
+    }
+}
+
+// The color board state, with one foreground color per pixel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorMap {
+    pub background: Color,
+    pub foreground: Vec<Color>,
+}
+
+// `data` holds `width * height` pixels, row by row. `colors` is only set
+// when the CHIP-8X color board is in use.
+#[derive(Clone)]
 pub struct PixelBuffer {
-    pub data: [bool; WIDTH * HEIGHT],
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<bool>,
+    pub colors: Option<ColorMap>,
+}
+
+impl PixelBuffer {
+    // Three bytes per pixel, `on` and `off` standing in for the colors when
+    // there is no color board.
+    pub fn to_rgb(&self, on: [u8; 3], off: [u8; 3]) -> Vec<u8> {
+        match self.colors {
+            Some(ref colors) => self
+                .data
+                .iter()
+                .zip(colors.foreground.iter())
+                .flat_map(|(&pixel, foreground)| {
+                    if pixel {
+                        foreground.rgb()
+                    } else {
+                        colors.background.rgb()
+                    }
+                })
+                .collect(),
+            None => self
+                .data
+                .iter()
+                .flat_map(|&pixel| if pixel { on } else { off })
+                .collect(),
+        }
+    }
+}
+
+/* INFO:
+    The sink keeps its own copy of the screen. `Vram` tells it which
+    rectangles changed, and only those pixels are copied over, so a sprite
+    costs its own size rather than the whole screen. Hosts either `get` the
+    whole screen, or `take_dirty` the rectangles and read them out of
+    `frame`; both only report changes made since the last call to either.
+    The generation counts the changes, for hosts that poll.
+*/
+
+// Beyond this many rectangles they are merged into one around them all.
+const MAX_RECTS: usize = 16;
+
+// An area of the screen, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    // The smallest rectangle covering both.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let (x, y) = (self.x.min(other.x), self.y.min(other.y));
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+        Rect {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        }
+    }
+    // Whether the two overlap or share an edge.
+    fn touches(&self, other: &Rect) -> bool {
+        self.x <= other.x + other.width
+            && other.x <= self.x + self.width
+            && self.y <= other.y + other.height
+            && other.y <= self.y + self.height
+    }
+    fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+}
+
+// Adds `rect`, merged with those it touches.
+pub(crate) fn add_rect(rects: &mut Vec<Rect>, mut rect: Rect) {
+    if rect.is_empty() {
+        return;
+    }
+    let mut index = 0;
+    while index < rects.len() {
+        if rects[index].touches(&rect) {
+            rect = rect.union(&rects.swap_remove(index));
+            index = 0;
+        } else {
+            index += 1;
+        }
+    }
+    rects.push(rect);
+    if rects.len() > MAX_RECTS {
+        let all = rects.iter().fold(rect, |all, rect| all.union(rect));
+        rects.clear();
+        rects.push(all);
+    }
+}
+
+// The parts of `length` pixels from `start` on a line `size` pixels long,
+// as (start, length). The part past the end comes around to the start when
+// `wrap` is set and is left out otherwise.
+fn spans(start: usize, length: usize, size: usize, wrap: bool) -> Vec<(usize, usize)> {
+    let length = length.min(size);
+    if start + length <= size {
+        vec![(start, length)]
+    } else if wrap {
+        vec![(start, size - start), (0, start + length - size)]
+    } else {
+        vec![(start, size - start)]
+    }
 }
 
 #[derive(Default)]
 pub struct VideoSink {
-    pub buffer: Option<PixelBuffer>,
+    frame: Option<PixelBuffer>,
+    generation: u64,
+    // Whether the frame changed since the last `get` or `take_dirty`.
+    fresh: bool,
+    dirty: Vec<Rect>,
+    // Every pixel lit in a buffer pushed since the last `take_lit`.
+    lit: Vec<bool>,
 }
 
 impl VideoSink {
     pub fn new() -> VideoSink {
-        VideoSink { buffer: None }
+        VideoSink::default()
+    }
+    // Replaces the whole screen.
+    pub fn push(&mut self, buffer: PixelBuffer) {
+        if self.lit.len() == buffer.data.len() {
+            for (lit, &pixel) in self.lit.iter_mut().zip(buffer.data.iter()) {
+                *lit |= pixel;
+            }
+        } else {
+            self.lit = buffer.data.clone();
+        }
+        let whole = Rect {
+            x: 0,
+            y: 0,
+            width: buffer.width,
+            height: buffer.height,
+        };
+        self.frame = Some(buffer);
+        self.dirty.clear();
+        self.changed(whole);
+    }
+    // Copies `rect` over from `vram`, or all of it when the size or the
+    // color board changed.
+    fn update(&mut self, vram: &Vram, rect: Rect) {
+        let frame = match self.frame {
+            Some(ref mut frame)
+                if (frame.width, frame.height) == (vram.width, vram.height)
+                    && frame.colors.is_some() == vram.colors.is_some() =>
+            {
+                frame
+            }
+            _ => {
+                self.push(vram.pixel_buffer());
+                return;
+            }
+        };
+        let zones_across = vram.width / ZONE_WIDTH;
+        let tracking = self.lit.len() == frame.data.len();
+        for y in rect.y..rect.y + rect.height {
+            let start = y * vram.width + rect.x;
+            let pixels = start..start + rect.width;
+            frame.data[pixels.clone()].copy_from_slice(&vram.data[pixels.clone()]);
+            if tracking {
+                for (lit, &pixel) in self.lit[pixels.clone()]
+                    .iter_mut()
+                    .zip(&vram.data[pixels.clone()])
+                {
+                    *lit |= pixel;
+                }
+            }
+            if let (Some(colors), Some(board)) = (frame.colors.as_mut(), vram.colors.as_ref()) {
+                for x in rect.x..rect.x + rect.width {
+                    colors.foreground[y * vram.width + x] =
+                        board.zones[y * zones_across + x / ZONE_WIDTH];
+                }
+            }
+        }
+        if let (Some(colors), Some(board)) = (frame.colors.as_mut(), vram.colors.as_ref()) {
+            colors.background = BACKGROUNDS[board.background];
+        }
+        if !tracking {
+            self.lit = frame.data.clone();
+        }
+        self.changed(rect);
+    }
+    fn changed(&mut self, rect: Rect) {
+        add_rect(&mut self.dirty, rect);
+        self.generation += 1;
+        self.fresh = true;
     }
+    // The whole screen, when it changed since the last `get` or `take_dirty`.
     pub fn get(&mut self) -> Option<PixelBuffer> {
-        self.buffer.take()
+        if !self.fresh {
+            return None;
+        }
+        self.fresh = false;
+        self.dirty.clear();
+        self.frame.clone()
+    }
+    // The screen as it is now, changed or not.
+    pub fn frame(&self) -> Option<&PixelBuffer> {
+        self.frame.as_ref()
+    }
+    // The rectangles of `frame` that changed since the last `get` or
+    // `take_dirty`, none of them overlapping.
+    pub fn take_dirty(&mut self) -> Vec<Rect> {
+        self.fresh = false;
+        mem::take(&mut self.dirty)
+    }
+    // How many changes came in so far.
+    pub fn generation(&self) -> u64 {
+        self.generation
     }
+    // Catches sprites that were drawn and erased again between two `get`s.
+    // Empty when nothing was pushed since the last call.
+    pub fn take_lit(&mut self) -> Vec<bool> {
+        mem::take(&mut self.lit)
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct ColorBoard {
+    background: usize,
+    // One color per zone, row by row.
+    zones: Vec<Color>,
 }
 
+#[derive(Clone)]
 pub struct Vram {
-    data: [bool; WIDTH * HEIGHT],
+    width: usize,
+    height: usize,
+    data: Vec<bool>,
+    colors: Option<ColorBoard>,
+    generation: u64,
+    // Changed since the last notice to a sink.
+    dirty: Vec<Rect>,
+}
+
+// Two screens are equal when they show the same, whatever their history.
+impl PartialEq for Vram {
+    fn eq(&self, other: &Vram) -> bool {
+        (self.width, self.height) == (other.width, other.height)
+            && self.data == other.data
+            && self.colors == other.colors
+    }
 }
 
 impl fmt::Debug for Vram {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for row in 0..HEIGHT {
-            for col in 0..WIDTH {
-                let pixel = &self.data[row * HEIGHT + col];
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let pixel = &self.data[row * self.width + col];
                 if *pixel {
                     write!(f, "#")?;
                 } else {
@@ -47,7 +336,12 @@ impl fmt::Debug for Vram {
 impl Default for Vram {
     fn default() -> Self {
         Vram {
-            data: [false; WIDTH * HEIGHT],
+            width: WIDTH,
+            height: HEIGHT,
+            data: vec![false; WIDTH * HEIGHT],
+            colors: None,
+            generation: 0,
+            dirty: Vec::new(),
         }
     }
 }
@@ -56,25 +350,212 @@ impl Vram {
     pub fn new() -> Vram {
         Self::default()
     }
-    pub fn clear(&mut self) {
-        self.data = [false; WIDTH * HEIGHT];
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    // How many changes were made to the screen so far.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+    fn whole(&self) -> Rect {
+        Rect {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        }
+    }
+    fn touch(&mut self, rects: &[Rect]) {
+        for &rect in rects {
+            add_rect(&mut self.dirty, rect);
+        }
+        self.generation += 1;
+    }
+    // Tells `sink` about the changes made since the last time. Changes made
+    // without a sink at hand, like switching to hi-res, wait for this.
+    pub fn flush(&mut self, sink: &mut VideoSink) {
+        for rect in mem::take(&mut self.dirty) {
+            sink.update(self, rect);
+        }
+    }
+    // Switches between 64x32 and the 64x64 hi-res mode, clearing the screen.
+    pub fn set_hires(&mut self, hires: bool) {
+        self.height = if hires { HIRES_HEIGHT } else { HEIGHT };
+        self.data = vec![false; self.width * self.height];
+        // The whole screen is dirty now, and rectangles of the old size may
+        // not fit.
+        self.dirty.clear();
+        let enabled = self.colors.is_some();
+        self.set_color_board(enabled);
+    }
+    // Fits or removes the CHIP-8X color board, resetting its colors.
+    pub fn set_color_board(&mut self, enabled: bool) {
+        self.colors = if enabled {
+            Some(ColorBoard {
+                background: 0,
+                zones: vec![Color::Red; self.width / ZONE_WIDTH * self.height],
+            })
+        } else {
+            None
+        };
+        let whole = self.whole();
+        self.touch(&[whole]);
+    }
+    pub fn cycle_background(&mut self, sink: &mut VideoSink) {
+        if let Some(ref mut colors) = self.colors {
+            colors.background = (colors.background + 1) % BACKGROUNDS.len();
+        }
+        let whole = self.whole();
+        self.touch(&[whole]);
+        self.flush(sink);
+    }
+    // Colors `columns` zones across and `rows` rows down from the zone at
+    // (`column`, `row`), wrapping around the screen edges.
+    pub fn color_zones(
+        &mut self,
+        column: usize,
+        row: usize,
+        columns: usize,
+        rows: usize,
+        color: Color,
+        sink: &mut VideoSink,
+    ) {
+        let (zones_across, height) = (self.width / ZONE_WIDTH, self.height);
+        if let Some(ref mut colors) = self.colors {
+            for y in row..row + rows {
+                for x in column..column + columns {
+                    colors.zones[(y % height) * zones_across + x % zones_across] = color;
+                }
+            }
+        }
+        let mut rects = Vec::new();
+        for (y, rows) in spans(row % height, rows, height, true) {
+            for (x, columns) in spans(column % zones_across, columns, zones_across, true) {
+                rects.push(Rect {
+                    x: x * ZONE_WIDTH,
+                    y,
+                    width: columns * ZONE_WIDTH,
+                    height: rows,
+                });
+            }
+        }
+        self.touch(&rects);
+        self.flush(sink);
+    }
+    // One bit per pixel, eight pixels to a byte with the leftmost in bit 7,
+    // the way the VIP keeps its display page.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.data
+            .chunks(8)
+            .map(|pixels| {
+                pixels
+                    .iter()
+                    .fold(0, |byte, &pixel| (byte << 1) | pixel as u8)
+            })
+            .collect()
     }
+    // Only the rows that changed count as dirty.
+    pub fn load_bytes(&mut self, bytes: &[u8]) {
+        let mut rows: Option<(usize, usize)> = None;
+        for (index, (pixels, byte)) in self.data.chunks_mut(8).zip(bytes.iter()).enumerate() {
+            for (bit, pixel) in pixels.iter_mut().enumerate() {
+                let lit = (byte >> (7 - bit)) & 1 == 1;
+                if *pixel != lit {
+                    *pixel = lit;
+                    let row = index * 8 / self.width;
+                    rows = Some(rows.map_or((row, row), |(first, _)| (first, row)));
+                }
+            }
+        }
+        if let Some((first, last)) = rows {
+            let rect = Rect {
+                x: 0,
+                y: first,
+                width: self.width,
+                height: last - first + 1,
+            };
+            self.touch(&[rect]);
+        }
+    }
+    pub fn save_state(&self, out: &mut Vec<u8>) {
+        out.push(self.height as u8);
+        out.extend(self.to_bytes());
+        match self.colors {
+            Some(ref colors) => {
+                out.push(1);
+                out.push(colors.background as u8);
+                out.extend(colors.zones.iter().map(|&color| color as u8));
+            }
+            None => out.push(0),
+        }
+    }
+    pub fn load_state(&mut self, reader: &mut Reader) -> Result<(), StateError> {
+        self.set_hires(reader.u8()? as usize == HIRES_HEIGHT);
+        self.load_bytes(reader.bytes(self.width * self.height / 8)?);
+        self.set_color_board(reader.u8()? != 0);
+        if let Some(ref mut colors) = self.colors {
+            colors.background = reader.u8()? as usize % BACKGROUNDS.len();
+            let bytes = reader.bytes(colors.zones.len())?;
+            for (zone, &bits) in colors.zones.iter_mut().zip(bytes.iter()) {
+                *zone = Color::from_bits(bits);
+            }
+        }
+        Ok(())
+    }
+    pub fn pixel_buffer(&self) -> PixelBuffer {
+        let zones_across = self.width / ZONE_WIDTH;
+        let colors = self.colors.as_ref().map(|colors| ColorMap {
+            background: BACKGROUNDS[colors.background],
+            foreground: (0..self.width * self.height)
+                .map(|index| {
+                    let (x, y) = (index % self.width, index / self.width);
+                    colors.zones[y * zones_across + x / ZONE_WIDTH]
+                })
+                .collect(),
+        });
+        PixelBuffer {
+            width: self.width,
+            height: self.height,
+            data: self.data.clone(),
+            colors,
+        }
+    }
+    pub fn clear(&mut self, sink: &mut VideoSink) {
+        for pixel in self.data.iter_mut() {
+            *pixel = false;
+        }
+        let whole = self.whole();
+        self.touch(&[whole]);
+        self.flush(sink);
+    }
+    // `sprite` holds one byte per row.
     pub fn draw_sprite(
         &mut self,
-        memory: &Memory,
-        sprite_addr: Address,
+        sprite: &[u8],
         x: u8,
         y: u8,
-        rows: u8,
+        wrap: bool,
         sink: &mut VideoSink,
     ) -> bool {
         let mut pixel_unset = false;
-        for row in 0..rows as usize {
-            let sprite = memory.read(sprite_addr + row as u16);
+        // The starting position always wraps; `wrap` decides whether the
+        // parts of the sprite hanging off the edge wrap too or are clipped.
+        let (width, height) = (self.width, self.height);
+        let x = x as usize % width;
+        let y = y as usize % height;
+        for (row, &sprite) in sprite.iter().enumerate() {
+            if !wrap && y + row >= height {
+                break;
+            }
             for col in 0..SPRITE_WIDTH {
+                if !wrap && x + col >= width {
+                    break;
+                }
                 let (blit, _) = sprite.overflowing_shr((SPRITE_WIDTH - col - 1) as u32);
-                let pixel = &mut self.data
-                    [((row + y as usize) % HEIGHT) * WIDTH + ((col + x as usize) % WIDTH)];
+                let pixel = &mut self.data[((row + y) % height) * width + ((col + x) % width)];
                 let existing_pixel = *pixel;
                 *pixel ^= blit & 1 == 1;
                 if existing_pixel && !(*pixel) {
@@ -82,16 +563,19 @@ impl Vram {
                 }
             }
         }
-        let mut buffer = PixelBuffer {
-            data: [false; WIDTH * HEIGHT],
-        };
-        for row in 0..HEIGHT {
-            for col in 0..WIDTH {
-                let pixel = &mut self.data[row * WIDTH + col];
-                buffer.data[row * WIDTH + col] = *pixel;
+        let mut rects = Vec::new();
+        for (y, rows) in spans(y, sprite.len(), height, wrap) {
+            for (x, columns) in spans(x, SPRITE_WIDTH, width, wrap) {
+                rects.push(Rect {
+                    x,
+                    y,
+                    width: columns,
+                    height: rows,
+                });
             }
         }
-        sink.buffer = Some(buffer);
+        self.touch(&rects);
+        self.flush(sink);
         pixel_unset
     }
 }
@@ -0,0 +1,340 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use chip8::quirks::Quirks;
+use chip8::Address;
+use program::{Program, ProgramError, Variant};
+use serde::Deserialize;
+
+/* INFO:
+    ROM metadata keyed by the SHA-1 of the ROM, in the format of
+    `database/programs.json` from the community chip-8-database
+    (https://github.com/chip-8/chip-8-database):
+
+    [
+      {
+        "title": "Pong",
+        "authors": ["Paul Vervalin"],
+        "release": "1990",
+        "roms": {
+          "<sha1>": {
+            "file": "Pong [Paul Vervalin, 1990].ch8",
+            "platforms": ["originalChip8"],
+            "quirkyPlatforms": { "originalChip8": { "shift": true } },
+            "tickrate": 15,
+            "startAddress": 512,
+            "keys": { "player1Up": 1, "player1Down": 4 },
+            "colors": { "pixels": ["#000000", "#ffffff"] }
+          }
+        }
+      }
+    ]
+
+    Unknown fields are ignored, so the full community file can be loaded with
+    `RomDatabase::parse`. `RomDatabase::bundled` holds the community entries
+    (MIT licensed) for the ROMs in `programs/`, with authors, years and keys
+    from the ROMs' own file names and notes where the community has none.
+    The VIP two-page hi-res ROMs it lacks are listed as `originalChip8`.
+*/
+
+const BUNDLED: &str = include_str!("../data/programs.json");
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Platform {
+    OriginalChip8,
+    HybridVip,
+    ModernChip8,
+    Chip8X,
+    Chip48,
+    SuperChip1,
+    SuperChip,
+    MegaChip8,
+    XoChip,
+}
+
+impl Platform {
+    pub fn from_id(id: &str) -> Option<Platform> {
+        match id {
+            "originalChip8" => Some(Platform::OriginalChip8),
+            "hybridVIP" => Some(Platform::HybridVip),
+            "modernChip8" => Some(Platform::ModernChip8),
+            "chip8x" => Some(Platform::Chip8X),
+            "chip48" => Some(Platform::Chip48),
+            "superchip1" => Some(Platform::SuperChip1),
+            "superchip" => Some(Platform::SuperChip),
+            "megachip8" => Some(Platform::MegaChip8),
+            "xochip" => Some(Platform::XoChip),
+            _ => None,
+        }
+    }
+    pub fn default_quirks(&self) -> Quirks {
+        let vip = Quirks {
+            shift: false,
+            memory_increment_by_x: false,
+            memory_leave_i_unchanged: false,
+            wrap: false,
+            jump: false,
+            vblank: true,
+            logic: true,
+        };
+        let superchip = Quirks {
+            shift: true,
+            memory_increment_by_x: false,
+            memory_leave_i_unchanged: true,
+            wrap: false,
+            jump: true,
+            vblank: false,
+            logic: false,
+        };
+        match *self {
+            Platform::OriginalChip8 | Platform::HybridVip | Platform::Chip8X => vip,
+            Platform::ModernChip8 => Quirks {
+                vblank: false,
+                logic: false,
+                ..vip
+            },
+            Platform::Chip48 | Platform::SuperChip1 => Quirks {
+                memory_increment_by_x: true,
+                memory_leave_i_unchanged: false,
+                ..superchip
+            },
+            Platform::SuperChip | Platform::MegaChip8 => superchip,
+            Platform::XoChip => Quirks {
+                wrap: true,
+                vblank: false,
+                logic: false,
+                ..vip
+            },
+        }
+    }
+    // The instruction set and memory layout the platform's ROMs expect, or
+    // `None` for XO-CHIP, whose 64K of memory the core does not emulate.
+    pub fn variant(&self) -> Option<Variant> {
+        match *self {
+            Platform::OriginalChip8 | Platform::HybridVip | Platform::ModernChip8 => {
+                Some(Variant::Chip8)
+            }
+            Platform::Chip8X => Some(Variant::Chip8X),
+            Platform::Chip48 | Platform::SuperChip1 | Platform::SuperChip | Platform::MegaChip8 => {
+                Some(Variant::SuperChip)
+            }
+            Platform::XoChip => None,
+        }
+    }
+    // Instructions per 60Hz frame.
+    pub fn default_tickrate(&self) -> usize {
+        match *self {
+            Platform::OriginalChip8 | Platform::HybridVip | Platform::Chip8X => 15,
+            Platform::ModernChip8 => 12,
+            Platform::Chip48 | Platform::SuperChip1 | Platform::SuperChip => 30,
+            Platform::MegaChip8 => 1000,
+            Platform::XoChip => 100,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Colors {
+    // Background first, then foreground.
+    pub pixels: Vec<[u8; 3]>,
+    pub buzzer: Option<[u8; 3]>,
+    pub silence: Option<[u8; 3]>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RomInfo {
+    pub title: String,
+    pub description: Option<String>,
+    pub authors: Vec<String>,
+    pub release: Option<String>,
+    pub file: Option<String>,
+    pub platform: Option<Platform>,
+    pub tickrate: Option<usize>,
+    pub start_address: Option<Address>,
+    pub quirks: Option<Quirks>,
+    // What each CHIP-8 key does, e.g. ("up", 0x5), ordered by key.
+    pub keys: Vec<(String, u8)>,
+    pub colors: Option<Colors>,
+}
+
+impl RomInfo {
+    pub fn cycles_per_frame(&self) -> Option<usize> {
+        self.tickrate
+            .or_else(|| self.platform.map(|platform| platform.default_tickrate()))
+    }
+    // Moves `program` to the variant of its platform and to its start
+    // address, which the file name may not have told.
+    pub fn lay_out(&self, program: Program) -> Result<Program, ProgramError> {
+        let variant = match self.platform {
+            Some(platform) => platform
+                .variant()
+                .ok_or(ProgramError::UnsupportedPlatform)?,
+            None => program.variant,
+        };
+        let address = self.start_address.unwrap_or_else(|| variant.load_address());
+        if (variant, address) == (program.variant, program.load_address) {
+            return Ok(program);
+        }
+        Program::with_layout(&program.data, variant, address)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DatabaseError {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Deserialize)]
+struct ProgramEntry {
+    title: String,
+    description: Option<String>,
+    #[serde(default)]
+    authors: Vec<String>,
+    release: Option<String>,
+    #[serde(default)]
+    roms: BTreeMap<String, RomEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RomEntry {
+    file: Option<String>,
+    #[serde(default)]
+    platforms: Vec<String>,
+    #[serde(default)]
+    quirky_platforms: BTreeMap<String, QuirkOverrides>,
+    tickrate: Option<usize>,
+    start_address: Option<Address>,
+    #[serde(default)]
+    keys: BTreeMap<String, u8>,
+    colors: Option<ColorEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuirkOverrides {
+    shift: Option<bool>,
+    memory_increment_by_x: Option<bool>,
+    memory_leave_i_unchanged: Option<bool>,
+    wrap: Option<bool>,
+    jump: Option<bool>,
+    vblank: Option<bool>,
+    logic: Option<bool>,
+}
+
+impl QuirkOverrides {
+    fn apply(&self, quirks: Quirks) -> Quirks {
+        Quirks {
+            shift: self.shift.unwrap_or(quirks.shift),
+            memory_increment_by_x: self
+                .memory_increment_by_x
+                .unwrap_or(quirks.memory_increment_by_x),
+            memory_leave_i_unchanged: self
+                .memory_leave_i_unchanged
+                .unwrap_or(quirks.memory_leave_i_unchanged),
+            wrap: self.wrap.unwrap_or(quirks.wrap),
+            jump: self.jump.unwrap_or(quirks.jump),
+            vblank: self.vblank.unwrap_or(quirks.vblank),
+            logic: self.logic.unwrap_or(quirks.logic),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ColorEntry {
+    #[serde(default)]
+    pixels: Vec<String>,
+    buzzer: Option<String>,
+    silence: Option<String>,
+}
+
+fn color(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    Some([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+}
+
+pub struct RomDatabase {
+    programs: Vec<ProgramEntry>,
+    index: BTreeMap<String, usize>,
+}
+
+impl RomDatabase {
+    pub fn bundled() -> RomDatabase {
+        RomDatabase::parse(BUNDLED).expect("Bundled ROM database is invalid!")
+    }
+    pub fn parse(json: &str) -> Result<RomDatabase, DatabaseError> {
+        let programs: Vec<ProgramEntry> =
+            serde_json::from_str(json).map_err(|error| DatabaseError {
+                line: error.line(),
+                column: error.column(),
+            })?;
+        let mut index = BTreeMap::new();
+        for (position, program) in programs.iter().enumerate() {
+            for sha1 in program.roms.keys() {
+                index.insert(sha1.to_lowercase(), position);
+            }
+        }
+        Ok(RomDatabase { programs, index })
+    }
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+    pub fn lookup(&self, program: &Program) -> Option<RomInfo> {
+        self.lookup_sha1(&program.sha1_hex())
+    }
+    pub fn lookup_sha1(&self, sha1: &str) -> Option<RomInfo> {
+        let sha1 = sha1.to_lowercase();
+        let program = &self.programs[*self.index.get(&sha1)?];
+        let rom = program
+            .roms
+            .iter()
+            .find(|(key, _)| key.to_lowercase() == sha1)
+            .map(|(_, rom)| rom)?;
+        // The first platform the ROM is known to run on decides the defaults.
+        // A few entries only name theirs under `quirkyPlatforms`.
+        let platform = rom
+            .platforms
+            .iter()
+            .chain(rom.quirky_platforms.keys())
+            .find_map(|id| Platform::from_id(id).map(|platform| (id, platform)));
+        let quirks = platform.map(|(id, platform)| {
+            let quirks = platform.default_quirks();
+            match rom.quirky_platforms.get(id) {
+                Some(overrides) => overrides.apply(quirks),
+                None => quirks,
+            }
+        });
+        let mut keys: Vec<(String, u8)> = rom
+            .keys
+            .iter()
+            .map(|(action, &key)| (action.clone(), key))
+            .collect();
+        keys.sort_by_key(|&(_, key)| key);
+        let colors = rom.colors.as_ref().map(|colors| Colors {
+            pixels: colors.pixels.iter().filter_map(|hex| color(hex)).collect(),
+            buzzer: colors.buzzer.as_ref().and_then(|hex| color(hex)),
+            silence: colors.silence.as_ref().and_then(|hex| color(hex)),
+        });
+        Some(RomInfo {
+            title: program.title.clone(),
+            description: program.description.clone(),
+            authors: program.authors.clone(),
+            release: program.release.clone(),
+            file: rom.file.clone(),
+            platform: platform.map(|(_, platform)| platform),
+            tickrate: rom.tickrate,
+            start_address: rom.start_address,
+            quirks,
+            keys,
+            colors,
+        })
+    }
+}
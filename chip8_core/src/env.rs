@@ -0,0 +1,270 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use chip8::audio::AudioSink;
+use chip8::keyboard::{HexKey, Keyboard};
+use chip8::random::{RandomSource, XorShift};
+use chip8::vram::{PixelBuffer, VideoSink};
+use chip8::{Address, Chip8};
+use core::str::FromStr;
+use program::Program;
+
+/* INFO:
+    Per-ROM settings, one `key = value` per line, `#` starts a comment:
+
+    cycles_per_frame = 10
+    frame_skip = 4
+    sticky_actions = 0.25      # probability of repeating the previous action
+    max_frames = 18000
+    score = bcd 0x3F0 3        # 3 BCD digits (at most 9) at 0x3F0, also `byte` and `word`
+    done = pc 0x2C4            # game over when PC == 0x2C4
+    done = byte 0x3F5 0x00     # ... or when memory[0x3F5] == 0x00
+
+    The reward of a step is the change in score.
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Score {
+    Bcd(Address, u8),
+    Byte(Address),
+    Word(Address),
+}
+
+impl Score {
+    fn read(&self, memory: &[u8]) -> i32 {
+        let read = |address: Address| memory[address as usize % memory.len()] as i32;
+        match *self {
+            Score::Bcd(address, digits) => (0..digits as Address).fold(0, |score, digit| {
+                score * 10 + read(address.wrapping_add(digit)) % 10
+            }),
+            Score::Byte(address) => read(address),
+            Score::Word(address) => (read(address) << 8) | read(address.wrapping_add(1)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Terminal {
+    Pc(Address),
+    Byte(Address, u8),
+}
+
+impl Terminal {
+    fn reached(&self, chip8: &Chip8) -> bool {
+        match *self {
+            Terminal::Pc(address) => chip8.registers().pc == address,
+            Terminal::Byte(address, value) => {
+                let memory = chip8.memory();
+                memory[address as usize % memory.len()] == value
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    UnknownKey(usize),
+    InvalidValue(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnvConfig {
+    pub cycles_per_frame: usize,
+    pub frame_skip: usize,
+    pub sticky_actions: f32,
+    pub max_frames: Option<usize>,
+    pub score: Option<Score>,
+    pub terminals: Vec<Terminal>,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            cycles_per_frame: 10,
+            frame_skip: 4,
+            sticky_actions: 0.0,
+            max_frames: None,
+            score: None,
+            terminals: Vec::new(),
+        }
+    }
+}
+
+fn number<T: FromStr>(value: &str) -> Option<T> {
+    value.parse().ok()
+}
+
+fn address(value: &str) -> Option<u16> {
+    if value.starts_with("0x") || value.starts_with("0X") {
+        u16::from_str_radix(&value[2..], 16).ok()
+    } else {
+        value.parse().ok()
+    }
+}
+
+impl EnvConfig {
+    pub fn parse(text: &str) -> Result<EnvConfig, ConfigError> {
+        let mut config = EnvConfig::default();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap_or("").trim();
+            let value = parts.next().unwrap_or("").trim();
+            let words: Vec<&str> = value.split_whitespace().collect();
+            let parsed = match key {
+                "cycles_per_frame" => number(value).map(|v| config.cycles_per_frame = v),
+                "frame_skip" => number(value).map(|v| config.frame_skip = v),
+                "sticky_actions" => number(value)
+                    .filter(|p: &f32| (0.0..=1.0).contains(p))
+                    .map(|v| config.sticky_actions = v),
+                "max_frames" => number(value).map(|v| config.max_frames = Some(v)),
+                "score" => match words.as_slice() {
+                    ["bcd", at] => address(at).map(|at| Score::Bcd(at, 3)),
+                    ["bcd", at, digits] => address(at).and_then(|at| {
+                        number(digits)
+                            .filter(|&d| d <= 9)
+                            .map(|d| Score::Bcd(at, d))
+                    }),
+                    ["byte", at] => address(at).map(Score::Byte),
+                    ["word", at] => address(at).map(Score::Word),
+                    _ => None,
+                }
+                .map(|score| config.score = Some(score)),
+                "done" => match words.as_slice() {
+                    ["pc", at] => address(at).map(Terminal::Pc),
+                    ["byte", at, value] => address(at).and_then(|at| {
+                        address(value)
+                            .filter(|&value| value <= 0xFF)
+                            .map(|value| Terminal::Byte(at, value as u8))
+                    }),
+                    _ => None,
+                }
+                .map(|terminal| config.terminals.push(terminal)),
+                _ => return Err(ConfigError::UnknownKey(line_number)),
+            };
+            if parsed.is_none() {
+                return Err(ConfigError::InvalidValue(line_number));
+            }
+        }
+        Ok(config)
+    }
+}
+
+pub struct Chip8Env {
+    config: EnvConfig,
+    program: Program,
+    chip8: Chip8,
+    keyboard: Keyboard,
+    video_sink: VideoSink,
+    audio_sink: AudioSink,
+    rng: XorShift,
+    action: u16,
+    score: i32,
+    frames: usize,
+}
+
+impl Chip8Env {
+    pub fn new(program: Program, config: EnvConfig) -> Chip8Env {
+        let mut env = Chip8Env {
+            config,
+            program,
+            chip8: Chip8::new(),
+            keyboard: Keyboard::new(),
+            video_sink: VideoSink::new(),
+            audio_sink: AudioSink::new(),
+            rng: XorShift::default(),
+            action: 0,
+            score: 0,
+            frames: 0,
+        };
+        env.reset(0);
+        env
+    }
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+    pub fn chip8(&self) -> &Chip8 {
+        &self.chip8
+    }
+    pub fn reset(&mut self, seed: u32) -> PixelBuffer {
+        self.chip8 = Chip8::new();
+        self.chip8.set_random_source(Box::new(XorShift::new(seed)));
+        self.chip8.load_program(&self.program);
+        self.keyboard = Keyboard::new();
+        self.rng = XorShift::new(seed.rotate_left(16) ^ 0x9E37_79B9);
+        self.action = 0;
+        self.score = self.read_score();
+        self.frames = 0;
+        self.chip8.pixel_buffer()
+    }
+    // `action` is a bitmask of held keys, bit n for hex key n.
+    pub fn step(&mut self, action: u16) -> (PixelBuffer, i32, bool) {
+        let mut done = false;
+        for _ in 0..self.config.frame_skip.max(1) {
+            // Sticky actions: with some probability the previous action is
+            // held for another frame, which stops agents from memorising
+            // frame-perfect input sequences.
+            let sticky = self.rng.next_u8() as f32 / 256.0 < self.config.sticky_actions;
+            if !sticky {
+                self.action = action;
+            }
+            for key in 0..0x10 {
+                self.keyboard
+                    .update_key(HexKey::from(key), self.action & (1 << key) != 0);
+            }
+            done = self.run_frame();
+            if done {
+                break;
+            }
+        }
+        let score = self.read_score();
+        let reward = score - self.score;
+        self.score = score;
+        (self.chip8.pixel_buffer(), reward, done)
+    }
+    fn run_frame(&mut self) -> bool {
+        self.frames += 1;
+        // PC terminals are checked after every instruction, since the game
+        // over routine may only pass through that address mid-frame.
+        let per_cycle = self
+            .config
+            .terminals
+            .iter()
+            .any(|terminal| matches!(*terminal, Terminal::Pc(_)));
+        if !per_cycle {
+            self.chip8.run(
+                self.config.cycles_per_frame,
+                &self.keyboard,
+                &mut self.video_sink,
+                &mut self.audio_sink,
+            );
+        } else {
+            for _ in 0..self.config.cycles_per_frame {
+                self.chip8
+                    .step(&self.keyboard, &mut self.video_sink, &mut self.audio_sink);
+                if self.terminal_reached() {
+                    return true;
+                }
+            }
+        }
+        self.chip8.tick_timers(&mut self.audio_sink);
+        self.is_done()
+    }
+    fn read_score(&self) -> i32 {
+        self.config
+            .score
+            .map_or(0, |score| score.read(self.chip8.memory()))
+    }
+    fn is_done(&self) -> bool {
+        self.config.max_frames.is_some_and(|max| self.frames >= max) || self.terminal_reached()
+    }
+    fn terminal_reached(&self) -> bool {
+        self.config
+            .terminals
+            .iter()
+            .any(|terminal| terminal.reached(&self.chip8))
+    }
+}
@@ -1,4 +1,21 @@
+#![no_std]
+
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
 extern crate rand;
+#[cfg(feature = "database")]
+extern crate serde;
+#[cfg(feature = "database")]
+extern crate serde_json;
+extern crate sha1_smol;
+#[cfg(feature = "std")]
+extern crate std;
 
+pub mod cdp1802;
 pub mod chip8;
+#[cfg(feature = "database")]
+pub mod database;
+pub mod env;
+pub mod notes;
 pub mod program;
@@ -0,0 +1,314 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+/* INFO:
+    ROMs in `programs/` are named `Title [Author, Year].ch8`, with variations:
+
+    Guess [David Winter] (alt).ch8            another build of the same game
+    Breakout (Brix hack) [David Winter, 1997].ch8
+    Brick (Brix hack, 1990).ch8               credits in parentheses
+    Trip8 Demo (2008) [Revival Studios].ch8   year after the title
+    Hires Maze [David Winter, 199x].ch8       unknown digit
+
+    Next to many of them is a `.txt` with the same name holding the notes that
+    came with the ROM. Those start either with a credits line
+
+    Blitz, by: David Winter
+    Keypad Test, by hap, 15-02-06
+
+    or with a header block
+
+    Title		:	Astro Dodge
+    Author		:	Martijn Wenting / Revival Studios
+    System		:	Chip8 / SuperChip8
+    Date		:	18/12/2008
+
+    followed by free text where the controls are described in sentences like
+    "Button 2,4,6,8 will move your ship, button 5 will start the game.".
+*/
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileName {
+    pub title: String,
+    pub authors: Vec<String>,
+    pub year: Option<String>,
+    pub alt: bool,
+}
+
+impl FileName {
+    pub fn parse(name: &str) -> FileName {
+        let mut stem = strip_extension(name.trim());
+        let mut alt = false;
+        if let Some(rest) = stem.strip_suffix(" (alt)") {
+            stem = rest;
+            alt = true;
+        }
+        let (mut title, credits) = match (stem.find(" ["), stem.rfind(']')) {
+            (Some(open), Some(close)) if open < close => {
+                if stem[close + 1..].trim() == "(alt)" {
+                    alt = true;
+                }
+                (stem[..open].to_string(), &stem[open + 2..close])
+            }
+            _ => match stem.rfind(" (") {
+                Some(open)
+                    if stem.ends_with(')') && is_year_list(&stem[open + 2..stem.len() - 1]) =>
+                {
+                    (stem[..open].to_string(), &stem[open + 2..stem.len() - 1])
+                }
+                _ => (stem.to_string(), ""),
+            },
+        };
+        if let Some(rest) = title.strip_suffix(" (alt)") {
+            title = rest.to_string();
+            alt = true;
+        }
+        let mut year = None;
+        // "Trip8 Demo (2008)"
+        if let Some(open) = title.rfind(" (") {
+            if title.ends_with(')') && is_year(&title[open + 2..title.len() - 1]) {
+                year = Some(title[open + 2..title.len() - 1].to_string());
+                title.truncate(open);
+            }
+        }
+        let mut parts: Vec<&str> = credits
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .collect();
+        if parts.last().is_some_and(|part| is_year(part)) {
+            year = parts.pop().map(str::to_string);
+        }
+        // "Brick (Brix hack, 1990)" names what it was made from, not who made it.
+        if parts.first().is_some_and(|part| part.ends_with(" hack")) {
+            title = format!("{} ({})", title, parts.remove(0));
+        }
+        FileName {
+            title,
+            authors: parts.into_iter().map(str::to_string).collect(),
+            year,
+            alt,
+        }
+    }
+}
+
+fn strip_extension(name: &str) -> &str {
+    match name.rfind('.') {
+        Some(dot) if name.len() - dot <= 4 && !name[dot..].contains(' ') => &name[..dot],
+        _ => name,
+    }
+}
+
+// "1990", or "199x" when the last digit is not known.
+fn is_year(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() == 4
+        && bytes[..3].iter().all(u8::is_ascii_digit)
+        && (bytes[3].is_ascii_digit() || bytes[3] == b'x')
+}
+
+fn is_year_list(text: &str) -> bool {
+    text.rsplit(',')
+        .next()
+        .is_some_and(|last| is_year(last.trim()))
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Notes {
+    pub title: Option<String>,
+    pub authors: Vec<String>,
+    pub date: Option<String>,
+    pub system: Option<String>,
+    // The sentences that explain the controls, in the order they appear.
+    pub controls: Vec<String>,
+    // The CHIP-8 keys those sentences mention, sorted.
+    pub keys: Vec<u8>,
+}
+
+impl Notes {
+    pub fn parse(text: &str) -> Notes {
+        let mut notes = Notes::default();
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        if let Some(first) = lines.next() {
+            notes.parse_credits(first);
+        }
+        for line in text.lines() {
+            if let Some((field, value)) = line.split_once(':') {
+                notes.parse_field(field.trim(), value.trim());
+            }
+        }
+        for sentence in sentences(text) {
+            let keys = keys_in(&sentence);
+            if keys.is_empty() && !mentions_keys(&sentence) {
+                continue;
+            }
+            for key in keys {
+                if !notes.keys.contains(&key) {
+                    notes.keys.push(key);
+                }
+            }
+            notes.controls.push(sentence);
+        }
+        notes.keys.sort_unstable();
+        notes
+    }
+    // The notes next to a ROM. An "(alt)" build shares the notes of the
+    // original when it has none of its own.
+    #[cfg(feature = "std")]
+    pub fn find<P: AsRef<Path>>(rom: P) -> Option<Notes> {
+        let rom = rom.as_ref();
+        let stem = rom.file_stem()?.to_string_lossy();
+        let original = stem.strip_suffix(" (alt)").unwrap_or(&stem);
+        [stem.as_ref(), original]
+            .iter()
+            .filter_map(|stem| fs::read(rom.with_file_name(format!("{}.txt", stem))).ok())
+            .next()
+            .map(|text| Notes::parse(&String::from_utf8_lossy(&text)))
+    }
+
+    // "Keypad Test, by hap, 15-02-06"
+    fn parse_credits(&mut self, line: &str) {
+        let mut parts = line.split(',').map(str::trim);
+        let title = parts.next().unwrap_or("");
+        let mut credited = false;
+        for part in parts {
+            if part
+                .get(..2)
+                .is_some_and(|by| by.eq_ignore_ascii_case("by"))
+            {
+                let author = part[2..].trim_start_matches(':').trim();
+                if !author.is_empty() {
+                    self.authors.push(author.to_string());
+                }
+                credited = true;
+            } else if credited && part.bytes().any(|b| b.is_ascii_digit()) {
+                self.date = Some(part.to_string());
+            }
+        }
+        if credited && !title.is_empty() {
+            self.title = Some(title.to_string());
+        }
+    }
+
+    // "Title		:	Astro Dodge"
+    fn parse_field(&mut self, field: &str, value: &str) {
+        if value.is_empty() {
+            return;
+        }
+        match field.to_lowercase().as_str() {
+            "title" => self.title = Some(value.to_string()),
+            "author" | "authors" => {
+                self.authors = value
+                    .split('/')
+                    .map(str::trim)
+                    .filter(|author| !author.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            }
+            "date" => self.date = Some(value.to_string()),
+            "system" => self.system = Some(value.to_string()),
+            _ => {}
+        }
+    }
+}
+
+// Paragraphs are joined back into single lines before splitting them at the
+// end of each sentence, so a decimal like "2.5" stays in one piece.
+fn sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut lines = text.lines().map(str::trim).peekable();
+    while lines.peek().is_some() {
+        let paragraph = lines
+            .by_ref()
+            .skip_while(|line| line.is_empty())
+            .take_while(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut start = 0;
+        let bytes = paragraph.as_bytes();
+        for (index, &byte) in bytes.iter().enumerate() {
+            let ends = matches!(byte, b'.' | b'!' | b'?')
+                && bytes.get(index + 1).is_none_or(|next| *next == b' ');
+            if ends {
+                push_sentence(&mut sentences, &paragraph[start..=index]);
+                start = index + 1;
+            }
+        }
+        push_sentence(&mut sentences, &paragraph[start..]);
+    }
+    sentences
+}
+
+fn push_sentence(sentences: &mut Vec<String>, sentence: &str) {
+    let sentence = sentence.trim();
+    if sentence.bytes().any(|b| b.is_ascii_alphabetic()) {
+        sentences.push(sentence.to_string());
+    }
+}
+
+// Words keep their inner dashes and dots, so "Chip-8" and "2.5" are not keys
+// but a run of keys like "2-4-6-8" is split up.
+fn words(sentence: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let separator = |c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '.');
+    for word in sentence.split(separator) {
+        let word = word.trim_matches(|c| c == '-' || c == '.');
+        if word.split('-').all(|part| part.len() == 1) {
+            words.extend(word.split('-'));
+        } else if !word.is_empty() {
+            words.push(word);
+        }
+    }
+    words
+}
+
+fn mentions_keys(sentence: &str) -> bool {
+    words(sentence).into_iter().any(|word| {
+        matches!(
+            word.to_lowercase().as_str(),
+            "key" | "keys" | "button" | "buttons"
+        )
+    })
+}
+
+fn is_instruction(word: &str) -> bool {
+    matches!(
+        word.to_lowercase().as_str(),
+        "key" | "keys" | "button" | "buttons" | "press" | "pressing" | "push" | "pushing" | "use"
+    )
+}
+
+// Digits, and the letters A-F where they cannot be read as plain English:
+// after "key" or "letter", or in quotes like "F".
+fn keys_in(sentence: &str) -> Vec<u8> {
+    let words = words(sentence);
+    if !words.iter().any(|word| is_instruction(word)) {
+        return Vec::new();
+    }
+    let mut keys = Vec::new();
+    for (position, word) in words.iter().enumerate() {
+        let key = match *word.as_bytes() {
+            [digit] if digit.is_ascii_digit() => digit - b'0',
+            [letter @ b'A'..=b'F'] => {
+                let previous = position
+                    .checked_sub(1)
+                    .map(|previous| words[previous].to_lowercase());
+                let named = matches!(
+                    previous.as_deref(),
+                    Some("key") | Some("keys") | Some("letter") | Some("through")
+                );
+                if !named && !sentence.contains(&format!("\"{}\"", word)) {
+                    continue;
+                }
+                letter - b'A' + 0xA
+            }
+            _ => continue,
+        };
+        keys.push(key);
+    }
+    keys
+}
@@ -1,18 +1,143 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use chip8::memory::MEMORY_SIZE;
+use chip8::Address;
+use sha1_smol::Sha1;
+#[cfg(feature = "std")]
 use std::fs::File;
-use std::io::Read;
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+pub const DEFAULT_LOAD_ADDRESS: Address = 0x200;
+
+/* INFO:
+    Chip8X    CHIP-8X, RCA's version for the VIP with the color board
+    Chip8E    CHIP-8E, Gilles Detillieux's extended VIP interpreter
+    Eti660    the ETI-660, which runs plain CHIP-8 programs from 0x600
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Variant {
+    #[default]
+    Chip8,
+    SuperChip,
+    Chip8X,
+    Chip8E,
+    Eti660,
+}
+
+const VARIANTS: [Variant; 5] = [
+    Variant::Chip8,
+    Variant::SuperChip,
+    Variant::Chip8X,
+    Variant::Chip8E,
+    Variant::Eti660,
+];
+
+impl Variant {
+    // The inverse of `variant as u8`, used by save states.
+    pub fn from_index(index: u8) -> Option<Variant> {
+        VARIANTS.get(index as usize).cloned()
+    }
+    // Every variant has the 4K `Memory` holds.
+    pub fn memory_size(&self) -> usize {
+        MEMORY_SIZE
+    }
+    // Where programs for this variant usually start.
+    pub fn load_address(&self) -> Address {
+        match *self {
+            Variant::Chip8X => 0x300,
+            Variant::Eti660 => 0x600,
+            _ => DEFAULT_LOAD_ADDRESS,
+        }
+    }
+    // The largest ROM that fits between `load_address` and the end of memory.
+    pub fn max_size(&self, load_address: Address) -> usize {
+        self.memory_size().saturating_sub(load_address as usize)
+    }
+}
+
+#[derive(Debug)]
+pub enum ProgramError {
+    TooLarge {
+        size: usize,
+        max: usize,
+    },
+    BadLoadAddress(Address),
+    // A ROM for a platform the core does not emulate, like XO-CHIP.
+    UnsupportedPlatform,
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for ProgramError {
+    fn from(error: io::Error) -> ProgramError {
+        ProgramError::Io(error)
+    }
+}
 
 pub struct Program {
     pub data: Vec<u8>,
+    pub variant: Variant,
+    pub load_address: Address,
 }
 
-impl Program {
-    pub fn new(path: &str) -> Program {
-        let mut file = File::open(path).expect("File not found!");
-        let mut file_contents = Vec::new();
-        file.read_to_end(&mut file_contents)
-            .expect("Cannot read file!");
+// Unchecked: bytes that do not fit in memory are dropped when loading.
+impl<'a> From<&'a [u8]> for Program {
+    fn from(data: &'a [u8]) -> Program {
         Program {
-            data: file_contents,
+            data: data.to_vec(),
+            variant: Variant::default(),
+            load_address: DEFAULT_LOAD_ADDRESS,
+        }
+    }
+}
+
+impl Program {
+    pub fn from_bytes(data: &[u8]) -> Result<Program, ProgramError> {
+        Program::with_layout(data, Variant::default(), DEFAULT_LOAD_ADDRESS)
+    }
+    pub fn for_variant(data: &[u8], variant: Variant) -> Result<Program, ProgramError> {
+        Program::with_layout(data, variant, variant.load_address())
+    }
+    pub fn with_layout(
+        data: &[u8],
+        variant: Variant,
+        load_address: Address,
+    ) -> Result<Program, ProgramError> {
+        if load_address as usize >= variant.memory_size() {
+            return Err(ProgramError::BadLoadAddress(load_address));
         }
+        let max = variant.max_size(load_address);
+        if data.len() > max {
+            return Err(ProgramError::TooLarge {
+                size: data.len(),
+                max,
+            });
+        }
+        Ok(Program {
+            data: data.to_vec(),
+            variant,
+            load_address,
+        })
+    }
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Program, ProgramError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Program::from_bytes(&data)
+    }
+    #[cfg(feature = "std")]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Program, ProgramError> {
+        Program::from_reader(File::open(path)?)
+    }
+    pub fn sha1(&self) -> [u8; 20] {
+        Sha1::from(&self.data).digest().bytes()
+    }
+    // Lowercase hex, the form ROM databases use as keys.
+    pub fn sha1_hex(&self) -> String {
+        Sha1::from(&self.data).digest().to_string()
     }
 }
@@ -0,0 +1,66 @@
+extern crate chip8_core;
+
+use chip8_core::chip8::audio::AudioSink;
+use chip8_core::chip8::keyboard::{HexKey, Keyboard};
+use chip8_core::chip8::random::XorShift;
+use chip8_core::chip8::vram::VideoSink;
+use chip8_core::chip8::{Backend, Chip8, Registers};
+use chip8_core::program::Program;
+
+const FRAMES: usize = 300;
+const CYCLES_PER_FRAME: usize = 15;
+
+// The registers, memory and screen after running `rom` for a while, with a
+// key held down now and then.
+fn run(backend: Backend, rom: &str) -> (Registers, Vec<u8>, Vec<bool>) {
+    let path = format!("{}/../programs/{}", env!("CARGO_MANIFEST_DIR"), rom);
+    let mut chip8 = Chip8::with_backend(backend);
+    chip8.set_random_source(Box::new(XorShift::new(0x1234_5678)));
+    chip8.load_program(&Program::from_path(path).unwrap());
+    let mut keyboard = Keyboard::new();
+    let mut video_sink = VideoSink::new();
+    let mut audio_sink = AudioSink::new();
+    for frame in 0..FRAMES {
+        let key = (frame / 20) % 0x10;
+        for other in 0..0x10 {
+            keyboard.update_key(HexKey::from(other as u8), other == key && frame % 40 < 20);
+        }
+        chip8.run(
+            CYCLES_PER_FRAME,
+            &keyboard,
+            &mut video_sink,
+            &mut audio_sink,
+        );
+        chip8.tick_timers(&mut audio_sink);
+    }
+    (
+        chip8.registers(),
+        chip8.memory().to_vec(),
+        chip8.pixel_buffer().data,
+    )
+}
+
+fn agree(rom: &str) {
+    let interpreted = run(Backend::Interpreter, rom);
+    assert!(
+        interpreted.2.iter().any(|&pixel| pixel),
+        "{} drew nothing",
+        rom
+    );
+    assert_eq!(run(Backend::Threaded, rom), interpreted, "{}", rom);
+    // Lockstep panics as soon as the two differ.
+    assert_eq!(run(Backend::Lockstep, rom), interpreted, "{}", rom);
+}
+
+#[test]
+fn games_run_the_same_on_every_backend() {
+    agree("Brix [Andreas Gustafsson, 1990].ch8");
+    agree("Space Invaders [David Winter].ch8");
+    agree("Tetris [Fran Dachille, 1991].ch8");
+    agree("Maze [David Winter, 199x].ch8");
+}
+
+#[test]
+fn hires_games_run_the_same_on_every_backend() {
+    agree("Hires Maze [David Winter, 199x].ch8");
+}
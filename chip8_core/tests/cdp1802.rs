@@ -0,0 +1,90 @@
+extern crate chip8_core;
+
+use chip8_core::cdp1802::{Bus, Cdp1802};
+
+struct Ram {
+    data: Vec<u8>,
+    out: Vec<(u8, u8)>,
+}
+
+impl Bus for Ram {
+    fn read(&mut self, address: u16) -> u8 {
+        self.data[address as usize]
+    }
+    fn write(&mut self, address: u16, value: u8) {
+        self.data[address as usize] = value;
+    }
+    fn output(&mut self, port: u8, value: u8) {
+        self.out.push((port, value));
+    }
+    fn ef(&mut self, flag: u8) -> bool {
+        flag == 3
+    }
+}
+
+// Runs `code` from 0 until it reaches IDL and returns the CPU and memory.
+fn run(code: &[u8]) -> (Cdp1802, Ram) {
+    let mut ram = Ram {
+        data: vec![0; 0x100],
+        out: Vec::new(),
+    };
+    ram.data[..code.len()].copy_from_slice(code);
+    let mut cpu = Cdp1802::new();
+    while !cpu.idle {
+        cpu.step(&mut ram);
+    }
+    (cpu, ram)
+}
+
+#[test]
+fn adds_and_subtracts_with_carry() {
+    // LDI F0, ADI 20: D = 10, DF = 1
+    let (cpu, _) = run(&[0xF8, 0xF0, 0xFC, 0x20, 0x00]);
+    assert_eq!((cpu.d, cpu.df), (0x10, true));
+    // LDI 10, SMI 20: D = F0 with a borrow, DF = 0
+    let (cpu, _) = run(&[0xF8, 0x10, 0xFF, 0x20, 0x00]);
+    assert_eq!((cpu.d, cpu.df), (0xF0, false));
+    // LDI 10, SDI 30: D = 30 - 10
+    let (cpu, _) = run(&[0xF8, 0x10, 0xFD, 0x30, 0x00]);
+    assert_eq!((cpu.d, cpu.df), (0x20, true));
+    // LDI 81, SHRC twice: the carry rotates back in at the top
+    let (cpu, _) = run(&[0xF8, 0x81, 0x76, 0x76, 0x00]);
+    assert_eq!((cpu.d, cpu.df), (0xA0, false));
+}
+
+#[test]
+fn counts_down_with_a_short_branch() {
+    // LDI 05, PLO R7, then DEC R7, GLO R7, BNZ back to the DEC
+    let (cpu, _) = run(&[0xF8, 0x05, 0xA7, 0x27, 0x87, 0x3A, 0x03, 0x00]);
+    assert_eq!((cpu.r[7], cpu.pc()), (0, 8));
+}
+
+#[test]
+fn long_branches_skip_and_test_flags() {
+    // B3 taken (EF3 is set), LBR 0010, then at 0x10: LSZ over LDI 99
+    let code = [
+        0x36, 0x04, 0x00, 0x00, 0xC0, 0x00, 0x10, 0x00, 0, 0, 0, 0, 0, 0, 0, 0, //
+        0xCE, 0xF8, 0x99, 0x00,
+    ];
+    let (cpu, _) = run(&code);
+    assert_eq!((cpu.d, cpu.pc()), (0, 0x14));
+}
+
+#[test]
+fn uses_x_for_memory_and_io() {
+    // SEX R2, R2 = 0x80, LDI 42, STXD, IRX, OUT 4 sends M(R2) and moves on
+    let (cpu, ram) = run(&[0xE2, 0xF8, 0x80, 0xA2, 0xF8, 0x42, 0x73, 0x60, 0x64, 0x00]);
+    assert_eq!(ram.data[0x80], 0x42);
+    assert_eq!(ram.out, vec![(4, 0x42)]);
+    assert_eq!(cpu.r[2], 0x81);
+}
+
+#[test]
+fn switches_program_counters_with_sep_and_mark() {
+    // R3 = 0x20, SEP R3; at 0x20: LDI 7, SEP R0 back to the caller
+    let mut code = vec![0xF8, 0x20, 0xA3, 0xD3, 0x00];
+    code.resize(0x20, 0);
+    code.extend_from_slice(&[0xF8, 0x07, 0xD0]);
+    let (cpu, _) = run(&code);
+    assert_eq!((cpu.d, cpu.p, cpu.pc()), (7, 0, 5));
+}
@@ -0,0 +1,38 @@
+// Shared by the integration tests, each of which uses only some of it.
+#![allow(dead_code)]
+
+use chip8_core::chip8::audio::AudioSink;
+use chip8_core::chip8::keyboard::Keyboard;
+use chip8_core::chip8::vram::VideoSink;
+use chip8_core::chip8::{Backend, Chip8};
+use chip8_core::program::Program;
+
+// The backends every behaviour is checked on; the lockstep one compares
+// itself against a second interpreter as it goes.
+pub const BACKENDS: [Backend; 2] = [Backend::Interpreter, Backend::Lockstep];
+
+// A machine on `backend`, set up by `setup` before `program` is loaded.
+pub fn machine<F>(backend: Backend, setup: F, program: &Program) -> Chip8
+where
+    F: FnOnce(&mut Chip8),
+{
+    let mut chip8 = Chip8::with_backend(backend);
+    setup(&mut chip8);
+    chip8.load_program(program);
+    chip8
+}
+
+// Runs `cycles` instructions of `rom` with no keys held.
+pub fn run<F>(backend: Backend, setup: F, rom: &[u8], cycles: usize) -> Chip8
+where
+    F: FnOnce(&mut Chip8),
+{
+    let mut chip8 = machine(backend, setup, &Program::from(rom));
+    chip8.run(
+        cycles,
+        &Keyboard::new(),
+        &mut VideoSink::new(),
+        &mut AudioSink::new(),
+    );
+    chip8
+}
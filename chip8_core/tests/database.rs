@@ -0,0 +1,152 @@
+extern crate chip8_core;
+
+use chip8_core::chip8::quirks::Quirks;
+use chip8_core::database::{Platform, RomDatabase};
+use chip8_core::program::{Program, ProgramError, Variant};
+use std::fs;
+
+#[test]
+fn bundled_database_covers_every_bundled_rom() {
+    let database = RomDatabase::bundled();
+    let programs = concat!(env!("CARGO_MANIFEST_DIR"), "/../programs");
+    for entry in fs::read_dir(programs).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|extension| extension == "ch8") {
+            let program = Program::from_path(&path).unwrap();
+            let info = database.lookup(&program);
+            assert!(info.is_some(), "{} is not in the database", path.display());
+            assert!(
+                info.unwrap().platform.is_some(),
+                "{} has no platform",
+                path.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn platform_defaults_apply_to_bundled_roms() {
+    let database = RomDatabase::bundled();
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../programs/Space Intercept [Joseph Weisbecker, 1978].ch8"
+    );
+    let info = database.lookup(&Program::from_path(path).unwrap()).unwrap();
+    assert_eq!(info.title, "Space Intercept");
+    assert_eq!(info.authors, vec!["Joseph Weisbecker"]);
+    assert_eq!(info.release.as_deref(), Some("1978"));
+    assert_eq!(info.platform, Some(Platform::OriginalChip8));
+    assert_eq!(info.quirks, Some(Platform::OriginalChip8.default_quirks()));
+    assert_eq!(info.cycles_per_frame(), Some(15));
+    assert_eq!(info.keys[0], ("largeUfo".to_string(), 1));
+}
+
+#[test]
+fn parses_the_community_format() {
+    let json = r##"[
+        {
+            "title": "Test",
+            "description": "A test program",
+            "authors": ["Someone"],
+            "images": ["ignored.png"],
+            "roms": {
+                "A9993E364706816ABA3E25717850C26C9CD0D89D": {
+                    "file": "test.ch8",
+                    "platforms": ["unknownPlatform", "superchip"],
+                    "quirkyPlatforms": { "superchip": { "wrap": true, "shift": false } },
+                    "startAddress": 1536,
+                    "screenRotation": 90,
+                    "keys": { "right": 6, "left": 4 },
+                    "colors": { "pixels": ["#000000", "#FF8000"], "buzzer": "bad" }
+                }
+            }
+        }
+    ]"##;
+    let database = RomDatabase::parse(json).unwrap();
+    assert_eq!(database.len(), 1);
+    let info = database
+        .lookup(&Program::from_bytes(b"abc").unwrap())
+        .unwrap();
+    assert_eq!(info.description.as_deref(), Some("A test program"));
+    assert_eq!(info.platform, Some(Platform::SuperChip));
+    assert_eq!(
+        info.quirks,
+        Some(Quirks {
+            wrap: true,
+            shift: false,
+            ..Platform::SuperChip.default_quirks()
+        })
+    );
+    assert_eq!(info.start_address, Some(0x600));
+    assert_eq!(
+        info.keys,
+        vec![("left".to_string(), 4), ("right".to_string(), 6)]
+    );
+    let colors = info.colors.unwrap();
+    assert_eq!(colors.pixels, vec![[0, 0, 0], [0xFF, 0x80, 0x00]]);
+    assert_eq!(colors.buzzer, None);
+    assert!(database.lookup_sha1("0000").is_none());
+}
+
+#[test]
+fn reports_where_the_json_is_broken() {
+    let error = RomDatabase::parse("[\n  {\"title\": }\n]").err().unwrap();
+    assert_eq!(error.line, 2);
+}
+
+#[test]
+fn quirky_platforms_override_the_defaults() {
+    let database = RomDatabase::bundled();
+    let info = database
+        .lookup_sha1("ac621d9fcada302ba6965768229ef130630bc525")
+        .unwrap();
+    assert_eq!(info.title, "Astro Dodge");
+    assert_eq!(info.platform, Some(Platform::OriginalChip8));
+    assert_eq!(
+        info.quirks,
+        Some(Quirks {
+            memory_leave_i_unchanged: true,
+            ..Platform::OriginalChip8.default_quirks()
+        })
+    );
+}
+
+fn abc_on(rom: &str) -> Result<Program, ProgramError> {
+    let json = format!(
+        r#"[{{ "title": "ABC", "roms": {{ "a9993e364706816aba3e25717850c26c9cd0d89d": {} }} }}]"#,
+        rom
+    );
+    let program = Program::from_bytes(b"abc").unwrap();
+    let info = RomDatabase::parse(&json).unwrap().lookup(&program).unwrap();
+    info.lay_out(program)
+}
+
+#[test]
+fn programs_are_laid_out_for_their_platform() {
+    let program = abc_on(r#"{ "platforms": ["chip8x"] }"#).unwrap();
+    assert_eq!(
+        (program.variant, program.load_address),
+        (Variant::Chip8X, 0x300)
+    );
+    assert_eq!(program.data, b"abc");
+    let program = abc_on(r#"{ "platforms": ["superchip"], "startAddress": 1536 }"#).unwrap();
+    assert_eq!(
+        (program.variant, program.load_address),
+        (Variant::SuperChip, 0x600)
+    );
+    let program = abc_on("{}").unwrap();
+    assert_eq!(
+        (program.variant, program.load_address),
+        (Variant::Chip8, 0x200)
+    );
+    match abc_on(r#"{ "startAddress": 4096 }"#) {
+        Err(ProgramError::BadLoadAddress(address)) => assert_eq!(address, 0x1000),
+        Err(error) => panic!("{:?}", error),
+        Ok(_) => panic!("0x1000 is past the end of memory"),
+    }
+    match abc_on(r#"{ "platforms": ["xochip"] }"#) {
+        Err(ProgramError::UnsupportedPlatform) => {}
+        Err(error) => panic!("{:?}", error),
+        Ok(_) => panic!("XO-CHIP is not emulated"),
+    }
+}
@@ -0,0 +1,139 @@
+extern crate chip8_core;
+
+use chip8_core::chip8::audio::AudioSink;
+use chip8_core::chip8::keyboard::Keyboard;
+use chip8_core::chip8::vram::{Color, Rect, VideoSink, Vram, HEIGHT, WIDTH};
+use chip8_core::chip8::Chip8;
+use chip8_core::program::Program;
+
+const BOX: [u8; 3] = [0xFF, 0x81, 0xFF];
+
+fn rect(x: usize, y: usize, width: usize, height: usize) -> Rect {
+    Rect {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+fn same_screen(sink: &VideoSink, vram: &Vram) {
+    let frame = sink.frame().unwrap();
+    let screen = vram.pixel_buffer();
+    assert_eq!((frame.width, frame.height), (screen.width, screen.height));
+    assert_eq!(frame.data, screen.data);
+    assert_eq!(frame.colors, screen.colors);
+}
+
+#[test]
+fn a_sprite_only_dirties_its_own_rectangle() {
+    let mut vram = Vram::new();
+    let mut sink = VideoSink::new();
+    vram.draw_sprite(&BOX, 0, 0, true, &mut sink);
+    // The first notice sends the whole screen.
+    assert_eq!(sink.take_dirty(), vec![rect(0, 0, WIDTH, HEIGHT)]);
+    vram.draw_sprite(&BOX, 10, 20, true, &mut sink);
+    assert_eq!(sink.take_dirty(), vec![rect(10, 20, 8, 3)]);
+    assert!(sink.take_dirty().is_empty());
+    same_screen(&sink, &vram);
+}
+
+#[test]
+fn sprites_past_the_edge_dirty_each_part() {
+    let mut vram = Vram::new();
+    let mut sink = VideoSink::new();
+    vram.draw_sprite(&BOX, 0, 0, true, &mut sink);
+    sink.take_dirty();
+    vram.draw_sprite(&BOX, 60, 31, true, &mut sink);
+    let mut dirty = sink.take_dirty();
+    dirty.sort_by_key(|rect| (rect.y, rect.x));
+    assert_eq!(
+        dirty,
+        vec![
+            rect(0, 0, 4, 2),
+            rect(60, 0, 4, 2),
+            rect(0, 31, 4, 1),
+            rect(60, 31, 4, 1),
+        ]
+    );
+    same_screen(&sink, &vram);
+
+    vram.draw_sprite(&BOX, 60, 31, false, &mut sink);
+    assert_eq!(sink.take_dirty(), vec![rect(60, 31, 4, 1)]);
+    same_screen(&sink, &vram);
+}
+
+#[test]
+fn touching_rectangles_are_merged() {
+    let mut vram = Vram::new();
+    let mut sink = VideoSink::new();
+    vram.draw_sprite(&BOX, 0, 0, true, &mut sink);
+    sink.take_dirty();
+    vram.draw_sprite(&BOX, 8, 8, true, &mut sink);
+    vram.draw_sprite(&BOX, 16, 8, true, &mut sink);
+    vram.draw_sprite(&BOX, 40, 20, true, &mut sink);
+    let mut dirty = sink.take_dirty();
+    dirty.sort_by_key(|rect| (rect.y, rect.x));
+    assert_eq!(dirty, vec![rect(8, 8, 16, 3), rect(40, 20, 8, 3)]);
+
+    // Too many apart become one around them all.
+    for index in 0..20 {
+        vram.draw_sprite(&[0x80], index % 5 * 12, index / 5 * 8, true, &mut sink);
+    }
+    assert_eq!(sink.take_dirty(), vec![rect(0, 0, 56, 25)]);
+    same_screen(&sink, &vram);
+}
+
+#[test]
+fn hosts_pull_the_frame_or_the_rectangles() {
+    let mut vram = Vram::new();
+    let mut sink = VideoSink::new();
+    assert!(sink.get().is_none());
+    assert_eq!(sink.generation(), 0);
+    vram.draw_sprite(&BOX, 0, 0, true, &mut sink);
+    vram.draw_sprite(&BOX, 8, 0, true, &mut sink);
+    assert_eq!(sink.generation(), 2);
+    assert_eq!(vram.generation(), 2);
+    // Taking the whole frame leaves no rectangles behind, and the other
+    // way around.
+    assert_eq!(sink.get().unwrap().data, vram.pixel_buffer().data);
+    assert!(sink.take_dirty().is_empty());
+    vram.draw_sprite(&BOX, 0, 0, true, &mut sink);
+    assert_eq!(sink.take_dirty().len(), 1);
+    assert!(sink.get().is_none());
+    assert!(sink.frame().is_some());
+}
+
+#[test]
+fn switching_resolution_and_colors_resends_the_screen() {
+    let mut vram = Vram::new();
+    let mut sink = VideoSink::new();
+    vram.draw_sprite(&BOX, 0, 8, true, &mut sink);
+    vram.set_hires(true);
+    vram.draw_sprite(&BOX, 0, 40, true, &mut sink);
+    let frame = sink.get().unwrap();
+    assert_eq!((frame.width, frame.height), (WIDTH, 64));
+    same_screen(&sink, &vram);
+
+    vram.set_color_board(true);
+    vram.color_zones(7, 62, 2, 4, Color::Green, &mut sink);
+    same_screen(&sink, &vram);
+    sink.take_dirty();
+    vram.color_zones(0, 0, 1, 1, Color::Yellow, &mut sink);
+    assert_eq!(sink.take_dirty(), vec![rect(0, 0, 8, 1)]);
+    vram.cycle_background(&mut sink);
+    same_screen(&sink, &vram);
+}
+
+#[test]
+fn clearing_the_screen_is_sent() {
+    // Draws the font's "0", then clears the screen.
+    let rom = [0xA0, 0x00, 0xD0, 0x05, 0x00, 0xE0];
+    let mut chip8 = Chip8::new();
+    chip8.load_program(&Program::from(&rom[..]));
+    let (keyboard, mut sink, mut audio) = (Keyboard::new(), VideoSink::new(), AudioSink::new());
+    chip8.run(2, &keyboard, &mut sink, &mut audio);
+    assert!(sink.get().unwrap().data.iter().any(|&pixel| pixel));
+    chip8.run(1, &keyboard, &mut sink, &mut audio);
+    assert!(sink.get().unwrap().data.iter().all(|&pixel| !pixel));
+}
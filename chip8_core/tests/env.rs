@@ -0,0 +1,171 @@
+extern crate chip8_core;
+
+use chip8_core::env::{Chip8Env, ConfigError, EnvConfig, Score, Terminal};
+use chip8_core::program::Program;
+
+// Counts up in V0 and keeps it at 0x3F0, one count every four cycles.
+const COUNTER: [u8; 10] = [0xA3, 0xF0, 0x70, 0x01, 0xA3, 0xF0, 0xF0, 0x55, 0x12, 0x02];
+
+// Keeps 1 at 0x3F1 while key 0 is held and 0 otherwise, every seven cycles.
+const KEY: [u8; 14] = [
+    0x61, 0x00, 0x60, 0x00, 0xE0, 0xA1, 0x61, 0x01, 0xA3, 0xF0, 0xF1, 0x55, 0x12, 0x00,
+];
+
+fn new_env(rom: &[u8], config: &str) -> Chip8Env {
+    Chip8Env::new(Program::from(rom), EnvConfig::parse(config).unwrap())
+}
+
+#[test]
+fn parses_every_setting() {
+    let config = EnvConfig::parse(
+        "# Breakout\n\
+         cycles_per_frame = 12\n\
+         frame_skip = 2\n\
+         \n\
+         sticky_actions = 0.25   # repeat a quarter of the time\n\
+         max_frames = 100\n\
+         score = bcd 0x3F0 2\n\
+         done = pc 0x2C4\n\
+         done = byte 0x3F5 0xFF\n",
+    )
+    .unwrap();
+    assert_eq!(
+        config,
+        EnvConfig {
+            cycles_per_frame: 12,
+            frame_skip: 2,
+            sticky_actions: 0.25,
+            max_frames: Some(100),
+            score: Some(Score::Bcd(0x3F0, 2)),
+            terminals: vec![Terminal::Pc(0x2C4), Terminal::Byte(0x3F5, 0xFF)],
+        }
+    );
+    assert_eq!(EnvConfig::parse("").unwrap(), EnvConfig::default());
+    assert_eq!(
+        EnvConfig::parse("score = bcd 1008").unwrap().score,
+        Some(Score::Bcd(1008, 3))
+    );
+    assert_eq!(
+        EnvConfig::parse("score = word 0x300").unwrap().score,
+        Some(Score::Word(0x300))
+    );
+}
+
+#[test]
+fn reports_the_line_of_a_bad_setting() {
+    assert_eq!(
+        EnvConfig::parse("frame_skip = 2\nspeed = 3"),
+        Err(ConfigError::UnknownKey(2))
+    );
+    assert_eq!(
+        EnvConfig::parse("\n\nframe_skip = fast"),
+        Err(ConfigError::InvalidValue(3))
+    );
+    assert_eq!(
+        EnvConfig::parse("sticky_actions = 1.5"),
+        Err(ConfigError::InvalidValue(1))
+    );
+    assert_eq!(
+        EnvConfig::parse("score = nibble 0x300"),
+        Err(ConfigError::InvalidValue(1))
+    );
+    assert_eq!(
+        EnvConfig::parse("score = bcd 0x3F0 10"),
+        Err(ConfigError::InvalidValue(1))
+    );
+    assert_eq!(
+        EnvConfig::parse("done = byte 0x3F5 0x100"),
+        Err(ConfigError::InvalidValue(1))
+    );
+}
+
+#[test]
+fn nine_bcd_digits_fit_the_score() {
+    // The score is read from the ROM itself, 999999999.
+    let env = new_env(&[0x09; 9], "score = bcd 0x200 9");
+    assert_eq!(env.config().score, Some(Score::Bcd(0x200, 9)));
+}
+
+#[test]
+fn rewards_the_change_in_score() {
+    let mut env = new_env(
+        &COUNTER,
+        "cycles_per_frame = 4\nframe_skip = 1\nscore = byte 0x3F0",
+    );
+    for _ in 0..5 {
+        let (_, reward, done) = env.step(0);
+        assert_eq!(reward, 1);
+        assert!(!done);
+    }
+}
+
+#[test]
+fn frame_skip_runs_several_frames_per_step() {
+    let mut env = new_env(
+        &COUNTER,
+        "cycles_per_frame = 4\nframe_skip = 3\nscore = byte 0x3F0",
+    );
+    assert_eq!(env.step(0).1, 3);
+    assert_eq!(env.step(0).1, 3);
+}
+
+#[test]
+fn reset_starts_the_game_over() {
+    let mut env = new_env(
+        &COUNTER,
+        "cycles_per_frame = 4\nframe_skip = 1\nscore = byte 0x3F0",
+    );
+    env.step(0);
+    env.step(0);
+    let screen = env.reset(7);
+    assert_eq!((screen.width, screen.height), (64, 32));
+    assert_eq!(env.chip8().registers().pc, 0x200);
+    assert_eq!(env.step(0).1, 1);
+}
+
+#[test]
+fn games_end_at_the_frame_limit() {
+    let mut env = new_env(
+        &COUNTER,
+        "cycles_per_frame = 4\nframe_skip = 2\nmax_frames = 5",
+    );
+    assert!(!env.step(0).2);
+    assert!(!env.step(0).2);
+    assert!(env.step(0).2);
+    assert!(!env.reset(0).data.is_empty());
+    assert!(!env.step(0).2);
+}
+
+#[test]
+fn games_end_at_a_terminal() {
+    // The count reaches 3 in the third frame.
+    let mut env = new_env(
+        &COUNTER,
+        "cycles_per_frame = 4\nframe_skip = 1\ndone = byte 0x3F0 3",
+    );
+    assert!(!env.step(0).2);
+    assert!(!env.step(0).2);
+    assert!(env.step(0).2);
+
+    // The store is passed mid-frame.
+    let mut env = new_env(&COUNTER, "cycles_per_frame = 100\ndone = pc 0x206");
+    assert!(env.step(0).2);
+    assert_eq!(env.chip8().registers().pc, 0x206);
+}
+
+#[test]
+fn actions_hold_keys_unless_the_last_one_sticks() {
+    let mut env = new_env(
+        &KEY,
+        "cycles_per_frame = 12\nframe_skip = 1\nscore = byte 0x3F1",
+    );
+    assert_eq!(env.step(0b1).1, 1);
+    assert_eq!(env.step(0b10).1, -1);
+
+    let mut env = new_env(
+        &KEY,
+        "cycles_per_frame = 12\nframe_skip = 1\nscore = byte 0x3F1\nsticky_actions = 1",
+    );
+    assert_eq!(env.step(0b1).1, 0);
+    assert_eq!(env.step(0b1).1, 0);
+}
@@ -0,0 +1,72 @@
+extern crate chip8_core;
+
+mod common;
+
+use chip8_core::chip8::vram::{HEIGHT, HIRES_HEIGHT, WIDTH};
+use chip8_core::chip8::{Backend, Chip8};
+use chip8_core::program::Program;
+use common::{run, BACKENDS};
+
+// `1260` at 0x200, then `code` at 0x2C0 where the hi-res interpreter starts.
+fn hires_rom(code: &[u8]) -> Vec<u8> {
+    let mut rom = vec![0; 0xC0];
+    rom[..2].copy_from_slice(&[0x12, 0x60]);
+    rom.extend_from_slice(code);
+    rom
+}
+
+#[test]
+fn switches_to_64x64_and_starts_at_0x2c0() {
+    // V0 = 60, I = font "0", draw its top row at (0, V0)
+    let rom = hires_rom(&[0x60, 60, 0xA0, 0x00, 0xD1, 0x01]);
+    for &backend in &BACKENDS {
+        let chip8 = run(backend, |_| {}, &rom, 1);
+        assert_eq!(chip8.registers().pc, 0x2C0);
+        let chip8 = run(backend, |_| {}, &rom, 4);
+        let pixels = chip8.pixel_buffer();
+        assert_eq!((pixels.width, pixels.height), (WIDTH, HIRES_HEIGHT));
+        assert_eq!(pixels.data.len(), WIDTH * HIRES_HEIGHT);
+        assert!(pixels.data[60 * WIDTH] && pixels.data[60 * WIDTH + 3]);
+    }
+}
+
+#[test]
+fn only_the_first_instruction_enables_hires() {
+    // Jump to 0x202, which jumps to 0x260, which loops
+    let mut rom = vec![0x12, 0x02, 0x12, 0x60];
+    rom.resize(0x60, 0);
+    rom.extend_from_slice(&[0x12, 0x60]);
+    let chip8 = run(Backend::Interpreter, |_| {}, &rom, 3);
+    assert_eq!(chip8.registers().pc, 0x260);
+    assert_eq!(chip8.pixel_buffer().height, HEIGHT);
+}
+
+#[test]
+fn clears_the_hires_screen_with_0230() {
+    // Draw at (0, 40), then clear with 0230
+    let rom = hires_rom(&[0x60, 40, 0xA0, 0x00, 0xD1, 0x01, 0x02, 0x30]);
+    let chip8 = run(Backend::Lockstep, |_| {}, &rom, 4);
+    assert!(chip8.pixel_buffer().data.iter().any(|&pixel| pixel));
+    let chip8 = run(Backend::Lockstep, |_| {}, &rom, 5);
+    assert!(chip8.pixel_buffer().data.iter().all(|&pixel| !pixel));
+
+    // Outside of hi-res mode 0230 is a machine code call and is skipped
+    let rom = [0xA0, 0x00, 0xD0, 0x01, 0x02, 0x30];
+    let chip8 = run(Backend::Lockstep, |_| {}, &rom, 3);
+    assert_eq!(chip8.registers().pc, 0x206);
+    assert!(chip8.pixel_buffer().data[0]);
+}
+
+#[test]
+fn snapshots_keep_the_resolution() {
+    let rom = hires_rom(&[0x60, 63, 0xA0, 0x00, 0xD1, 0x01, 0x12, 0xC6]);
+    let chip8 = run(Backend::Interpreter, |_| {}, &rom, 4);
+    let mut restored = Chip8::new();
+    restored.load_state(&chip8.save_state()).unwrap();
+    let pixels = restored.pixel_buffer();
+    assert_eq!(pixels.height, HIRES_HEIGHT);
+    assert!(pixels.data[63 * WIDTH]);
+
+    restored.load_program(&Program::from(&[0x12, 0x00][..]));
+    assert_eq!(restored.pixel_buffer().height, HEIGHT);
+}
@@ -0,0 +1,162 @@
+extern crate chip8_core;
+extern crate proptest;
+
+mod common;
+
+use chip8_core::chip8::audio::{AudioEvent, AudioSink};
+use chip8_core::chip8::keyboard::{HexKey, Keyboard};
+use chip8_core::chip8::vram::VideoSink;
+use chip8_core::chip8::{Backend, Chip8, Registers};
+use chip8_core::program::Program;
+use proptest::prelude::*;
+
+fn run(backend: Backend, rom: &[u8], cycles: usize, keys: u16) -> Chip8 {
+    let mut chip8 = common::machine(backend, |_| {}, &Program::from(rom));
+    let mut keyboard = Keyboard::new();
+    for key in 0..0x10 {
+        keyboard.update_key(HexKey::from(key), keys & (1 << key) != 0);
+    }
+    chip8.run(
+        cycles,
+        &keyboard,
+        &mut VideoSink::new(),
+        &mut AudioSink::new(),
+    );
+    chip8
+}
+
+// Assembles `instructions`, runs exactly that many cycles and returns the
+// registers.
+fn execute(instructions: &[u16]) -> Registers {
+    let rom: Vec<u8> = instructions
+        .iter()
+        .flat_map(|instruction| vec![(instruction >> 8) as u8, *instruction as u8])
+        .collect();
+    run(Backend::Interpreter, &rom, instructions.len(), 0).registers()
+}
+
+fn set(vx: u8, value: u8) -> u16 {
+    0x6000 | (vx as u16) << 8 | value as u16
+}
+
+fn alu(vx: u8, vy: u8, op: u16) -> u16 {
+    0x8000 | (vx as u16) << 8 | (vy as u16) << 4 | op
+}
+
+// Two distinct registers, neither of them VF.
+fn register_pair() -> impl Strategy<Value = (u8, u8)> {
+    (0u8..0xF, 0u8..0xF).prop_filter("distinct registers", |(x, y)| x != y)
+}
+
+proptest! {
+    #[test]
+    fn add_sets_vf_iff_the_sum_overflowed((x, y) in register_pair(), a: u8, b: u8) {
+        let registers = execute(&[set(x, a), set(y, b), alu(x, y, 0x4)]);
+        prop_assert_eq!(registers.v[x as usize], a.wrapping_add(b));
+        prop_assert_eq!(registers.v[0xF] == 1, a as u16 + b as u16 > 0xFF);
+    }
+
+    #[test]
+    fn sub_sets_vf_iff_there_was_no_borrow((x, y) in register_pair(), a: u8, b: u8) {
+        let registers = execute(&[set(x, a), set(y, b), alu(x, y, 0x5)]);
+        prop_assert_eq!(registers.v[x as usize], a.wrapping_sub(b));
+        prop_assert_eq!(registers.v[0xF] == 1, a >= b);
+    }
+
+    #[test]
+    fn subn_sets_vf_iff_there_was_no_borrow((x, y) in register_pair(), a: u8, b: u8) {
+        let registers = execute(&[set(x, a), set(y, b), alu(x, y, 0x7)]);
+        prop_assert_eq!(registers.v[x as usize], b.wrapping_sub(a));
+        prop_assert_eq!(registers.v[0xF] == 1, b >= a);
+    }
+
+    #[test]
+    fn shifts_move_the_lost_bit_into_vf((x, y) in register_pair(), a: u8) {
+        let registers = execute(&[set(x, a), alu(x, y, 0x6)]);
+        prop_assert_eq!(registers.v[x as usize], a >> 1);
+        prop_assert_eq!(registers.v[0xF], a & 1);
+        let registers = execute(&[set(x, a), alu(x, y, 0xE)]);
+        prop_assert_eq!(registers.v[x as usize], a << 1);
+        prop_assert_eq!(registers.v[0xF], a >> 7);
+    }
+
+    #[test]
+    fn skips_are_taken_iff_the_condition_holds(x in 0u8..0x10, a: u8, b: u8) {
+        let skip_eq = 0x3000 | (x as u16) << 8 | b as u16;
+        let skip_neq = 0x4000 | (x as u16) << 8 | b as u16;
+        let pc = execute(&[set(x, a), skip_eq]).pc;
+        prop_assert_eq!(pc, if a == b { 0x206 } else { 0x204 });
+        let pc = execute(&[set(x, a), skip_neq]).pc;
+        prop_assert_eq!(pc, if a != b { 0x206 } else { 0x204 });
+    }
+
+    #[test]
+    fn bcd_writes_three_decimal_digits(x in 0u8..0x10, a: u8, i in 0x300u16..0xFFD) {
+        let rom = [
+            0x60 | x, a,
+            0xA0 | (i >> 8) as u8, i as u8,
+            0xF0 | x, 0x33,
+        ];
+        let chip8 = run(Backend::Interpreter, &rom, 3, 0);
+        let i = i as usize;
+        prop_assert_eq!(&chip8.memory()[i..i + 3], &[a / 100, a / 10 % 10, a % 10][..]);
+    }
+
+    #[test]
+    fn store_then_load_round_trips(values: [u8; 0x10], x in 0u8..0x10, i in 0x300u16..0xFF0) {
+        let mut instructions: Vec<u16> = (0..0x10).map(|r| set(r, values[r as usize])).collect();
+        instructions.push(0xA000 | i);
+        instructions.push(0xF055 | (x as u16) << 8);
+        let stored = execute(&instructions);
+        prop_assert_eq!(stored.i, i + x as u16 + 1);
+        instructions.extend((0..0x10).map(|r| set(r, 0)));
+        instructions.push(0xA000 | i);
+        instructions.push(0xF065 | (x as u16) << 8);
+        let loaded = execute(&instructions);
+        for (r, &value) in values.iter().enumerate() {
+            let expected = if r <= x as usize { value } else { 0 };
+            prop_assert_eq!(loaded.v[r], expected);
+        }
+    }
+
+    #[test]
+    fn font_points_at_the_low_nibble_glyph(x in 0u8..0x10, a: u8) {
+        let registers = execute(&[set(x, a), 0xF029 | (x as u16) << 8]);
+        prop_assert_eq!(registers.i, (a & 0xF) as u16 * 5);
+    }
+
+    // Arbitrary programs must never panic, and the threaded backend has to
+    // agree with the interpreter on them; lockstep panics if it does not.
+    #[test]
+    fn arbitrary_programs_run_in_lockstep(
+        rom in proptest::collection::vec(any::<u8>(), 0..0x200),
+        keys: u16,
+        cycles in 0usize..2000,
+    ) {
+        let chip8 = run(Backend::Lockstep, &rom, cycles, keys);
+        prop_assert!((chip8.registers().pc as usize) < chip8.memory().len());
+    }
+}
+
+#[test]
+fn timers_count_frames_not_instructions() {
+    // Sets the delay timer to 0x10 and the sound timer to 3, then loops.
+    let rom = [0x60, 0x10, 0xF0, 0x15, 0x61, 0x03, 0xF1, 0x18, 0x12, 0x08];
+    for &backend in &[Backend::Interpreter, Backend::Threaded, Backend::Lockstep] {
+        let mut chip8 = Chip8::with_backend(backend);
+        chip8.load_program(&Program::from(&rom[..]));
+        let (keyboard, mut video_sink) = (Keyboard::new(), VideoSink::new());
+        let mut audio_sink = AudioSink::new();
+        chip8.run(100, &keyboard, &mut video_sink, &mut audio_sink);
+        assert!(matches!(audio_sink.event.take(), Some(AudioEvent::Play)));
+        let registers = chip8.registers();
+        assert_eq!((registers.delay_timer, registers.sound_timer), (0x10, 3));
+        for _ in 0..3 {
+            chip8.run(100, &keyboard, &mut video_sink, &mut audio_sink);
+            chip8.tick_timers(&mut audio_sink);
+        }
+        assert!(matches!(audio_sink.event, Some(AudioEvent::Stop)));
+        let registers = chip8.registers();
+        assert_eq!((registers.delay_timer, registers.sound_timer), (0x0D, 0));
+    }
+}
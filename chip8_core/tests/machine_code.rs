@@ -0,0 +1,82 @@
+extern crate chip8_core;
+
+mod common;
+
+use chip8_core::chip8::audio::AudioSink;
+use chip8_core::chip8::keyboard::Keyboard;
+use chip8_core::chip8::vram::VideoSink;
+use chip8_core::chip8::{Backend, Chip8, MachineCode};
+use chip8_core::program::Program;
+use common::{run, BACKENDS};
+
+fn with(machine_code: MachineCode) -> impl FnOnce(&mut Chip8) {
+    move |chip8| chip8.set_machine_code(machine_code)
+}
+
+// V2 = 3, call the 1802 routine at 0x206, then loop. The routine adds 5 to
+// VX, which is V2 as the address of a 02nn call starts with 2, and returns
+// with SEP R4.
+const ADD_TO_VX: &[u8] = &[
+    0x62, 0x03, 0x02, 0x06, 0x12, 0x04, //
+    0x06, // LDN R6
+    0xFC, 0x05, // ADI 5
+    0x56, // STR R6
+    0xD4, // SEP R4
+];
+
+#[test]
+fn runs_routines_against_the_vip_registers() {
+    for &backend in &BACKENDS {
+        let chip8 = run(backend, with(MachineCode::Cdp1802), ADD_TO_VX, 3);
+        assert_eq!(chip8.registers().v[2], 8);
+        assert_eq!(chip8.registers().pc, 0x204);
+        assert_eq!(chip8.memory()[0xEF2], 8);
+    }
+    let chip8 = run(Backend::Interpreter, with(MachineCode::Skip), ADD_TO_VX, 3);
+    assert_eq!(chip8.registers().v[2], 3);
+}
+
+#[test]
+fn routines_draw_on_the_display_page() {
+    // RB.0 = 0x08, M(RB) = 0xFF: the first byte of row 1
+    let rom = [
+        0x02, 0x04, 0x12, 0x02, //
+        0xF8, 0x08, 0xAB, 0xF8, 0xFF, 0x5B, 0xD4,
+    ];
+    let chip8 = run(Backend::Lockstep, with(MachineCode::Cdp1802), &rom, 2);
+    let pixels = chip8.pixel_buffer();
+    assert!(pixels.data[64..72].iter().all(|&pixel| pixel));
+    assert_eq!(pixels.data.iter().filter(|&&pixel| pixel).count(), 8);
+}
+
+#[test]
+fn skips_calls_that_cannot_work() {
+    // A call into the interpreter at 0x000, then a routine that never returns
+    let rom = [0x00, 0x00, 0x02, 0x06, 0x12, 0x04, 0x30, 0x06];
+    let chip8 = run(Backend::Interpreter, with(MachineCode::Cdp1802), &rom, 2);
+    assert_eq!(chip8.registers().pc, 0x204);
+}
+
+#[test]
+fn runs_the_clock_program_delay_loop() {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../programs/Clock Program [Bill Fisher, 1981].ch8"
+    );
+    let program = Program::from_path(path).unwrap();
+    let mut chip8 = common::machine(Backend::Interpreter, with(MachineCode::Cdp1802), &program);
+    // Jump straight to the 02D8 that paces the clock
+    chip8.write_memory(0x200, 0x12);
+    chip8.write_memory(0x201, 0x66);
+    chip8.step(
+        &Keyboard::new(),
+        &mut VideoSink::new(),
+        &mut AudioSink::new(),
+    );
+    chip8.step(
+        &Keyboard::new(),
+        &mut VideoSink::new(),
+        &mut AudioSink::new(),
+    );
+    assert_eq!(chip8.registers().pc, 0x268);
+}
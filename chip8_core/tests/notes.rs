@@ -0,0 +1,83 @@
+extern crate chip8_core;
+
+use chip8_core::notes::{FileName, Notes};
+
+const PROGRAMS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../programs");
+
+#[test]
+fn parses_the_file_name_conventions() {
+    let name = FileName::parse("Astro Dodge [Revival Studios, 2008].ch8");
+    assert_eq!(name.title, "Astro Dodge");
+    assert_eq!(name.authors, vec!["Revival Studios"]);
+    assert_eq!(name.year.as_deref(), Some("2008"));
+    assert!(!name.alt);
+
+    let name = FileName::parse("Guess [David Winter] (alt).ch8");
+    assert_eq!(
+        (name.title.as_str(), name.year, name.alt),
+        ("Guess", None, true)
+    );
+
+    let name = FileName::parse("Brick (Brix hack, 1990).ch8");
+    assert_eq!(name.title, "Brick (Brix hack)");
+    assert!(name.authors.is_empty());
+    assert_eq!(name.year.as_deref(), Some("1990"));
+
+    let name = FileName::parse("Trip8 Demo (2008) [Revival Studios].ch8");
+    assert_eq!(name.title, "Trip8 Demo");
+    assert_eq!(name.year.as_deref(), Some("2008"));
+
+    let name = FileName::parse("Hires Maze [David Winter, 199x].ch8");
+    assert_eq!(name.year.as_deref(), Some("199x"));
+
+    assert_eq!(FileName::parse("IBM Logo.ch8").title, "IBM Logo");
+}
+
+#[test]
+fn parses_the_header_block() {
+    let notes = Notes::find(format!(
+        "{}/Astro Dodge [Revival Studios, 2008].ch8",
+        PROGRAMS
+    ))
+    .unwrap();
+    assert_eq!(notes.title.as_deref(), Some("Astro Dodge"));
+    assert_eq!(notes.authors, vec!["Martijn Wenting", "Revival Studios"]);
+    assert_eq!(notes.date.as_deref(), Some("18/12/2008"));
+    assert_eq!(notes.system.as_deref(), Some("Chip8 / SuperChip8"));
+    assert_eq!(
+        notes.controls,
+        vec!["Button 2,4,6,8 will move your ship, button 5 will start the game."]
+    );
+    assert_eq!(notes.keys, vec![2, 4, 5, 6, 8]);
+}
+
+#[test]
+fn parses_the_credits_line() {
+    let notes = Notes::parse("Keypad Test, by hap, 15-02-06\n\npress a chip8 key\n");
+    assert_eq!(notes.title.as_deref(), Some("Keypad Test"));
+    assert_eq!(notes.authors, vec!["hap"]);
+    assert_eq!(notes.date.as_deref(), Some("15-02-06"));
+
+    let notes = Notes::parse("BRICK: a modified version of BRIX, a CHIP-8 game.");
+    assert_eq!(notes.title, None);
+    assert!(notes.authors.is_empty());
+}
+
+#[test]
+fn finds_keys_only_where_the_controls_are_explained() {
+    let notes = Notes::parse(
+        "Sequence Shoot\r\n\r\nPressing the Key C shoots the top target, Key D the one below\r\n\
+         it.  Keys 2-4-6-8 and 0 move.  A player has 3 lives.  The puck travels for\r\n\
+         2.5 seconds when you hold the key.  Runs on any Chip-8 or\r\nSuperChip compatible emulator.",
+    );
+    assert_eq!(notes.keys, vec![0, 2, 4, 6, 8, 0xC, 0xD]);
+    assert_eq!(notes.controls.len(), 3);
+    assert!(notes.controls[0].ends_with("the one below it."));
+}
+
+#[test]
+fn alt_builds_share_the_notes_of_the_original() {
+    let notes = Notes::find(format!("{}/15 Puzzle [Roger Ivie] (alt).ch8", PROGRAMS)).unwrap();
+    assert_eq!(notes.keys, vec![2, 4, 6, 8]);
+    assert!(Notes::find(format!("{}/IBM Logo.ch8", PROGRAMS)).is_none());
+}
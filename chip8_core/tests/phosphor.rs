@@ -0,0 +1,198 @@
+extern crate chip8_core;
+
+use chip8_core::chip8::phosphor::{Persistence, Phosphor};
+use chip8_core::chip8::vram::{PixelBuffer, Rect, VideoSink, Vram, HEIGHT, HIRES_HEIGHT, WIDTH};
+
+// A 4x1 screen with the given pixels lit.
+fn screen(pixels: [bool; 4]) -> PixelBuffer {
+    PixelBuffer {
+        width: 4,
+        height: 1,
+        data: pixels.to_vec(),
+        colors: None,
+    }
+}
+
+fn show(phosphor: &mut Phosphor, sink: &mut VideoSink, pixels: [bool; 4]) -> bool {
+    sink.push(screen(pixels));
+    phosphor.update(sink);
+    phosphor.frame()
+}
+
+#[test]
+fn sink_remembers_every_pixel_lit() {
+    let mut sink = VideoSink::new();
+    assert!(sink.take_lit().is_empty());
+    sink.push(screen([true, false, false, false]));
+    sink.push(screen([false, true, false, false]));
+    assert_eq!(sink.take_lit(), vec![true, true, false, false]);
+    assert!(sink.take_lit().is_empty());
+    assert_eq!(sink.get().unwrap().data, vec![false, true, false, false]);
+}
+
+#[test]
+fn off_follows_the_screen() {
+    let mut phosphor = Phosphor::new(Persistence::Off);
+    let mut sink = VideoSink::new();
+    assert!(show(&mut phosphor, &mut sink, [true, false, true, false]));
+    assert_eq!((phosphor.width(), phosphor.height()), (4, 1));
+    assert_eq!(phosphor.levels(), &[1.0, 0.0, 1.0, 0.0]);
+    assert!(show(&mut phosphor, &mut sink, [false, false, true, false]));
+    assert_eq!(phosphor.levels(), &[0.0, 0.0, 1.0, 0.0]);
+    assert!(!phosphor.frame());
+}
+
+#[test]
+fn blend_mixes_in_the_frame_before() {
+    let mut phosphor = Phosphor::new(Persistence::Blend);
+    let mut sink = VideoSink::new();
+    show(&mut phosphor, &mut sink, [true, true, false, false]);
+    assert_eq!(phosphor.levels(), &[0.5, 0.5, 0.0, 0.0]);
+    show(&mut phosphor, &mut sink, [true, false, false, false]);
+    assert_eq!(phosphor.levels(), &[1.0, 0.5, 0.0, 0.0]);
+    assert!(phosphor.frame());
+    assert_eq!(phosphor.levels(), &[1.0, 0.0, 0.0, 0.0]);
+    assert!(!phosphor.frame());
+}
+
+#[test]
+fn decay_halves_every_half_life() {
+    let mut phosphor = Phosphor::new(Persistence::Decay { half_life: 2 });
+    let mut sink = VideoSink::new();
+    show(&mut phosphor, &mut sink, [true, false, false, false]);
+    show(&mut phosphor, &mut sink, [false, false, false, false]);
+    phosphor.frame();
+    assert!((phosphor.levels()[0] - 0.5).abs() < 0.001);
+    phosphor.frame();
+    phosphor.frame();
+    assert!((phosphor.levels()[0] - 0.25).abs() < 0.001);
+
+    // The fade ends once it is too dim to see
+    let frames = (0..100).take_while(|_| phosphor.frame()).count();
+    assert!(frames < 20);
+    assert_eq!(phosphor.levels()[0], 0.0);
+
+    // A pixel lit again shows at full brightness
+    show(&mut phosphor, &mut sink, [true, false, false, false]);
+    assert_eq!(phosphor.levels()[0], 1.0);
+}
+
+#[test]
+fn vblank_only_shows_sprites_erased_within_the_frame() {
+    let mut phosphor = Phosphor::new(Persistence::Off);
+    phosphor.set_vblank_only(true);
+    let mut sink = VideoSink::new();
+    // Erased, then drawn one pixel over before the frame ends
+    sink.push(screen([true, false, false, false]));
+    sink.push(screen([false, false, false, false]));
+    assert!(!phosphor.update(&mut sink));
+    sink.push(screen([false, true, false, false]));
+    assert!(!phosphor.update(&mut sink));
+    assert!(phosphor.frame());
+    assert_eq!(phosphor.levels(), &[1.0, 1.0, 0.0, 0.0]);
+
+    // The next frame starts from the screen as it was left
+    assert!(phosphor.frame());
+    assert_eq!(phosphor.levels(), &[0.0, 1.0, 0.0, 0.0]);
+}
+
+#[test]
+fn switching_resolution_starts_dark() {
+    let mut phosphor = Phosphor::new(Persistence::Decay { half_life: 10 });
+    let mut sink = VideoSink::new();
+    show(&mut phosphor, &mut sink, [true, true, true, true]);
+    sink.push(PixelBuffer {
+        width: 2,
+        height: 1,
+        data: vec![false, true],
+        colors: None,
+    });
+    phosphor.update(&mut sink);
+    phosphor.frame();
+    assert_eq!(phosphor.levels(), &[0.0, 1.0]);
+}
+
+#[test]
+fn switching_resolution_resizes_the_color_board() {
+    let mut phosphor = Phosphor::new(Persistence::Off);
+    let mut sink = VideoSink::new();
+    let mut vram = Vram::new();
+    vram.set_color_board(true);
+    vram.flush(&mut sink);
+    phosphor.update(&mut sink);
+    vram.set_hires(true);
+    vram.flush(&mut sink);
+    phosphor.update(&mut sink);
+    phosphor.frame();
+    assert_eq!((phosphor.width(), phosphor.height()), (WIDTH, HIRES_HEIGHT));
+    assert_eq!(
+        phosphor.to_rgb([255, 255, 255], [0, 0, 0]).len(),
+        WIDTH * HIRES_HEIGHT * 3
+    );
+}
+
+#[test]
+fn rgb_mixes_between_the_colors() {
+    let mut phosphor = Phosphor::new(Persistence::Blend);
+    let mut sink = VideoSink::new();
+    show(&mut phosphor, &mut sink, [true, false, false, false]);
+    show(&mut phosphor, &mut sink, [true, true, false, false]);
+    let rgb = phosphor.to_rgb([200, 100, 0], [0, 0, 100]);
+    assert_eq!(rgb, vec![200, 100, 0, 100, 50, 50, 0, 0, 100, 0, 0, 100]);
+}
+
+#[test]
+fn only_what_changed_is_dirty() {
+    let mut phosphor = Phosphor::new(Persistence::Off);
+    let mut sink = VideoSink::new();
+    let mut vram = Vram::new();
+    vram.draw_sprite(&[0xFF], 0, 0, true, &mut sink);
+    phosphor.update(&mut sink);
+    assert!(phosphor.frame());
+    let whole = Rect {
+        x: 0,
+        y: 0,
+        width: WIDTH,
+        height: HEIGHT,
+    };
+    assert_eq!(phosphor.take_dirty(), vec![whole]);
+
+    vram.draw_sprite(&[0xC0, 0xC0], 10, 20, true, &mut sink);
+    phosphor.update(&mut sink);
+    assert!(phosphor.frame());
+    let sprite = Rect {
+        x: 10,
+        y: 20,
+        width: 8,
+        height: 2,
+    };
+    assert_eq!(phosphor.take_dirty(), vec![sprite]);
+    assert!(phosphor.take_dirty().is_empty());
+    let rgb = phosphor.rect_to_rgb(sprite, [9, 9, 9], [0, 0, 0]);
+    assert_eq!(rgb.len(), 8 * 2 * 3);
+    assert_eq!(&rgb[..9], &[9, 9, 9, 9, 9, 9, 0, 0, 0]);
+    assert_eq!(&rgb[24..30], &[9, 9, 9, 9, 9, 9]);
+    // The rest of the screen is still as the first sprite left it.
+    assert_eq!(
+        &phosphor.levels()[..9],
+        &[1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0]
+    );
+}
+
+#[test]
+fn fading_dirties_the_whole_screen() {
+    let mut phosphor = Phosphor::new(Persistence::Blend);
+    let mut sink = VideoSink::new();
+    show(&mut phosphor, &mut sink, [true, false, false, false]);
+    phosphor.take_dirty();
+    assert!(phosphor.frame());
+    let whole = Rect {
+        x: 0,
+        y: 0,
+        width: 4,
+        height: 1,
+    };
+    assert_eq!(phosphor.take_dirty(), vec![whole]);
+    assert!(!phosphor.frame());
+    assert!(phosphor.take_dirty().is_empty());
+}
@@ -0,0 +1,37 @@
+extern crate chip8_core;
+
+use chip8_core::chip8::Chip8;
+use chip8_core::program::{Program, ProgramError, Variant};
+
+#[test]
+fn rejects_roms_that_do_not_fit_in_memory() {
+    assert!(Program::from_bytes(&[0; 3584]).is_ok());
+    match Program::from_bytes(&[0; 3585]) {
+        Err(ProgramError::TooLarge { size, max }) => assert_eq!((size, max), (3585, 3584)),
+        other => panic!("expected TooLarge, got {:?}", other.err()),
+    }
+    assert!(Program::with_layout(&[0; 3584], Variant::SuperChip, 0x200).is_ok());
+    assert!(Program::with_layout(&[0; 3585], Variant::SuperChip, 0x200).is_err());
+    assert!(Program::with_layout(&[0; 2561], Variant::Chip8, 0x600).is_err());
+    assert!(Program::with_layout(&[], Variant::Chip8, 0x1000).is_err());
+}
+
+#[test]
+fn loads_at_the_requested_address() {
+    let program = Program::with_layout(&[0x12, 0x34], Variant::Chip8, 0x600).unwrap();
+    let mut chip8 = Chip8::new();
+    chip8.load_program(&program);
+    assert_eq!(chip8.registers().pc, 0x600);
+    assert_eq!(&chip8.memory()[0x600..0x602], &[0x12, 0x34]);
+    assert_eq!(&chip8.memory()[0x200..0x202], &[0, 0]);
+}
+
+#[test]
+fn identifies_roms_by_sha1() {
+    let program = Program::from_bytes(b"abc").unwrap();
+    assert_eq!(
+        program.sha1_hex(),
+        "a9993e364706816aba3e25717850c26c9cd0d89d"
+    );
+    assert_eq!(program.sha1()[..4], [0xa9, 0x99, 0x3e, 0x36]);
+}
@@ -0,0 +1,110 @@
+extern crate chip8_core;
+
+mod common;
+
+use chip8_core::chip8::quirks::Quirks;
+use chip8_core::chip8::vram::WIDTH;
+use chip8_core::chip8::{Backend, Chip8};
+use common::run;
+
+fn with(quirks: Quirks) -> impl FnOnce(&mut Chip8) {
+    move |chip8| chip8.set_quirks(quirks)
+}
+
+fn vip() -> Quirks {
+    Quirks {
+        shift: false,
+        memory_increment_by_x: false,
+        memory_leave_i_unchanged: false,
+        wrap: false,
+        jump: false,
+        vblank: true,
+        logic: true,
+    }
+}
+
+#[test]
+fn shift_reads_vy_unless_the_quirk_is_set() {
+    // V0 = 0x01, V1 = 0x80, V0 <<= V1
+    let rom = [0x60, 0x01, 0x61, 0x80, 0x80, 0x1E];
+    let chip8 = run(Backend::Lockstep, with(vip()), &rom, 3);
+    assert_eq!(
+        (chip8.registers().v[0], chip8.registers().v[0xF]),
+        (0x00, 1)
+    );
+    let chip8 = run(Backend::Lockstep, with(Quirks::default()), &rom, 3);
+    assert_eq!(
+        (chip8.registers().v[0], chip8.registers().v[0xF]),
+        (0x02, 0)
+    );
+}
+
+#[test]
+fn logic_resets_vf_with_the_quirk() {
+    // VF = 1, V0 |= V1
+    let rom = [0x6F, 0x01, 0x80, 0x11];
+    let chip8 = run(Backend::Lockstep, with(vip()), &rom, 2);
+    assert_eq!(chip8.registers().v[0xF], 0);
+    let chip8 = run(Backend::Lockstep, with(Quirks::default()), &rom, 2);
+    assert_eq!(chip8.registers().v[0xF], 1);
+}
+
+#[test]
+fn memory_quirks_control_how_i_moves() {
+    // I = 0x300, store V0..V2
+    let rom = [0xA3, 0x00, 0xF2, 0x55];
+    let i = |quirks| {
+        run(Backend::Interpreter, with(quirks), &rom, 2)
+            .registers()
+            .i
+    };
+    assert_eq!(i(vip()), 0x303);
+    assert_eq!(
+        i(Quirks {
+            memory_increment_by_x: true,
+            ..vip()
+        }),
+        0x302
+    );
+    assert_eq!(
+        i(Quirks {
+            memory_leave_i_unchanged: true,
+            ..vip()
+        }),
+        0x300
+    );
+}
+
+#[test]
+fn jump_adds_vx_with_the_quirk() {
+    // V0 = 0x10, V3 = 0x20, jump to 0x300 + V0 or V3
+    let rom = [0x60, 0x10, 0x63, 0x20, 0xB3, 0x00];
+    let pc = |quirks| {
+        run(Backend::Interpreter, with(quirks), &rom, 3)
+            .registers()
+            .pc
+    };
+    assert_eq!(pc(vip()), 0x310);
+    assert_eq!(
+        pc(Quirks {
+            jump: true,
+            ..vip()
+        }),
+        0x320
+    );
+}
+
+#[test]
+fn sprites_clip_at_the_edge_unless_they_wrap() {
+    // V0 = 62, I = font "0", draw its top row (0xF0) at (V0, V1) = (62, 0)
+    let rom = [0x60, 62, 0xA0, 0x00, 0xD0, 0x11];
+    let pixels = |quirks| {
+        run(Backend::Interpreter, with(quirks), &rom, 3)
+            .pixel_buffer()
+            .data
+    };
+    let clipped = pixels(vip());
+    assert!(clipped[62] && clipped[WIDTH - 1] && !clipped[0] && !clipped[1]);
+    let wrapped = pixels(Quirks::default());
+    assert!(wrapped[62] && wrapped[WIDTH - 1] && wrapped[0] && wrapped[1]);
+}
@@ -0,0 +1,209 @@
+extern crate chip8_core;
+
+use chip8_core::chip8::recording::{Recording, MAX_SCALE};
+use chip8_core::chip8::vram::PixelBuffer;
+
+const ON: [u8; 3] = [0xFF, 0xFF, 0xFF];
+const OFF: [u8; 3] = [0x00, 0x00, 0x00];
+
+fn screen(width: usize, height: usize, lit: impl FnMut(usize) -> bool) -> PixelBuffer {
+    PixelBuffer {
+        width,
+        height,
+        data: (0..width * height).map(lit).collect(),
+        colors: None,
+    }
+}
+
+// Reads codes least significant bit first.
+struct Bits<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Bits<'a> {
+    fn read(&mut self, size: usize) -> usize {
+        let mut code = 0;
+        for bit in 0..size {
+            let at = self.position + bit;
+            code |= ((self.bytes[at / 8] >> (at % 8)) as usize & 1) << bit;
+        }
+        self.position += size;
+        code
+    }
+}
+
+fn lzw_decode(bytes: &[u8], min_size: usize) -> Vec<u8> {
+    let (clear, end) = (1 << min_size, (1 << min_size) + 1);
+    let mut bits = Bits { bytes, position: 0 };
+    let mut table: Vec<Vec<u8>> = Vec::new();
+    let mut size = min_size + 1;
+    let mut previous: Option<Vec<u8>> = None;
+    let mut out = Vec::new();
+    loop {
+        let code = bits.read(size);
+        if code == clear {
+            table = (0..clear).map(|index| vec![index as u8]).collect();
+            table.push(Vec::new());
+            table.push(Vec::new());
+            size = min_size + 1;
+            previous = None;
+            continue;
+        }
+        if code == end {
+            return out;
+        }
+        let entry = match previous {
+            None => table[code].clone(),
+            Some(ref previous) => {
+                let entry = if code < table.len() {
+                    table[code].clone()
+                } else {
+                    let mut entry = previous.clone();
+                    entry.push(previous[0]);
+                    entry
+                };
+                if table.len() < 0x1000 {
+                    let mut new = previous.clone();
+                    new.push(entry[0]);
+                    table.push(new);
+                }
+                if table.len() == 1 << size && size < 12 {
+                    size += 1;
+                }
+                entry
+            }
+        };
+        out.extend_from_slice(&entry);
+        previous = Some(entry);
+    }
+}
+
+// Every frame's delay and color indexes.
+type Frames = Vec<(u16, Vec<u8>)>;
+
+fn decode(gif: &[u8]) -> ((usize, usize), Frames) {
+    assert_eq!(&gif[..6], b"GIF89a");
+    let word = |at: usize| u16::from_le_bytes([gif[at], gif[at + 1]]);
+    let size = (word(6) as usize, word(8) as usize);
+    let mut at = 13 + 3 * (2 << (gif[10] & 7));
+    let mut delay = 0;
+    let mut frames = Vec::new();
+    let sub_blocks = |at: &mut usize| {
+        let mut data = Vec::new();
+        while gif[*at] != 0 {
+            data.extend_from_slice(&gif[*at + 1..*at + 1 + gif[*at] as usize]);
+            *at += gif[*at] as usize + 1;
+        }
+        *at += 1;
+        data
+    };
+    loop {
+        match gif[at] {
+            0x3B => return (size, frames),
+            0x21 => {
+                if gif[at + 1] == 0xF9 {
+                    delay = word(at + 4);
+                }
+                at += 2;
+                sub_blocks(&mut at);
+            }
+            0x2C => {
+                at += 10;
+                let min_size = gif[at] as usize;
+                at += 1;
+                let data = sub_blocks(&mut at);
+                frames.push((delay, lzw_decode(&data, min_size)));
+            }
+            block => panic!("Unexpected block {:02X}", block),
+        }
+    }
+}
+
+#[test]
+fn frames_decode_to_the_screen() {
+    let mut recording = Recording::new(2, ON, OFF).unwrap();
+    let first = screen(4, 2, |pixel| pixel == 1);
+    recording.frame(&first);
+    recording.frame(&first);
+    recording.frame(&screen(4, 2, |_| true));
+    let (size, frames) = decode(&recording.finish().unwrap());
+    assert_eq!(size, (8, 4));
+    assert_eq!(frames.len(), 2);
+    let row = [0, 0, 1, 1, 0, 0, 0, 0];
+    let zeros = [0; 8];
+    assert_eq!(frames[0].1, [row, row, zeros, zeros].concat());
+    assert_eq!(frames[1].1, vec![1; 32]);
+}
+
+#[test]
+fn delays_keep_to_60_hz() {
+    // A new screen every tick, so only every other one lasts long enough.
+    let mut recording = Recording::new(1, ON, OFF).unwrap();
+    for tick in 0..120 {
+        recording.frame(&screen(64, 32, |pixel| pixel == tick));
+    }
+    let (_, frames) = decode(&recording.finish().unwrap());
+    assert!(frames.iter().all(|&(delay, _)| delay >= 2));
+    let total: u16 = frames.iter().map(|&(delay, _)| delay).sum();
+    assert_eq!(total, 200);
+}
+
+#[test]
+fn long_frames_are_repeated() {
+    // 40000 ticks of the same screen last 66667 hundredths of a second.
+    let mut recording = Recording::new(1, ON, OFF).unwrap();
+    let blank = screen(64, 32, |_| false);
+    for _ in 0..40_000 {
+        recording.frame(&blank);
+    }
+    let (_, frames) = decode(&recording.finish().unwrap());
+    let delays: Vec<u16> = frames.iter().map(|&(delay, _)| delay).collect();
+    assert_eq!(delays, vec![0xFFFF, 1132]);
+    assert_eq!(frames[0].1, frames[1].1);
+}
+
+#[test]
+fn busy_screens_clear_the_code_table() {
+    // Noise runs past 4096 codes.
+    let mut recording = Recording::new(2, ON, OFF).unwrap();
+    let mut seed = 1u32;
+    let noise = screen(64, 64, |_| {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed & 1 == 1
+    });
+    recording.frame(&noise);
+    let (_, frames) = decode(&recording.finish().unwrap());
+    let expected: Vec<u8> = (0..128 * 128)
+        .map(|index| noise.data[(index / 128 / 2) * 64 + index % 128 / 2] as u8)
+        .collect();
+    assert_eq!(frames[0].1, expected);
+}
+
+#[test]
+fn other_sizes_are_left_out() {
+    let mut recording = Recording::new(1, ON, OFF).unwrap();
+    recording.frame(&screen(64, 32, |_| false));
+    recording.frame(&screen(64, 64, |_| true));
+    assert_eq!(recording.frames(), 2);
+    let (size, frames) = decode(&recording.finish().unwrap());
+    assert_eq!(size, (64, 32));
+    assert_eq!(frames.len(), 1);
+}
+
+#[test]
+fn scales_too_large_for_a_gif_are_refused() {
+    assert!(Recording::new(MAX_SCALE, ON, OFF).is_some());
+    assert!(Recording::new(MAX_SCALE + 1, ON, OFF).is_none());
+    // Screens of other sizes could still overflow, and are left out.
+    let mut recording = Recording::new(MAX_SCALE, ON, OFF).unwrap();
+    recording.frame(&screen(128, 1, |_| false));
+    assert_eq!(recording.finish(), None);
+}
+
+#[test]
+fn nothing_recorded_is_no_gif() {
+    assert_eq!(Recording::new(1, ON, OFF).unwrap().finish(), None);
+}
@@ -0,0 +1,65 @@
+extern crate chip8_core;
+
+use chip8_core::chip8::audio::AudioSink;
+use chip8_core::chip8::keyboard::Keyboard;
+use chip8_core::chip8::vram::{PixelBuffer, VideoSink, Vram};
+use chip8_core::chip8::Chip8;
+use chip8_core::program::Program;
+
+const ON: [u8; 3] = [0x33, 0xFF, 0x66];
+const OFF: [u8; 3] = [0x11, 0x22, 0x33];
+
+// The font's "0" at (0, 0) and "1" at (60, 28), hanging off the corner.
+fn screen() -> PixelBuffer {
+    let rom = [
+        0xA0, 0x00, 0xD0, 0x05, 0x60, 60, 0x61, 28, 0xA0, 0x05, 0xD0, 0x15,
+    ];
+    let mut chip8 = Chip8::new();
+    chip8.load_program(&Program::from(&rom[..]));
+    chip8.run(
+        6,
+        &Keyboard::new(),
+        &mut VideoSink::new(),
+        &mut AudioSink::new(),
+    );
+    chip8.pixel_buffer()
+}
+
+#[test]
+fn png_matches_the_reference() {
+    assert_eq!(
+        screen().to_png(1, ON, OFF),
+        &include_bytes!("images/screen.png")[..]
+    );
+    assert_eq!(
+        screen().to_png(3, ON, OFF),
+        &include_bytes!("images/screen_x3.png")[..]
+    );
+}
+
+#[test]
+fn pbm_matches_the_reference() {
+    assert_eq!(screen().to_pbm(), &include_bytes!("images/screen.pbm")[..]);
+}
+
+#[test]
+fn ascii_draws_rows_top_down() {
+    let ascii = screen().to_ascii();
+    let lines: Vec<&str> = ascii.lines().collect();
+    assert_eq!(lines.len(), 32);
+    assert!(lines.iter().all(|line| line.len() == 64));
+    assert_eq!(&lines[0][..4], "####");
+    assert_eq!(&lines[1][..4], "#  #");
+    assert_eq!(&lines[28][60..], "  # ");
+    assert_eq!(&lines[31][60..], "  # ");
+}
+
+#[test]
+fn vram_debug_matches_ascii() {
+    // Wider than it is tall, so rows are not `HEIGHT` pixels apart.
+    let mut vram = Vram::new();
+    vram.draw_sprite(&[0xFF], 0, 1, false, &mut VideoSink::new());
+    let debug = format!("{:?}", vram);
+    assert_eq!(debug, vram.pixel_buffer().to_ascii());
+    assert_eq!(debug.lines().nth(1), Some(&*format!("{:<64}", "########")));
+}
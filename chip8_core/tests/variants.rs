@@ -0,0 +1,120 @@
+extern crate chip8_core;
+
+mod common;
+
+use chip8_core::chip8::audio::AudioSink;
+use chip8_core::chip8::keyboard::Keyboard;
+use chip8_core::chip8::vram::{Color, VideoSink, WIDTH};
+use chip8_core::chip8::{Backend, Chip8};
+use chip8_core::program::{Program, Variant};
+use common::BACKENDS;
+
+// The listings are in the `.txt` next to each ROM.
+const CHIP8X: &[u8] = include_bytes!("roms/chip8x.ch8");
+const CHIP8E: &[u8] = include_bytes!("roms/chip8e.ch8");
+const ETI660: &[u8] = include_bytes!("roms/eti660.ch8");
+
+// One instruction per frame, so the timers count down after each.
+fn frames(chip8: &mut Chip8, cycles: usize) {
+    let (keyboard, mut video_sink, mut audio_sink) =
+        (Keyboard::new(), VideoSink::new(), AudioSink::new());
+    for _ in 0..cycles {
+        chip8.run(1, &keyboard, &mut video_sink, &mut audio_sink);
+        chip8.tick_timers(&mut audio_sink);
+    }
+}
+
+fn run(backend: Backend, rom: &[u8], variant: Variant, cycles: usize) -> Chip8 {
+    let program = Program::for_variant(rom, variant).unwrap();
+    let mut chip8 = common::machine(backend, |_| {}, &program);
+    frames(&mut chip8, cycles);
+    chip8
+}
+
+#[test]
+fn chip8x_colors_the_screen() {
+    for &backend in &BACKENDS {
+        let chip8 = run(backend, CHIP8X, Variant::Chip8X, 100);
+        let registers = chip8.registers();
+        assert_eq!(registers.pc, 0x32C);
+        assert_eq!(registers.v[0x8], 0x73);
+        assert_eq!((registers.v[0xB], registers.v[0xC]), (1, 0));
+
+        let pixels = chip8.pixel_buffer();
+        assert!(pixels.data[0]);
+        let colors = pixels.colors.as_ref().unwrap();
+        assert_eq!(colors.background, Color::Black);
+        let color = |x: usize, y: usize| colors.foreground[y * WIDTH + x];
+        assert_eq!((color(0, 0), color(15, 3)), (Color::Green, Color::Green));
+        assert_eq!((color(0, 4), color(16, 0)), (Color::Red, Color::Red));
+        assert_eq!((color(16, 8), color(23, 10)), (Color::Aqua, Color::Aqua));
+        assert_eq!(color(16, 11), Color::Red);
+        assert_eq!(
+            &pixels.to_rgb([1; 3], [0; 3])[..6],
+            &[0, 0xFF, 0, 0, 0xFF, 0]
+        );
+    }
+}
+
+#[test]
+fn only_chip8x_has_a_color_board() {
+    let chip8 = run(Backend::Interpreter, CHIP8X, Variant::Chip8, 1);
+    assert!(chip8.pixel_buffer().colors.is_none());
+    let chip8 = run(Backend::Interpreter, CHIP8E, Variant::Chip8E, 1);
+    assert!(chip8.pixel_buffer().colors.is_none());
+}
+
+#[test]
+fn chip8e_runs_its_extensions() {
+    for &backend in &BACKENDS {
+        let chip8 = run(backend, CHIP8E, Variant::Chip8E, 200);
+        let registers = chip8.registers();
+        assert_eq!(registers.pc, 0x234);
+        assert_eq!(&registers.v[..3], &[0x22, 3, 5]);
+        assert_eq!((registers.v[0x5], registers.i), (3, 0x303));
+        assert_eq!(&registers.v[0xA..0xE], &[0; 4]);
+        assert_eq!(&chip8.memory()[0x300..0x303], &[5, 3, 0x22]);
+    }
+}
+
+#[test]
+fn chip8e_delay_waits_for_the_timer() {
+    // 16 instructions reach Fx4F, which then holds for 16 frames
+    let chip8 = run(Backend::Interpreter, CHIP8E, Variant::Chip8E, 32);
+    assert_eq!(chip8.registers().pc, 0x228);
+    let chip8 = run(Backend::Interpreter, CHIP8E, Variant::Chip8E, 33);
+    assert_eq!(chip8.registers().pc, 0x22A);
+}
+
+#[test]
+fn variant_instructions_are_plain_chip8_elsewhere() {
+    // 5011 compares for equality and BF02 jumps to 0xF02 + V0
+    let chip8 = run(Backend::Interpreter, CHIP8E, Variant::Chip8, 14);
+    let registers = chip8.registers();
+    assert_eq!((registers.v[0xA], registers.v[0xB]), (0xFF, 0xFF));
+    assert_eq!(registers.pc, 0xF02);
+}
+
+#[test]
+fn eti660_programs_start_at_0x600() {
+    let chip8 = run(Backend::Lockstep, ETI660, Variant::Eti660, 20);
+    let registers = chip8.registers();
+    assert_eq!((registers.pc, registers.v[2]), (0x60A, 1));
+    assert!(chip8.pixel_buffer().data[..4].iter().all(|&pixel| pixel));
+}
+
+#[test]
+fn snapshots_keep_the_variant_and_colors() {
+    let chip8 = run(Backend::Interpreter, CHIP8X, Variant::Chip8X, 100);
+    let mut restored = Chip8::new();
+    restored.load_state(&chip8.save_state()).unwrap();
+    assert_eq!(restored.variant(), Variant::Chip8X);
+    assert_eq!(restored.pixel_buffer().colors, chip8.pixel_buffer().colors);
+
+    // Halfway through the CHIP-8E Fx4F delay
+    let chip8 = run(Backend::Interpreter, CHIP8E, Variant::Chip8E, 24);
+    let mut restored = Chip8::new();
+    restored.load_state(&chip8.save_state()).unwrap();
+    frames(&mut restored, 9);
+    assert_eq!(restored.registers().pc, 0x22A);
+}
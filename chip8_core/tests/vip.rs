@@ -0,0 +1,187 @@
+extern crate chip8_core;
+
+use chip8_core::chip8::audio::{AudioEvent, AudioSink};
+use chip8_core::chip8::keyboard::{HexKey, Keyboard};
+use chip8_core::chip8::state::StateError;
+use chip8_core::chip8::vip::CYCLES_PER_FRAME;
+use chip8_core::chip8::vram::{VideoSink, HEIGHT, WIDTH};
+use chip8_core::chip8::{Backend, Chip8};
+use chip8_core::program::{Program, ProgramError};
+
+// Stands in for the VIP interpreter: sets up the registers the way it does,
+// with the timers at 5 and 3, leaves R0 to DMA, turns the display on and
+// then polls key A, writing 1 to V0 while it is held.
+const INTERPRETER: &[u8] = &[
+    0x91, 0xBB, // GHI R1, PHI RB: the display page
+    0xFF, 0x01, 0xB2, 0xB6, // SMI 01, PHI R2, PHI R6
+    0xF8, 0xCF, 0xA2, // LDI CF, PLO R2: the stack
+    0xF8, 0xF0, 0xA6, // LDI F0, PLO R6: V0
+    0xF8, 0x81, 0xB1, 0xF8, 0x46, 0xA1, // R1 = 0x8146
+    0xF8, 0x05, 0xB8, 0xF8, 0x03, 0xA8, // R8 = 0x0503
+    0xF8, 0x1C, 0xA4, 0xD4, // R4 = 0x01C, SEP R4
+    0xE2, 0x69, // SEX R2, INP 1
+    0xF8, 0x0A, 0x52, 0x62, 0x22, // 01E: OUT 2 with key A
+    0x36, 0x27, // B3 027
+    0x30, 0x1E, // BR 01E
+    0xF8, 0x01, 0x56, // 027: V0 = 1
+    0x30, 0x1E, // BR 01E
+];
+
+fn vip() -> Chip8 {
+    let mut chip8 = Chip8::with_backend(Backend::Vip);
+    chip8.set_vip_interpreter(INTERPRETER).unwrap();
+    chip8.load_program(&Program::from(&[0x12, 0x00][..]));
+    // Rows 0, 1 and 31 of the display page
+    for &(address, value) in &[(0xF00, 0xFF), (0xF08, 0x81), (0xFFF, 0x01)] {
+        chip8.write_memory(address, value);
+    }
+    chip8
+}
+
+fn frames(chip8: &mut Chip8, frames: usize, keyboard: &Keyboard) -> (VideoSink, AudioSink) {
+    let mut video_sink = VideoSink::new();
+    let mut audio_sink = AudioSink::new();
+    audio_sink.event = None;
+    chip8.run(
+        frames * CYCLES_PER_FRAME,
+        keyboard,
+        &mut video_sink,
+        &mut audio_sink,
+    );
+    (video_sink, audio_sink)
+}
+
+#[test]
+fn pixie_shows_the_display_page() {
+    let mut chip8 = vip();
+    let (mut video_sink, _) = frames(&mut chip8, 1, &Keyboard::new());
+    let pixels = video_sink.get().unwrap();
+    assert_eq!((pixels.width, pixels.height), (WIDTH, HEIGHT));
+    assert!(pixels.data[..8].iter().all(|&pixel| pixel));
+    assert!(!pixels.data[8]);
+    assert!(pixels.data[WIDTH] && pixels.data[WIDTH + 7]);
+    assert!(!pixels.data[WIDTH + 1]);
+    assert!(pixels.data[WIDTH * HEIGHT - 1]);
+    assert_eq!(pixels.data.iter().filter(|&&pixel| pixel).count(), 11);
+
+    // Nothing changed, so nothing more is sent
+    let (mut video_sink, _) = frames(&mut chip8, 1, &Keyboard::new());
+    assert!(video_sink.get().is_none());
+}
+
+#[test]
+fn interrupts_count_down_the_timers_and_sound() {
+    let mut chip8 = vip();
+    let (_, audio_sink) = frames(&mut chip8, 1, &Keyboard::new());
+    assert!(matches!(audio_sink.event, Some(AudioEvent::Play)));
+    assert_eq!(chip8.registers().delay_timer, 4);
+    assert_eq!(chip8.registers().sound_timer, 2);
+
+    let (_, audio_sink) = frames(&mut chip8, 2, &Keyboard::new());
+    assert!(matches!(audio_sink.event, Some(AudioEvent::Stop)));
+    assert_eq!(chip8.registers().delay_timer, 2);
+    assert_eq!(chip8.registers().sound_timer, 0);
+}
+
+#[test]
+fn keypad_answers_on_ef3() {
+    let mut chip8 = vip();
+    frames(&mut chip8, 1, &Keyboard::new());
+    assert_eq!(chip8.registers().v[0], 0);
+
+    let mut keyboard = Keyboard::new();
+    keyboard.update_key(HexKey::X5, true);
+    frames(&mut chip8, 1, &keyboard);
+    assert_eq!(chip8.registers().v[0], 0);
+    keyboard.update_key(HexKey::Xa, true);
+    frames(&mut chip8, 1, &keyboard);
+    assert_eq!(chip8.registers().v[0], 1);
+    assert_eq!(chip8.memory()[0xEF0], 1);
+}
+
+#[test]
+fn snapshots_stay_with_their_backend() {
+    let mut chip8 = vip();
+    frames(&mut chip8, 1, &Keyboard::new());
+    let state = chip8.save_state();
+
+    let mut restored = vip();
+    restored.load_state(&state).unwrap();
+    frames(&mut chip8, 2, &Keyboard::new());
+    frames(&mut restored, 2, &Keyboard::new());
+    assert_eq!(restored.registers(), chip8.registers());
+    assert_eq!(restored.memory(), chip8.memory());
+    assert_eq!(restored.pixel_buffer().data, chip8.pixel_buffer().data);
+
+    assert_eq!(
+        Chip8::new().load_state(&state),
+        Err(StateError::WrongBackend)
+    );
+    assert_eq!(
+        restored.load_state(&Chip8::new().save_state()),
+        Err(StateError::WrongBackend)
+    );
+}
+
+#[test]
+fn interpreter_must_fit_below_0x200() {
+    let mut chip8 = Chip8::with_backend(Backend::Vip);
+    match chip8.set_vip_interpreter(&[0; 0x201]) {
+        Err(ProgramError::TooLarge { size, max }) => assert_eq!((size, max), (0x201, 0x200)),
+        other => panic!("{:?}", other),
+    }
+}
+
+// Sets up like INTERPRETER, then waits for a key with the same calls the VIP
+// interpreter makes for Fx0A, keeps it in V0 and sets V1.
+const WAIT_FOR_KEY: &[u8] = &[
+    0x91, 0xBB, // GHI R1, PHI RB: the display page
+    0xFF, 0x01, 0xB2, 0xB6, // SMI 01, PHI R2, PHI R6
+    0xF8, 0xCF, 0xA2, // LDI CF, PLO R2: the stack
+    0xF8, 0xF0, 0xA6, // LDI F0, PLO R6: V0
+    0xF8, 0x81, 0xB1, 0xF8, 0x46, 0xA1, // R1 = 0x8146
+    0xF8, 0x18, 0xA3, 0xD3, // R3 = 0x018, SEP R3
+    0x00, 0x00, //
+    0xE2, 0x69, // 018: SEX R2, INP 1
+    0xF8, 0x81, 0xBC, 0xF8, 0x95, 0xAC, // RC = 0x8195
+    0x22, 0xDC, 0x12, 0x56, // DEC R2, SEP RC, INC R2, STR R6
+    0x16, 0xF8, 0x01, 0x56, // INC R6, LDI 01, STR R6
+    0x30, 0x2A, // 02A: BR 02A
+];
+
+fn wait_for_key(chip8: &mut Chip8) {
+    let mut keyboard = Keyboard::new();
+    frames(chip8, 2, &keyboard);
+    assert_eq!(chip8.registers().v[1], 0);
+
+    // The key is only taken once it is let go.
+    keyboard.update_key(HexKey::X7, true);
+    frames(chip8, 3, &keyboard);
+    assert_eq!(chip8.registers().v[1], 0);
+    keyboard.update_key(HexKey::X7, false);
+    frames(chip8, 2, &keyboard);
+    assert_eq!(chip8.registers().v[0], 7);
+    assert_eq!(chip8.registers().v[1], 1);
+}
+
+#[test]
+fn monitor_waits_for_a_key() {
+    let mut chip8 = Chip8::with_backend(Backend::Vip);
+    chip8.set_vip_interpreter(WAIT_FOR_KEY).unwrap();
+    chip8.load_program(&Program::from(&[0x12, 0x00][..]));
+    wait_for_key(&mut chip8);
+}
+
+// The VIP interpreter is not ours to ship either, so this one runs with the
+// image in VIP_INTERPRETER: `cargo test -- --ignored`.
+#[test]
+#[ignore]
+fn interpreter_waits_for_a_key() {
+    let path = std::env::var("VIP_INTERPRETER").expect("VIP_INTERPRETER is not set");
+    let interpreter = std::fs::read(path).unwrap();
+    let mut chip8 = Chip8::with_backend(Backend::Vip);
+    chip8.set_vip_interpreter(&interpreter).unwrap();
+    // F00A, V1 = 1, then loop.
+    chip8.load_program(&Program::from(&[0xF0, 0x0A, 0x61, 0x01, 0x12, 0x04][..]));
+    wait_for_key(&mut chip8);
+}
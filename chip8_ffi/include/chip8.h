@@ -0,0 +1,115 @@
+#ifndef CHIP8_H
+#define CHIP8_H
+
+/* Generated by cbindgen from chip8_ffi. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct Chip8 Chip8;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates an emulator. `seed` drives the `Cxkk` random instruction.
+ * Release it with `chip8_destroy`.
+ */
+struct Chip8 *chip8_create(uint32_t seed);
+
+/**
+ * # Safety
+ * `chip8` must come from `chip8_create` and not be used afterwards.
+ */
+void chip8_destroy(struct Chip8 *chip8);
+
+/**
+ * Resets the machine and loads `len` bytes of ROM. Returns false if `data` is null or the ROM
+ * does not fit in memory, leaving the machine untouched.
+ *
+ * # Safety
+ * `chip8` must be a live handle and `data` must point to `len` readable bytes.
+ */
+bool chip8_load_rom(struct Chip8 *chip8, const uint8_t *data, uintptr_t len);
+
+/**
+ * Executes a single instruction. The delay and sound timers are left alone; use
+ * `chip8_run_frame` to count them down.
+ *
+ * # Safety
+ * `chip8` must be a live handle.
+ */
+void chip8_step(struct Chip8 *chip8);
+
+/**
+ * Executes `cycles` instructions, one frame's worth for the caller, then
+ * counts the delay and sound timers down once.
+ *
+ * # Safety
+ * `chip8` must be a live handle.
+ */
+void chip8_run_frame(struct Chip8 *chip8, uint32_t cycles);
+
+/**
+ * Presses or releases hex key `key` (0x0-0xF). Other values are ignored.
+ *
+ * # Safety
+ * `chip8` must be a live handle.
+ */
+void chip8_set_key(struct Chip8 *chip8, uint8_t key, bool pressed);
+
+/**
+ * The display is 64x32 until a hi-res ROM switches it to 64x64.
+ *
+ * # Safety
+ * `chip8` must be a live handle.
+ */
+uint32_t chip8_framebuffer_width(const struct Chip8 *chip8);
+
+/**
+ * # Safety
+ * `chip8` must be a live handle.
+ */
+uint32_t chip8_framebuffer_height(const struct Chip8 *chip8);
+
+/**
+ * Copies the display into `out` as one byte per pixel (1 on, 0 off), row by row.
+ * Returns the number of pixels in the display; nothing is written if `len` is smaller.
+ *
+ * # Safety
+ * `chip8` must be a live handle and `out` must point to `len` writable bytes.
+ */
+uintptr_t chip8_read_framebuffer(const struct Chip8 *chip8, uint8_t *out, uintptr_t len);
+
+/**
+ * # Safety
+ * `chip8` must be a live handle.
+ */
+bool chip8_sound_playing(const struct Chip8 *chip8);
+
+/**
+ * Serializes the machine into `out`. Returns the size of the snapshot;
+ * nothing is written if `len` is smaller, so call with a null `out` to size the buffer.
+ *
+ * # Safety
+ * `chip8` must be a live handle and `out` must point to `len` writable bytes.
+ */
+uintptr_t chip8_save_state(const struct Chip8 *chip8, uint8_t *out, uintptr_t len);
+
+/**
+ * Restores a snapshot written by `chip8_save_state`. Returns false and leaves
+ * the machine untouched if the snapshot is invalid.
+ *
+ * # Safety
+ * `chip8` must be a live handle and `data` must point to `len` readable bytes.
+ */
+bool chip8_load_state(struct Chip8 *chip8, const uint8_t *data, uintptr_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHIP8_H */
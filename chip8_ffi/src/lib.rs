@@ -0,0 +1,221 @@
+use chip8_core::chip8::audio::{AudioEvent, AudioSink};
+use chip8_core::chip8::keyboard::{HexKey, Keyboard};
+use chip8_core::chip8::random::XorShift;
+use chip8_core::chip8::vram::VideoSink;
+use chip8_core::chip8::Chip8;
+use chip8_core::program::Program;
+use std::ptr;
+use std::slice;
+
+pub struct Emulator {
+    chip8: Chip8,
+    keyboard: Keyboard,
+    video_sink: VideoSink,
+    audio_sink: AudioSink,
+    seed: u32,
+    sound_playing: bool,
+}
+
+impl Emulator {
+    fn new(seed: u32) -> Emulator {
+        let mut chip8 = Chip8::new();
+        chip8.set_random_source(Box::new(XorShift::new(seed)));
+        Emulator {
+            chip8,
+            keyboard: Keyboard::new(),
+            video_sink: VideoSink::new(),
+            audio_sink: AudioSink::new(),
+            seed,
+            sound_playing: false,
+        }
+    }
+    // A `frame` also counts the timers down.
+    fn run(&mut self, cycles: usize, frame: bool) {
+        self.chip8.run(
+            cycles,
+            &self.keyboard,
+            &mut self.video_sink,
+            &mut self.audio_sink,
+        );
+        if frame {
+            self.chip8.tick_timers(&mut self.audio_sink);
+        }
+        self.video_sink.get();
+        match self.audio_sink.event.take() {
+            Some(AudioEvent::Play) => self.sound_playing = true,
+            Some(AudioEvent::Stop) => self.sound_playing = false,
+            None => {}
+        }
+    }
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if data.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(data, len))
+    }
+}
+
+/// Creates an emulator. `seed` drives the `Cxkk` random instruction.
+/// Release it with `chip8_destroy`.
+#[no_mangle]
+pub extern "C" fn chip8_create(seed: u32) -> *mut Emulator {
+    Box::into_raw(Box::new(Emulator::new(seed)))
+}
+
+/// # Safety
+/// `chip8` must come from `chip8_create` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_destroy(chip8: *mut Emulator) {
+    if !chip8.is_null() {
+        drop(Box::from_raw(chip8));
+    }
+}
+
+/// Resets the machine and loads `len` bytes of ROM. Returns false if `data` is null or the ROM
+/// does not fit in memory, leaving the machine untouched.
+///
+/// # Safety
+/// `chip8` must be a live handle and `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_load_rom(chip8: *mut Emulator, data: *const u8, len: usize) -> bool {
+    match (
+        chip8.as_mut(),
+        bytes(data, len).and_then(|rom| Program::from_bytes(rom).ok()),
+    ) {
+        (Some(chip8), Some(program)) => {
+            *chip8 = Emulator::new(chip8.seed);
+            chip8.chip8.load_program(&program);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Executes a single instruction. The delay and sound timers are left alone;
+/// use `chip8_run_frame` to count them down.
+///
+/// # Safety
+/// `chip8` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_step(chip8: *mut Emulator) {
+    if let Some(chip8) = chip8.as_mut() {
+        chip8.run(1, false);
+    }
+}
+
+/// Executes `cycles` instructions, one frame's worth for the caller, then
+/// counts the delay and sound timers down once.
+///
+/// # Safety
+/// `chip8` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_run_frame(chip8: *mut Emulator, cycles: u32) {
+    if let Some(chip8) = chip8.as_mut() {
+        chip8.run(cycles as usize, true);
+    }
+}
+
+/// Presses or releases hex key `key` (0x0-0xF). Other values are ignored.
+///
+/// # Safety
+/// `chip8` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_set_key(chip8: *mut Emulator, key: u8, pressed: bool) {
+    if let Some(chip8) = chip8.as_mut() {
+        if key <= 0xF {
+            chip8.keyboard.update_key(HexKey::from(key), pressed);
+        }
+    }
+}
+
+/// The display is 64x32 until a hi-res ROM switches it to 64x64.
+///
+/// # Safety
+/// `chip8` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_framebuffer_width(chip8: *const Emulator) -> u32 {
+    chip8
+        .as_ref()
+        .map_or(0, |chip8| chip8.chip8.pixel_buffer().width as u32)
+}
+
+/// # Safety
+/// `chip8` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_framebuffer_height(chip8: *const Emulator) -> u32 {
+    chip8
+        .as_ref()
+        .map_or(0, |chip8| chip8.chip8.pixel_buffer().height as u32)
+}
+
+/// Copies the display into `out` as one byte per pixel (1 on, 0 off), row by row.
+/// Returns the number of pixels in the display; nothing is written if `len` is smaller.
+///
+/// # Safety
+/// `chip8` must be a live handle and `out` must point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_read_framebuffer(
+    chip8: *const Emulator,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    let chip8 = match chip8.as_ref() {
+        Some(chip8) => chip8,
+        None => return 0,
+    };
+    let buffer = chip8.chip8.pixel_buffer();
+    if !out.is_null() && len >= buffer.data.len() {
+        for (index, pixel) in buffer.data.iter().enumerate() {
+            *out.add(index) = *pixel as u8;
+        }
+    }
+    buffer.data.len()
+}
+
+/// # Safety
+/// `chip8` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_sound_playing(chip8: *const Emulator) -> bool {
+    chip8.as_ref().is_some_and(|chip8| chip8.sound_playing)
+}
+
+/// Serializes the machine into `out`. Returns the size of the snapshot;
+/// nothing is written if `len` is smaller, so call with a null `out` to size the buffer.
+///
+/// # Safety
+/// `chip8` must be a live handle and `out` must point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_save_state(
+    chip8: *const Emulator,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    let chip8 = match chip8.as_ref() {
+        Some(chip8) => chip8,
+        None => return 0,
+    };
+    let state = chip8.chip8.save_state();
+    if !out.is_null() && len >= state.len() {
+        ptr::copy_nonoverlapping(state.as_ptr(), out, state.len());
+    }
+    state.len()
+}
+
+/// Restores a snapshot written by `chip8_save_state`. Returns false and leaves
+/// the machine untouched if the snapshot is invalid.
+///
+/// # Safety
+/// `chip8` must be a live handle and `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_load_state(
+    chip8: *mut Emulator,
+    data: *const u8,
+    len: usize,
+) -> bool {
+    match (chip8.as_mut(), bytes(data, len)) {
+        (Some(chip8), Some(state)) => chip8.chip8.load_state(state).is_ok(),
+        _ => false,
+    }
+}
@@ -0,0 +1,44 @@
+#include <assert.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "chip8.h"
+
+/* 00E0 clear, A20A set I to the sprite below, D005 draw it at (0, 0), 1206 loop. */
+static const uint8_t ROM[] = {
+    0x00, 0xE0, 0xA2, 0x0A, 0xD0, 0x05, 0x12, 0x06, 0x00, 0x00, 0xF0, 0x80,
+};
+
+int main(void) {
+    Chip8 *chip8 = chip8_create(1);
+    assert(chip8 != NULL);
+    assert(chip8_load_rom(chip8, ROM, sizeof(ROM)));
+    chip8_run_frame(chip8, 10);
+    chip8_set_key(chip8, 0x5, true);
+    chip8_set_key(chip8, 0x10, true);
+
+    size_t pixels = chip8_framebuffer_width(chip8) * chip8_framebuffer_height(chip8);
+    uint8_t *framebuffer = malloc(pixels);
+    assert(chip8_read_framebuffer(chip8, framebuffer, pixels) == pixels);
+    assert(framebuffer[0] == 1 && framebuffer[3] == 1 && framebuffer[4] == 0);
+    assert(framebuffer[64] == 1 && framebuffer[65] == 0);
+    assert(!chip8_sound_playing(chip8));
+
+    size_t size = chip8_save_state(chip8, NULL, 0);
+    uint8_t *state = malloc(size);
+    assert(chip8_save_state(chip8, state, size) == size);
+
+    Chip8 *restored = chip8_create(2);
+    assert(!chip8_load_state(restored, state, size - 1));
+    assert(chip8_load_state(restored, state, size));
+    chip8_step(restored);
+    assert(chip8_read_framebuffer(restored, framebuffer, pixels) == pixels);
+    assert(framebuffer[0] == 1 && framebuffer[64] == 1);
+
+    free(state);
+    free(framebuffer);
+    chip8_destroy(restored);
+    chip8_destroy(chip8);
+    puts("ok");
+    return 0;
+}
@@ -0,0 +1,26 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+// Builds tests/c/test_chip8.c against the static library and the checked-in
+// header, then runs it.
+#[test]
+fn c_program_uses_the_api() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // Cargo leaves the library's staticlib next to the test binaries.
+    let deps_dir = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let executable = deps_dir.join("test_chip8");
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .arg(manifest_dir.join("tests/c/test_chip8.c"))
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg(deps_dir.join("libchip8_ffi.a"))
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&executable)
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success());
+    let output = Command::new(&executable).output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
@@ -0,0 +1,4 @@
+from ._chip8 import Chip8, Framebuffer
+from .env import Chip8Env
+
+__all__ = ["Chip8", "Chip8Env", "Framebuffer"]
@@ -0,0 +1,68 @@
+"""A Gym-style environment around the native `Chip8` machine.
+
+It follows the classic `reset()` / `step(action)` protocol without depending
+on gym itself, so it can be wrapped by any RL framework.
+"""
+
+from ._chip8 import Chip8
+
+try:
+    import numpy
+except ImportError:  # numpy is optional
+    numpy = None
+
+KEY_COUNT = 16
+
+
+class Chip8Env:
+    """Runs a ROM as an episodic environment.
+
+    `action` is a 16-bit mask of held hex keys. `reward_fn(chip8)` returns the
+    reward for the frames just run and `done_fn(chip8)` says whether the
+    episode is over; both can read `chip8.ram()`, `chip8.pc`, `chip8.v`, ...
+    """
+
+    def __init__(
+        self,
+        rom,
+        reward_fn=None,
+        done_fn=None,
+        frame_skip=4,
+        cycles_per_frame=10,
+        max_frames=None,
+    ):
+        self.chip8 = Chip8(rom, cycles_per_frame=cycles_per_frame)
+        self.reward_fn = reward_fn or (lambda chip8: 0.0)
+        self.done_fn = done_fn or (lambda chip8: False)
+        self.frame_skip = frame_skip
+        self.max_frames = max_frames
+        self.action_count = 1 << KEY_COUNT
+        self.frames = 0
+
+    @property
+    def observation_shape(self):
+        # Hi-res ROMs switch from 32 to 64 rows once they start running.
+        return (self.chip8.height, self.chip8.width)
+
+    def reset(self, seed=0):
+        self.chip8.reset(seed)
+        self.frames = 0
+        return self._observation()
+
+    def step(self, action):
+        if not 0 <= action < self.action_count:
+            raise ValueError("action must be a 16-bit key mask")
+        self.chip8.set_keys(action)
+        self.chip8.step(self.frame_skip)
+        self.frames += self.frame_skip
+        reward = self.reward_fn(self.chip8)
+        done = bool(self.done_fn(self.chip8))
+        truncated = self.max_frames is not None and self.frames >= self.max_frames
+        info = {"frames": self.frames, "truncated": truncated}
+        return self._observation(), reward, done or truncated, info
+
+    def _observation(self):
+        framebuffer = self.chip8.framebuffer()
+        if numpy is not None:
+            return numpy.asarray(framebuffer)
+        return memoryview(framebuffer)
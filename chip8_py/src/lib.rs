@@ -0,0 +1,202 @@
+use chip8_core::chip8::audio::{AudioEvent, AudioSink};
+use chip8_core::chip8::keyboard::{HexKey, Keyboard};
+use chip8_core::chip8::random::XorShift;
+use chip8_core::chip8::vram::VideoSink;
+use chip8_core::chip8::Chip8 as Machine;
+use chip8_core::program::Program;
+use pyo3::exceptions::{PyBufferError, PyIndexError, PyValueError};
+use pyo3::ffi;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+
+#[pyclass(unsendable)]
+struct Chip8 {
+    machine: Machine,
+    program: Program,
+    cycles_per_frame: usize,
+    keyboard: Keyboard,
+    video_sink: VideoSink,
+    audio_sink: AudioSink,
+    sound_playing: bool,
+}
+
+impl Chip8 {
+    fn boot(program: &Program, seed: u32) -> Machine {
+        let mut machine = Machine::new();
+        machine.set_random_source(Box::new(XorShift::new(seed)));
+        machine.load_program(program);
+        machine
+    }
+    fn check_address(&self, address: usize) -> PyResult<()> {
+        if address < self.machine.memory().len() {
+            Ok(())
+        } else {
+            Err(PyIndexError::new_err(format!(
+                "address 0x{:X} is outside of memory",
+                address
+            )))
+        }
+    }
+}
+
+#[pymethods]
+impl Chip8 {
+    #[new]
+    #[pyo3(signature = (rom, cycles_per_frame=10, seed=0))]
+    fn new(rom: &[u8], cycles_per_frame: usize, seed: u32) -> PyResult<Chip8> {
+        let program = Program::from_bytes(rom)
+            .map_err(|error| PyValueError::new_err(format!("invalid ROM: {:?}", error)))?;
+        Ok(Chip8 {
+            machine: Chip8::boot(&program, seed),
+            program,
+            cycles_per_frame,
+            keyboard: Keyboard::new(),
+            video_sink: VideoSink::new(),
+            audio_sink: AudioSink::new(),
+            sound_playing: false,
+        })
+    }
+    /// Reloads the ROM into a fresh machine, reseeding `Cxkk` with `seed`.
+    #[pyo3(signature = (seed=0))]
+    fn reset(&mut self, seed: u32) {
+        self.machine = Chip8::boot(&self.program, seed);
+        self.keyboard = Keyboard::new();
+        self.sound_playing = false;
+    }
+    /// Runs `frames` frames of `cycles_per_frame` instructions each.
+    #[pyo3(signature = (frames=1))]
+    fn step(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.machine.run(
+                self.cycles_per_frame,
+                &self.keyboard,
+                &mut self.video_sink,
+                &mut self.audio_sink,
+            );
+            self.machine.tick_timers(&mut self.audio_sink);
+        }
+        self.video_sink.get();
+        match self.audio_sink.event.take() {
+            Some(AudioEvent::Play) => self.sound_playing = true,
+            Some(AudioEvent::Stop) => self.sound_playing = false,
+            None => {}
+        }
+    }
+    /// Sets all sixteen keys at once; bit `n` is hex key `n`.
+    fn set_keys(&mut self, mask: u16) {
+        for key in 0..0x10 {
+            self.keyboard
+                .update_key(HexKey::from(key), mask & (1 << key) != 0);
+        }
+    }
+    /// The display as a read-only `(height, width)` buffer of 0/1 bytes.
+    fn framebuffer(&self) -> Framebuffer {
+        let buffer = self.machine.pixel_buffer();
+        Framebuffer {
+            data: buffer.data.iter().map(|pixel| *pixel as u8).collect(),
+            shape: [buffer.height as isize, buffer.width as isize],
+            strides: [buffer.width as isize, 1],
+        }
+    }
+    fn ram<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, self.machine.memory())
+    }
+    fn read_ram(&self, address: usize) -> PyResult<u8> {
+        self.check_address(address)?;
+        Ok(self.machine.memory()[address])
+    }
+    fn write_ram(&mut self, address: usize, value: u8) -> PyResult<()> {
+        self.check_address(address)?;
+        self.machine.write_memory(address as u16, value);
+        Ok(())
+    }
+    fn save_state<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.machine.save_state())
+    }
+    fn load_state(&mut self, state: &[u8]) -> PyResult<()> {
+        self.machine
+            .load_state(state)
+            .map_err(|error| PyValueError::new_err(format!("invalid state: {:?}", error)))
+    }
+    #[getter]
+    fn pc(&self) -> u16 {
+        self.machine.registers().pc
+    }
+    #[getter]
+    fn v(&self) -> Vec<u8> {
+        self.machine.registers().v.to_vec()
+    }
+    #[getter]
+    fn i(&self) -> u16 {
+        self.machine.registers().i
+    }
+    #[getter]
+    fn sound_playing(&self) -> bool {
+        self.sound_playing
+    }
+    #[getter]
+    fn width(&self) -> usize {
+        self.machine.pixel_buffer().width
+    }
+    /// 32, or 64 once a hi-res ROM has switched to 64x64.
+    #[getter]
+    fn height(&self) -> usize {
+        self.machine.pixel_buffer().height
+    }
+}
+
+// A frozen copy of the display exposed through the buffer protocol, so
+// `numpy.asarray(chip8.framebuffer())` is a `(height, width)` uint8 array
+// without copying.
+#[pyclass(frozen)]
+struct Framebuffer {
+    data: Vec<u8>,
+    shape: [isize; 2],
+    strides: [isize; 2],
+}
+
+#[pymethods]
+impl Framebuffer {
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("view is null"));
+        }
+        if flags & ffi::PyBUF_WRITABLE == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err("framebuffer is read-only"));
+        }
+        let framebuffer = slf.get();
+        (*view).buf = framebuffer.data.as_ptr() as *mut c_void;
+        (*view).len = framebuffer.data.len() as isize;
+        (*view).readonly = 1;
+        (*view).itemsize = 1;
+        (*view).format = if flags & ffi::PyBUF_FORMAT == ffi::PyBUF_FORMAT {
+            b"B\0".as_ptr() as *mut c_char
+        } else {
+            ptr::null_mut()
+        };
+        (*view).ndim = 2;
+        (*view).shape = framebuffer.shape.as_ptr() as *mut isize;
+        (*view).strides = framebuffer.strides.as_ptr() as *mut isize;
+        (*view).suboffsets = ptr::null_mut();
+        (*view).internal = ptr::null_mut();
+        (*view).obj = slf.into_any().into_ptr();
+        Ok(())
+    }
+    unsafe fn __releasebuffer__(&self, _view: *mut ffi::Py_buffer) {}
+    fn __len__(&self) -> usize {
+        self.data.len()
+    }
+}
+
+#[pymodule]
+fn _chip8(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Chip8>()?;
+    module.add_class::<Framebuffer>()?;
+    Ok(())
+}
@@ -0,0 +1,88 @@
+"""Run with a locally built extension, no network needed:
+
+    cargo build -p chip8_py
+    python3 chip8_py/tests/test_chip8.py
+"""
+
+import os
+import shutil
+import sys
+import tempfile
+import unittest
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+ROOT = os.path.join(HERE, "..", "..")
+
+
+def import_chip8():
+    """Imports an installed `chip8`, or assembles one from the cargo build."""
+    try:
+        import chip8
+        return chip8
+    except ImportError:
+        pass
+    library = os.environ.get(
+        "CHIP8_PY_LIBRARY", os.path.join(ROOT, "target", "debug", "lib_chip8.so")
+    )
+    package = os.path.join(tempfile.mkdtemp(), "chip8")
+    shutil.copytree(os.path.join(HERE, "..", "python", "chip8"), package)
+    shutil.copy(library, os.path.join(package, "_chip8.so"))
+    sys.path.insert(0, os.path.dirname(package))
+    import chip8
+    return chip8
+
+
+chip8 = import_chip8()
+
+# 00E0 clear, A20A set I to the sprite below, D005 draw it at (0, 0), 1206 loop.
+ROM = bytes([0x00, 0xE0, 0xA2, 0x0A, 0xD0, 0x05, 0x12, 0x06, 0x00, 0x00, 0xF0, 0x80])
+
+
+class Chip8Test(unittest.TestCase):
+    def test_framebuffer_is_a_two_dimensional_buffer(self):
+        machine = chip8.Chip8(ROM, cycles_per_frame=4)
+        machine.step()
+        view = memoryview(machine.framebuffer())
+        self.assertEqual(view.shape, (32, 64))
+        self.assertEqual(view.format, "B")
+        self.assertEqual(view.tolist()[0][0:5], [1, 1, 1, 1, 0])
+        self.assertEqual(view.tolist()[1][0:2], [1, 0])
+
+    def test_ram_access(self):
+        machine = chip8.Chip8(ROM)
+        self.assertEqual(machine.ram()[0x200:0x200 + len(ROM)], ROM)
+        machine.write_ram(0x300, 0x42)
+        self.assertEqual(machine.read_ram(0x300), 0x42)
+        with self.assertRaises(IndexError):
+            machine.read_ram(0x1000)
+
+    def test_save_and_restore_state(self):
+        machine = chip8.Chip8(ROM)
+        machine.step()
+        state = machine.save_state()
+        machine.reset()
+        self.assertEqual(machine.pc, 0x200)
+        machine.load_state(state)
+        self.assertEqual(machine.pc, 0x206)
+        with self.assertRaises(ValueError):
+            machine.load_state(b"nope")
+
+    def test_env_protocol(self):
+        env = chip8.Chip8Env(
+            ROM,
+            reward_fn=lambda machine: float(machine.read_ram(0x20B)),
+            done_fn=lambda machine: machine.pc == 0x206,
+            frame_skip=2,
+        )
+        observation = env.reset(seed=7)
+        self.assertEqual(memoryview(observation).shape, env.observation_shape)
+        observation, reward, done, info = env.step(0b101)
+        self.assertEqual(reward, 0x80)
+        self.assertTrue(done)
+        self.assertEqual(info["frames"], 2)
+        with self.assertRaises(ValueError):
+            env.step(1 << 16)
+
+
+if __name__ == "__main__":
+    unittest.main()
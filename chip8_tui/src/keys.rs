@@ -0,0 +1,80 @@
+use chip8_core::chip8::keyboard::{HexKey, Keyboard};
+use std::time::{Duration, Instant};
+
+/* INFO:
+    Most terminals only report key presses, repeated while the key is held,
+    so a key counts as held until its repeats stop coming. The first repeat
+    comes later than the ones after it, so a fresh press is held longer.
+    Terminals that report releases (with the kitty keyboard protocol) are
+    believed as soon as the first release arrives.
+*/
+pub const FIRST_HOLD: Duration = Duration::from_millis(550);
+pub const REPEAT_HOLD: Duration = Duration::from_millis(120);
+
+#[derive(Default)]
+pub struct KeyTracker {
+    // When each key is let go, if it is held.
+    until: [Option<Instant>; 0x10],
+    releases: bool,
+}
+
+impl KeyTracker {
+    pub fn new() -> KeyTracker {
+        KeyTracker::default()
+    }
+    // A press or a repeat of `key` at `now`.
+    pub fn press(&mut self, key: u8, now: Instant) {
+        let key = key as usize & 0xF;
+        let hold = match self.until[key] {
+            Some(until) if until > now => REPEAT_HOLD,
+            _ => FIRST_HOLD,
+        };
+        self.until[key] = Some(now + hold);
+    }
+    pub fn release(&mut self, key: u8) {
+        self.releases = true;
+        self.until[key as usize & 0xF] = None;
+    }
+    // Whether the terminal was seen to report releases.
+    pub fn reports_releases(&self) -> bool {
+        self.releases
+    }
+    pub fn update(&mut self, keyboard: &mut Keyboard, now: Instant) {
+        for (key, until) in self.until.iter_mut().enumerate() {
+            if !self.releases && until.is_some_and(|until| until <= now) {
+                *until = None;
+            }
+            keyboard.update_key(HexKey::from(key as u8), until.is_some());
+        }
+    }
+}
+
+/* INFO:
+    chip8      qwerty
+    1 2 3 C    1 2 3 4
+    4 5 6 D    q w e r
+    7 8 9 E    a s d f
+    A 0 B F    z x c v
+*/
+pub fn qwerty(character: char) -> Option<u8> {
+    let key = match character.to_ascii_lowercase() {
+        '1' => 0x1,
+        '2' => 0x2,
+        '3' => 0x3,
+        '4' => 0xC,
+        'q' => 0x4,
+        'w' => 0x5,
+        'e' => 0x6,
+        'r' => 0xD,
+        'a' => 0x7,
+        's' => 0x8,
+        'd' => 0x9,
+        'f' => 0xE,
+        'z' => 0xA,
+        'x' => 0x0,
+        'c' => 0xB,
+        'v' => 0xF,
+        _ => return None,
+    };
+    Some(key)
+}
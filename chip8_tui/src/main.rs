@@ -0,0 +1,184 @@
+use chip8_core::chip8::audio::{AudioEvent, AudioSink};
+use chip8_core::chip8::keyboard::Keyboard;
+use chip8_core::chip8::vram::VideoSink;
+use chip8_core::chip8::Chip8;
+use chip8_core::database::RomDatabase;
+use chip8_core::program::Program;
+use chip8_tui::keys::{qwerty, KeyTracker};
+use chip8_tui::screen::{half_blocks, registers};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::style::Print;
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+use std::env;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+const DEFAULT_CYCLES_PER_FRAME: usize = 10;
+const FRAME: Duration = Duration::from_micros(16_667);
+// Room for the register panel to the right of the screen.
+const PANEL_WIDTH: u16 = 10;
+
+fn main() {
+    let mut program_file = None;
+    // With --quiet the sound timer only shows in the panel, without a bell.
+    let mut quiet = false;
+    let mut usage = false;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--quiet" => quiet = true,
+            _ if program_file.is_none() => program_file = Some(arg),
+            _ => usage = true,
+        }
+    }
+    let program_file = match program_file {
+        Some(ref program_file) if !usage => program_file.clone(),
+        _ => {
+            eprintln!("chip8-tui [--quiet] [CHIP8 FILE]");
+            return;
+        }
+    };
+    let mut program = match Program::from_path(&program_file) {
+        Ok(program) => program,
+        Err(error) => {
+            eprintln!("Cannot load {}: {:?}", program_file, error);
+            return;
+        }
+    };
+    let info = RomDatabase::bundled().lookup(&program);
+    if let Some(ref info) = info {
+        program = match info.lay_out(program) {
+            Ok(program) => program,
+            Err(error) => {
+                eprintln!("Cannot load {}: {:?}", program_file, error);
+                return;
+            }
+        };
+    }
+    let mut chip8 = Chip8::new();
+    let mut cycles_per_frame = DEFAULT_CYCLES_PER_FRAME;
+    if let Some(ref info) = info {
+        if let Some(quirks) = info.quirks {
+            chip8.set_quirks(quirks);
+        }
+        cycles_per_frame = info.cycles_per_frame().unwrap_or(cycles_per_frame);
+    }
+    chip8.load_program(&program);
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode().unwrap();
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide).unwrap();
+    // Ignored by terminals without the kitty keyboard protocol.
+    let enhanced = terminal::supports_keyboard_enhancement().unwrap_or(false);
+    if enhanced {
+        execute!(
+            stdout,
+            PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                    | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+            )
+        )
+        .unwrap();
+    }
+    let result = run(&mut chip8, cycles_per_frame, quiet, &mut stdout);
+    if enhanced {
+        execute!(stdout, PopKeyboardEnhancementFlags).unwrap();
+    }
+    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen).unwrap();
+    terminal::disable_raw_mode().unwrap();
+    if let Err(error) = result {
+        eprintln!("{}", error);
+    }
+}
+
+// Runs until Esc or Ctrl+C.
+fn run<W: Write>(
+    chip8: &mut Chip8,
+    cycles_per_frame: usize,
+    quiet: bool,
+    out: &mut W,
+) -> io::Result<()> {
+    let mut keyboard = Keyboard::new();
+    let mut keys = KeyTracker::new();
+    let mut video_sink = VideoSink::new();
+    let mut audio_sink = AudioSink::new();
+    let mut beeping = false;
+    let mut screen = chip8.pixel_buffer();
+    let mut redraw = true;
+    let mut next_frame = Instant::now();
+    loop {
+        while event::poll(next_frame.saturating_duration_since(Instant::now()))? {
+            match event::read()? {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc, ..
+                }) => return Ok(()),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers,
+                    ..
+                }) if modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char(character),
+                    kind,
+                    ..
+                }) => {
+                    if let Some(key) = qwerty(character) {
+                        match kind {
+                            KeyEventKind::Release => keys.release(key),
+                            _ => keys.press(key, Instant::now()),
+                        }
+                    }
+                }
+                Event::Resize(..) => {
+                    queue!(out, terminal::Clear(ClearType::All))?;
+                    redraw = true;
+                }
+                _ => {}
+            }
+        }
+        next_frame += FRAME;
+        keys.update(&mut keyboard, Instant::now());
+        chip8.run(
+            cycles_per_frame,
+            &keyboard,
+            &mut video_sink,
+            &mut audio_sink,
+        );
+        chip8.tick_timers(&mut audio_sink);
+        match audio_sink.event.take() {
+            Some(AudioEvent::Play) => {
+                if !beeping && !quiet {
+                    queue!(out, Print('\x07'))?;
+                }
+                beeping = true;
+            }
+            Some(AudioEvent::Stop) => beeping = false,
+            None => {}
+        }
+        if let Some(buffer) = video_sink.get() {
+            screen = buffer;
+            redraw = true;
+        }
+        let (columns, rows) = terminal::size()?;
+        let lines = half_blocks(
+            &screen,
+            columns.saturating_sub(PANEL_WIDTH) as usize,
+            rows as usize,
+        );
+        if redraw {
+            for (row, line) in lines.iter().enumerate() {
+                queue!(out, cursor::MoveTo(0, row as u16), Print(line))?;
+            }
+            redraw = false;
+        }
+        let left = lines.first().map_or(0, |line| line.chars().count()) as u16 + 2;
+        let mut panel = registers(&chip8.registers());
+        panel.push(if beeping { "BEEP" } else { "    " }.to_string());
+        for (row, line) in panel.iter().enumerate().take(rows as usize) {
+            queue!(out, cursor::MoveTo(left, row as u16), Print(line))?;
+        }
+        out.flush()?;
+    }
+}
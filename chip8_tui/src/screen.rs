@@ -0,0 +1,66 @@
+use chip8_core::chip8::vram::PixelBuffer;
+use chip8_core::chip8::Registers;
+
+/* INFO:
+    Every character cell shows two pixels, one above the other:
+
+    ' '  neither   '▀'  the top one
+    '▄'  the bottom one   '█'  both
+
+    so 64x32 takes 64x16 cells. A buffer too big for the terminal is shrunk
+    by a whole factor, a cell pixel lit when any pixel it covers is.
+*/
+
+// How many screen pixels go into each cell pixel, across and down, for the
+// buffer to fit in `columns` by `rows` cells.
+pub fn shrink(width: usize, height: usize, columns: usize, rows: usize) -> usize {
+    let across = width.div_ceil(columns.max(1));
+    let down = height.div_ceil(rows.max(1) * 2);
+    across.max(down).max(1)
+}
+
+// The buffer as lines of half blocks, fitting in `columns` by `rows` cells.
+pub fn half_blocks(buffer: &PixelBuffer, columns: usize, rows: usize) -> Vec<String> {
+    let factor = shrink(buffer.width, buffer.height, columns, rows);
+    let width = buffer.width.div_ceil(factor);
+    let height = buffer.height.div_ceil(factor);
+    // Any pixel lit in the `factor` square at `x`, `y`, in cell pixels.
+    let lit = |x: usize, y: usize| {
+        (y * factor..((y + 1) * factor).min(buffer.height)).any(|row| {
+            (x * factor..((x + 1) * factor).min(buffer.width))
+                .any(|column| buffer.data[row * buffer.width + column])
+        })
+    };
+    (0..height.div_ceil(2))
+        .map(|row| {
+            (0..width)
+                .map(|x| {
+                    let top = lit(x, row * 2);
+                    let bottom = row * 2 + 1 < height && lit(x, row * 2 + 1);
+                    match (top, bottom) {
+                        (false, false) => ' ',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (true, true) => '█',
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// The side panel, one line each.
+pub fn registers(registers: &Registers) -> Vec<String> {
+    let mut lines: Vec<String> = registers
+        .v
+        .iter()
+        .enumerate()
+        .map(|(index, value)| format!("V{:X} {:02X}", index, value))
+        .collect();
+    lines.push(format!("I  {:03X}", registers.i));
+    lines.push(format!("PC {:03X}", registers.pc));
+    lines.push(format!("SP {:X}", registers.sp));
+    lines.push(format!("DT {:02X}", registers.delay_timer));
+    lines.push(format!("ST {:02X}", registers.sound_timer));
+    lines
+}
@@ -0,0 +1,58 @@
+use chip8_core::chip8::keyboard::{HexKey, Keyboard};
+use chip8_tui::keys::{qwerty, KeyTracker, FIRST_HOLD, REPEAT_HOLD};
+use std::time::{Duration, Instant};
+
+fn held(tracker: &mut KeyTracker, now: Instant) -> bool {
+    let mut keyboard = Keyboard::new();
+    tracker.update(&mut keyboard, now);
+    keyboard.get_pressed(HexKey::X5)
+}
+
+#[test]
+fn a_tap_is_held_until_the_first_repeat_would_come() {
+    let start = Instant::now();
+    let mut tracker = KeyTracker::new();
+    tracker.press(0x5, start);
+    assert!(held(
+        &mut tracker,
+        start + FIRST_HOLD - Duration::from_millis(1)
+    ));
+    assert!(!held(&mut tracker, start + FIRST_HOLD));
+}
+
+#[test]
+fn repeats_keep_the_key_held() {
+    let start = Instant::now();
+    let mut tracker = KeyTracker::new();
+    tracker.press(0x5, start);
+    let repeat = start + Duration::from_millis(500);
+    tracker.press(0x5, repeat);
+    assert!(held(
+        &mut tracker,
+        repeat + REPEAT_HOLD - Duration::from_millis(1)
+    ));
+    // Letting go is noticed soon after the repeats stop.
+    assert!(!held(&mut tracker, repeat + REPEAT_HOLD));
+}
+
+#[test]
+fn reported_releases_are_believed() {
+    let start = Instant::now();
+    let mut tracker = KeyTracker::new();
+    tracker.press(0x5, start);
+    tracker.release(0x5);
+    assert!(tracker.reports_releases());
+    assert!(!held(&mut tracker, start));
+    // Without timing out once the terminal reports releases
+    tracker.press(0x5, start);
+    assert!(held(&mut tracker, start + FIRST_HOLD * 10));
+}
+
+#[test]
+fn qwerty_layout() {
+    assert_eq!(qwerty('1'), Some(0x1));
+    assert_eq!(qwerty('4'), Some(0xC));
+    assert_eq!(qwerty('X'), Some(0x0));
+    assert_eq!(qwerty('v'), Some(0xF));
+    assert_eq!(qwerty('p'), None);
+}
@@ -0,0 +1,56 @@
+use chip8_core::chip8::vram::PixelBuffer;
+use chip8_core::chip8::Chip8;
+use chip8_tui::screen::{half_blocks, registers, shrink};
+
+fn buffer(width: usize, height: usize, lit: &[(usize, usize)]) -> PixelBuffer {
+    let mut data = vec![false; width * height];
+    for &(x, y) in lit {
+        data[y * width + x] = true;
+    }
+    PixelBuffer {
+        width,
+        height,
+        data,
+        colors: None,
+    }
+}
+
+#[test]
+fn two_pixels_per_cell() {
+    let lines = half_blocks(&buffer(4, 2, &[(1, 0), (2, 1), (3, 0), (3, 1)]), 80, 24);
+    assert_eq!(lines, [" ▀▄█"]);
+}
+
+#[test]
+fn the_screen_takes_half_the_rows() {
+    let lines = half_blocks(&buffer(64, 32, &[]), 80, 24);
+    assert_eq!(lines.len(), 16);
+    assert!(lines.iter().all(|line| line.chars().count() == 64));
+}
+
+#[test]
+fn an_odd_last_row_has_only_tops() {
+    let lines = half_blocks(&buffer(2, 3, &[(0, 2), (1, 2)]), 80, 24);
+    assert_eq!(lines, ["  ", "▀▀"]);
+}
+
+#[test]
+fn hires_shrinks_to_fit() {
+    // 64x64 needs 32 rows, so 24 halve it.
+    assert_eq!(shrink(64, 64, 80, 32), 1);
+    assert_eq!(shrink(64, 64, 80, 24), 2);
+    assert_eq!(shrink(128, 64, 80, 40), 2);
+    let lines = half_blocks(&buffer(64, 64, &[(63, 63)]), 80, 24);
+    assert_eq!(lines.len(), 16);
+    assert_eq!(lines[15].chars().nth(31), Some('▄'));
+    assert_eq!(lines[15].chars().filter(|&cell| cell != ' ').count(), 1);
+}
+
+#[test]
+fn panel_lists_every_register() {
+    let lines = registers(&Chip8::new().registers());
+    assert_eq!(lines.len(), 21);
+    assert_eq!(lines[0], "V0 00");
+    assert_eq!(lines[0xF], "VF 00");
+    assert_eq!(lines[17], "PC 200");
+}
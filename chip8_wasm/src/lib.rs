@@ -0,0 +1,115 @@
+use chip8_core::chip8::audio::{AudioEvent, AudioSink};
+use chip8_core::chip8::keyboard::{HexKey, Keyboard};
+use chip8_core::chip8::random::XorShift;
+use chip8_core::chip8::vram::{VideoSink, HEIGHT, WIDTH};
+use chip8_core::chip8::Chip8;
+use chip8_core::program::Program;
+use wasm_bindgen::prelude::*;
+
+const ON_COLOR: [u8; 4] = [10, 250, 10, 255];
+const OFF_COLOR: [u8; 4] = [10, 10, 10, 255];
+
+#[wasm_bindgen]
+pub struct Emulator {
+    chip8: Chip8,
+    keyboard: Keyboard,
+    video_sink: VideoSink,
+    audio_sink: AudioSink,
+    seed: u32,
+    width: usize,
+    height: usize,
+    framebuffer: Vec<u8>,
+    sound_playing: bool,
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    // wasm32-unknown-unknown has no entropy source, so the page passes in a
+    // seed (e.g. from `Math.random()`) for the `Random` instruction.
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u32) -> Emulator {
+        let mut emulator = Emulator {
+            chip8: Chip8::new(),
+            keyboard: Keyboard::new(),
+            video_sink: VideoSink::new(),
+            audio_sink: AudioSink::new(),
+            seed,
+            width: WIDTH,
+            height: HEIGHT,
+            framebuffer: Vec::new(),
+            sound_playing: false,
+        };
+        emulator.reset();
+        emulator
+    }
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), JsError> {
+        let program = Program::from_bytes(rom)
+            .map_err(|error| JsError::new(&format!("cannot load ROM: {:?}", error)))?;
+        self.reset();
+        self.chip8.load_program(&program);
+        Ok(())
+    }
+    pub fn run_frame(&mut self, cycles: usize) {
+        self.chip8.run(
+            cycles,
+            &self.keyboard,
+            &mut self.video_sink,
+            &mut self.audio_sink,
+        );
+        self.chip8.tick_timers(&mut self.audio_sink);
+        match self.audio_sink.event.take() {
+            Some(AudioEvent::Play) => self.sound_playing = true,
+            Some(AudioEvent::Stop) => self.sound_playing = false,
+            None => {}
+        }
+        if let Some(buffer) = self.video_sink.get() {
+            // Hi-res ROMs switch to 64x64 after they start.
+            self.width = buffer.width;
+            self.height = buffer.height;
+            self.framebuffer.resize(buffer.width * buffer.height * 4, 0);
+            for (rgba, pixel) in self.framebuffer.chunks_mut(4).zip(buffer.data.iter()) {
+                rgba.copy_from_slice(if *pixel { &ON_COLOR } else { &OFF_COLOR });
+            }
+        }
+    }
+    pub fn set_key(&mut self, key: u8, pressed: bool) {
+        if key <= 0xF {
+            self.keyboard.update_key(HexKey::from(key), pressed);
+        }
+    }
+    pub fn key_pressed(&self, key: u8) -> bool {
+        key <= 0xF && self.keyboard.get_pressed(HexKey::from(key))
+    }
+    pub fn framebuffer(&self) -> Vec<u8> {
+        self.framebuffer.clone()
+    }
+    pub fn width(&self) -> u32 {
+        self.width as u32
+    }
+    pub fn height(&self) -> u32 {
+        self.height as u32
+    }
+    pub fn sound_playing(&self) -> bool {
+        self.sound_playing
+    }
+}
+
+impl Emulator {
+    fn reset(&mut self) {
+        self.chip8 = Chip8::new();
+        self.chip8
+            .set_random_source(Box::new(XorShift::new(self.seed)));
+        self.keyboard = Keyboard::new();
+        self.video_sink = VideoSink::new();
+        self.audio_sink = AudioSink::new();
+        self.width = WIDTH;
+        self.height = HEIGHT;
+        self.framebuffer = OFF_COLOR
+            .iter()
+            .cloned()
+            .cycle()
+            .take(WIDTH * HEIGHT * 4)
+            .collect();
+        self.sound_playing = false;
+    }
+}
@@ -0,0 +1,32 @@
+use chip8_wasm::Emulator;
+use wasm_bindgen_test::*;
+
+// 00E0 clear, A20A set I to the sprite below, D005 draw it at (0, 0), 1206 loop.
+const ROM: [u8; 12] = [
+    0x00, 0xE0, 0xA2, 0x0A, 0xD0, 0x05, 0x12, 0x06, 0x00, 0x00, 0xF0, 0x80,
+];
+
+#[wasm_bindgen_test]
+fn draws_into_rgba_framebuffer() {
+    let mut emulator = Emulator::new(1);
+    emulator.load_rom(&ROM).unwrap();
+    emulator.run_frame(10);
+    let framebuffer = emulator.framebuffer();
+    assert_eq!(
+        framebuffer.len(),
+        (emulator.width() * emulator.height() * 4) as usize
+    );
+    assert_eq!(&framebuffer[0..4], &[10, 250, 10, 255]);
+    assert_eq!(&framebuffer[4 * 4..4 * 5], &[10, 10, 10, 255]);
+}
+
+#[wasm_bindgen_test]
+fn ignores_keys_outside_the_keypad() {
+    let mut emulator = Emulator::new(1);
+    emulator.set_key(0x10, true);
+    emulator.set_key(0xFF, true);
+    assert!((0..=0xFF).all(|key| !emulator.key_pressed(key)));
+    emulator.set_key(0xF, true);
+    assert!(emulator.key_pressed(0xF));
+    assert!((0..0xF).all(|key| !emulator.key_pressed(key)));
+}
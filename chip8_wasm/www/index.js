@@ -0,0 +1,62 @@
+import init, { Emulator } from "./pkg/chip8_wasm.js";
+
+const CYCLES_PER_FRAME = 2;
+const KEYS = {
+  "1": 0x1, "2": 0x2, "3": 0x3, "4": 0xc,
+  "q": 0x4, "w": 0x5, "e": 0x6, "r": 0xd,
+  "a": 0x7, "s": 0x8, "d": 0x9, "f": 0xe,
+  "z": 0xa, "x": 0x0, "c": 0xb, "v": 0xf,
+};
+
+async function main() {
+  await init();
+  const emulator = new Emulator((Math.random() * 0xffffffff) >>> 0);
+  const canvas = document.getElementById("screen");
+  const context = canvas.getContext("2d");
+  const audio = new AudioContext();
+  let oscillator = null;
+  let running = false;
+
+  document.getElementById("rom").addEventListener("change", async (event) => {
+    const file = event.target.files[0];
+    try {
+      emulator.load_rom(new Uint8Array(await file.arrayBuffer()));
+      running = true;
+    } catch (error) {
+      alert(error.message);
+    }
+  });
+  for (const [type, pressed] of [["keydown", true], ["keyup", false]]) {
+    document.addEventListener(type, (event) => {
+      const key = KEYS[event.key.toLowerCase()];
+      if (key !== undefined) {
+        emulator.set_key(key, pressed);
+      }
+    });
+  }
+
+  function frame() {
+    if (running) {
+      emulator.run_frame(CYCLES_PER_FRAME);
+      const pixels = new Uint8ClampedArray(emulator.framebuffer());
+      context.putImageData(
+        new ImageData(pixels, emulator.width(), emulator.height()),
+        0,
+        0
+      );
+      if (emulator.sound_playing() && oscillator === null) {
+        oscillator = audio.createOscillator();
+        oscillator.frequency.value = 440;
+        oscillator.connect(audio.destination);
+        oscillator.start();
+      } else if (!emulator.sound_playing() && oscillator !== null) {
+        oscillator.stop();
+        oscillator = null;
+      }
+    }
+    requestAnimationFrame(frame);
+  }
+  requestAnimationFrame(frame);
+}
+
+main();
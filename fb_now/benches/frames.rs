@@ -0,0 +1,50 @@
+#[macro_use]
+extern crate criterion;
+extern crate fb_now;
+extern crate glium;
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use criterion::Criterion;
+use fb_now::{preset, Renderer, ScaleMode};
+use glium::texture::texture2d::Texture2d;
+
+// A 64x32 buffer shown in a 640x320 window, the client's default.
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 32;
+const SCALE: u32 = 10;
+
+fn frames(c: &mut Criterion) {
+    let display = match common::headless() {
+        Some(display) => display,
+        None => return,
+    };
+    let target = Texture2d::empty(&display, WIDTH * SCALE, HEIGHT * SCALE).unwrap();
+    let mut group = c.benchmark_group("frame");
+    for &passes in &["none", "scanlines", "vip"] {
+        let mut renderer = Renderer::new(&display, WIDTH, HEIGHT);
+        if passes != "none" {
+            renderer.set_passes(&preset(passes).unwrap()).unwrap();
+        }
+        let mut shade = 0u8;
+        group.bench_function(passes, |b| {
+            b.iter(|| {
+                shade = shade.wrapping_add(1);
+                renderer
+                    .update_buffer(&[shade; (WIDTH * HEIGHT * 3) as usize])
+                    .unwrap();
+                renderer.draw(&mut target.as_surface(), ScaleMode::Fit);
+                // Wait for the GPU, as a swap would.
+                display.finish();
+            })
+        });
+        // However many frames that was, nothing was made for them.
+        let textures = if passes == "none" { 1 } else { 3 };
+        assert_eq!(renderer.textures_created(), textures);
+    }
+    group.finish();
+}
+
+criterion_group!(benches, frames);
+criterion_main!(benches);
@@ -1,104 +1,628 @@
 #[macro_use]
 extern crate glium;
 
+use glium::backend::{Context, Facade};
 pub use glium::glutin;
+use glium::glutin::dpi::LogicalSize;
+use glium::glutin::GlContext;
 use glium::index::PrimitiveType;
-use glium::texture::unsigned_texture2d::UnsignedTexture2d;
-use glium::texture::RawImage2d;
-use glium::{Display, Surface};
+use glium::texture::texture2d::Texture2d;
+use glium::texture::{ClientFormat, MipmapsOption, RawImage2d, UncompressedFloatFormat};
+use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, SamplerWrapFunction, Uniforms};
+use glium::{Display, IndexBuffer, Program, ProgramCreationError, Rect, Surface, VertexBuffer};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Instant;
 
-pub struct FbNow {
-    pub events_loop: glutin::EventsLoop,
-    display: Display,
-    buffer_width: u32,
-    buffer_height: u32,
+mod passes;
+mod software;
+#[cfg(feature = "software")]
+mod x11;
+
+pub use passes::{preset, Pass, PASSES};
+pub use software::Blitter;
+
+#[derive(Copy, Clone)]
+struct Vertex {
+    position: [f32; 2],
+    tex_coords: [f32; 2],
 }
 
-impl FbNow {
-    pub fn new(window: glutin::WindowBuilder, buffer_width: u32, buffer_height: u32) -> FbNow {
-        let events_loop = glutin::EventsLoop::new();
-        let context = glutin::ContextBuilder::new().with_vsync(true);
-        let display = glium::Display::new(window, context, &events_loop).unwrap();
-        FbNow {
-            events_loop,
-            display,
-            buffer_width,
-            buffer_height,
+implement_vertex!(Vertex, position, tex_coords);
+
+// The window opens at this many screen pixels per buffer pixel.
+pub const DEFAULT_SCALE: u32 = 8;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ScaleMode {
+    // Covers the window, distorting the image to its shape.
+    Stretch,
+    // As large as fits with the buffer's shape, leaving black bars.
+    #[default]
+    Fit,
+    // Like `Fit`, but only by whole multiples so every pixel is the same size.
+    Integer,
+    // Covers the window with the buffer's shape, cropping what overflows.
+    Fill,
+}
+
+pub const SCALE_MODES: [ScaleMode; 4] = [
+    ScaleMode::Stretch,
+    ScaleMode::Fit,
+    ScaleMode::Integer,
+    ScaleMode::Fill,
+];
+
+impl ScaleMode {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ScaleMode::Stretch => "stretch",
+            ScaleMode::Fit => "fit",
+            ScaleMode::Integer => "integer",
+            ScaleMode::Fill => "fill",
         }
     }
-    pub fn update_buffer(&self, buffer: Vec<u8>) {
-        let vertex_buffer = {
-            #[derive(Copy, Clone)]
-            struct Vertex {
-                position: [f32; 2],
-                tex_coords: [f32; 2],
+    pub fn from_name(name: &str) -> Option<ScaleMode> {
+        SCALE_MODES.iter().cloned().find(|mode| mode.name() == name)
+    }
+    // The mode after this one, wrapping around.
+    pub fn next(&self) -> ScaleMode {
+        let index = SCALE_MODES.iter().position(|mode| mode == self).unwrap();
+        SCALE_MODES[(index + 1) % SCALE_MODES.len()]
+    }
+}
+
+// Where the buffer lands in the window, in window pixels from the bottom
+// left. It can reach past the window edges with `Fill`, or with `Integer` in
+// a window smaller than the buffer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Placement {
+    pub left: i32,
+    pub bottom: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub fn place(mode: ScaleMode, window: (u32, u32), buffer: (u32, u32)) -> Placement {
+    let across = window.0 as f64 / buffer.0 as f64;
+    let down = window.1 as f64 / buffer.1 as f64;
+    let scale = match mode {
+        ScaleMode::Stretch => {
+            return Placement {
+                left: 0,
+                bottom: 0,
+                width: window.0,
+                height: window.1,
             }
-            implement_vertex!(Vertex, position, tex_coords);
-            glium::VertexBuffer::new(
-                &self.display,
-                &[
-                    Vertex {
-                        position: [-1.0, -1.0],
-                        tex_coords: [0.0, 1.0],
-                    },
-                    Vertex {
-                        position: [-1.0, 1.0],
-                        tex_coords: [0.0, 0.0],
-                    },
-                    Vertex {
-                        position: [1.0, 1.0],
-                        tex_coords: [1.0, 0.0],
-                    },
-                    Vertex {
-                        position: [1.0, -1.0],
-                        tex_coords: [1.0, 1.0],
-                    },
-                ],
-            ).unwrap()
-        };
-        let index_buffer = glium::IndexBuffer::new(
-            &self.display,
-            PrimitiveType::TriangleStrip,
-            &[1u16, 2, 0, 3],
+        }
+        ScaleMode::Fit => across.min(down),
+        ScaleMode::Integer => across.min(down).floor().max(1.0),
+        ScaleMode::Fill => across.max(down),
+    };
+    let width = (buffer.0 as f64 * scale).round() as u32;
+    let height = (buffer.1 as f64 * scale).round() as u32;
+    Placement {
+        left: (window.0 as i32 - width as i32) / 2,
+        bottom: (window.1 as i32 - height as i32) / 2,
+        width,
+        height,
+    }
+}
+
+// Every pass draws the whole window with this.
+const PASS_VERTEX: &str = "
+    #version 140
+    in vec2 position;
+    out vec2 v_tex_coords;
+    void main() {
+        gl_Position = vec4(position, 0.0, 1.0);
+        v_tex_coords = (position + 1.0) / 2.0;
+    }
+";
+
+// A pass that did not compile, with the compiler's complaint.
+#[derive(Debug)]
+pub struct PassError {
+    pub pass: String,
+    pub error: ProgramCreationError,
+}
+
+// Part of the buffer, counted from its top left pixel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BufferError {
+    // Pixels that are not width × height RGB, with their length and the
+    // length that was wanted.
+    Length { length: usize, expected: usize },
+    // A region reaching past the edge of the buffer.
+    OutOfBounds(Region),
+}
+
+fn check_length(buffer: &[u8], width: u32, height: u32) -> Result<(), BufferError> {
+    let expected = width as usize * height as usize * 3;
+    if buffer.len() == expected {
+        Ok(())
+    } else {
+        Err(BufferError::Length {
+            length: buffer.len(),
+            expected,
+        })
+    }
+}
+
+fn check_region(region: Region, buffer: &[u8], size: (u32, u32)) -> Result<(), BufferError> {
+    if region.x as u64 + region.width as u64 > size.0 as u64
+        || region.y as u64 + region.height as u64 > size.1 as u64
+    {
+        return Err(BufferError::OutOfBounds(region));
+    }
+    check_length(buffer, region.width, region.height)
+}
+
+// The GPU side of the framebuffer: built once, after which a frame only
+// writes the pixels into the texture and draws it.
+pub struct Renderer {
+    context: Rc<Context>,
+    program: Program,
+    vertex_buffer: VertexBuffer<Vertex>,
+    index_buffer: IndexBuffer<u16>,
+    texture: Texture2d,
+    passes: Vec<Program>,
+    // Two textures the size of the surface that the passes read and write
+    // in turn.
+    targets: RefCell<Vec<Texture2d>>,
+    // Every texture made so far, the buffer's and the passes'.
+    textures_created: Cell<usize>,
+    start: Instant,
+}
+
+impl Renderer {
+    pub fn new<F: Facade>(facade: &F, buffer_width: u32, buffer_height: u32) -> Renderer {
+        let vertex_buffer = VertexBuffer::new(
+            facade,
+            &[
+                Vertex {
+                    position: [-1.0, -1.0],
+                    tex_coords: [0.0, 1.0],
+                },
+                Vertex {
+                    position: [-1.0, 1.0],
+                    tex_coords: [0.0, 0.0],
+                },
+                Vertex {
+                    position: [1.0, 1.0],
+                    tex_coords: [1.0, 0.0],
+                },
+                Vertex {
+                    position: [1.0, -1.0],
+                    tex_coords: [1.0, 1.0],
+                },
+            ],
         ).unwrap();
-        let program = program!(&self.display,
+        let index_buffer =
+            IndexBuffer::new(facade, PrimitiveType::TriangleStrip, &[1u16, 2, 0, 3]).unwrap();
+        let program = program!(facade,
             140 => {
                 vertex: "
                     #version 140
+                    uniform vec4 area;
                     in vec2 position;
                     in vec2 tex_coords;
                     out vec2 v_tex_coords;
                     void main() {
-                        gl_Position = vec4(position, 0.0, 1.0);
+                        vec2 corner = mix(area.xy, area.zw, (position + 1.0) / 2.0);
+                        gl_Position = vec4(corner, 0.0, 1.0);
                         v_tex_coords = tex_coords;
                     }
                 ",
                 fragment: "
                     #version 140
-                    uniform usampler2D tex;
+                    uniform sampler2D tex;
                     in vec2 v_tex_coords;
                     out vec4 f_color;
                     void main() {
-                        f_color = texture(tex, v_tex_coords) / 255.0;
+                        f_color = texture(tex, v_tex_coords);
                     }
                 "
             },
         ).unwrap();
-        let image_data = RawImage2d::from_raw_rgb(buffer, (self.buffer_width, self.buffer_height));
-        let tex = UnsignedTexture2d::new(&self.display, image_data).unwrap();
+        Renderer {
+            context: facade.get_context().clone(),
+            program,
+            vertex_buffer,
+            index_buffer,
+            texture: Renderer::texture(facade, buffer_width, buffer_height),
+            passes: Vec::new(),
+            targets: RefCell::new(Vec::new()),
+            textures_created: Cell::new(1),
+            start: Instant::now(),
+        }
+    }
+    fn texture<F: Facade>(facade: &F, width: u32, height: u32) -> Texture2d {
+        Texture2d::empty_with_format(
+            facade,
+            UncompressedFloatFormat::U8U8U8,
+            MipmapsOption::NoMipmap,
+            width,
+            height,
+        ).unwrap()
+    }
+    pub fn buffer_size(&self) -> (u32, u32) {
+        self.texture.dimensions()
+    }
+    // The texture is only replaced when the size actually changes.
+    pub fn resize_buffer<F: Facade>(&mut self, facade: &F, buffer_width: u32, buffer_height: u32) {
+        if self.buffer_size() != (buffer_width, buffer_height) {
+            self.texture = Renderer::texture(facade, buffer_width, buffer_height);
+            self.textures_created.set(self.textures_created.get() + 1);
+        }
+    }
+    // `buffer` is RGB, three bytes per pixel, row by row from the top.
+    pub fn update_buffer(&self, buffer: &[u8]) -> Result<(), BufferError> {
+        let (width, height) = self.buffer_size();
+        let whole = Region {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+        self.update_region(whole, buffer)
+    }
+    // Writes `pixels`, RGB and row by row like the buffer, into `region`
+    // alone.
+    pub fn update_region(&self, region: Region, pixels: &[u8]) -> Result<(), BufferError> {
+        check_region(region, pixels, self.buffer_size())?;
+        if region.width == 0 || region.height == 0 {
+            return Ok(());
+        }
+        let image = RawImage2d {
+            data: Cow::Borrowed(pixels),
+            width: region.width,
+            height: region.height,
+            format: ClientFormat::U8U8U8,
+        };
+        // The texture's rows are the buffer's, top first.
+        let rect = Rect {
+            left: region.x,
+            bottom: region.y,
+            width: region.width,
+            height: region.height,
+        };
+        self.texture.write(rect, image);
+        Ok(())
+    }
+    // Replaces the passes, keeping the old ones if any fails to compile.
+    pub fn set_passes(&mut self, passes: &[Pass]) -> Result<(), PassError> {
+        let mut programs = Vec::new();
+        for pass in passes {
+            let program = Program::from_source(&self.context, PASS_VERTEX, &pass.source, None)
+                .map_err(|error| PassError {
+                    pass: pass.name.clone(),
+                    error,
+                })?;
+            programs.push(program);
+        }
+        self.passes = programs;
+        Ok(())
+    }
+    // Clears the surface to black, draws the buffer placed by `mode` and runs
+    // the passes over it.
+    pub fn draw<S: Surface>(&self, surface: &mut S, mode: ScaleMode) {
+        let time = self.start.elapsed();
+        let time = time.as_secs() as f32 + time.subsec_nanos() as f32 / 1e9;
+        self.draw_at(surface, mode, time);
+    }
+    // Like `draw`, with the passes' `time` given.
+    pub fn draw_at<S: Surface>(&self, surface: &mut S, mode: ScaleMode, time: f32) {
+        let (width, height) = surface.get_dimensions();
+        if self.passes.is_empty() || width == 0 || height == 0 {
+            self.draw_buffer(surface, mode);
+            return;
+        }
+        let mut targets = self.targets.borrow_mut();
+        if targets.first().map(|target| target.dimensions()) != Some((width, height)) {
+            *targets = (0..2)
+                .map(|_| {
+                    Texture2d::empty_with_format(
+                        &self.context,
+                        UncompressedFloatFormat::U8U8U8U8,
+                        MipmapsOption::NoMipmap,
+                        width,
+                        height,
+                    )
+                    .unwrap()
+                })
+                .collect();
+            self.textures_created.set(self.textures_created.get() + 2);
+        }
+        self.draw_buffer(&mut targets[0].as_surface(), mode);
+        let placement = place(mode, (width, height), self.buffer_size());
+        let area = [
+            placement.left as f32 / width as f32,
+            placement.bottom as f32 / height as f32,
+            (placement.left + placement.width as i32) as f32 / width as f32,
+            (placement.bottom + placement.height as i32) as f32 / height as f32,
+        ];
+        let (buffer_width, buffer_height) = self.buffer_size();
+        for (index, program) in self.passes.iter().enumerate() {
+            let uniforms = uniform! {
+                source: targets[index % 2].sampled()
+                    .minify_filter(MinifySamplerFilter::Linear)
+                    .magnify_filter(MagnifySamplerFilter::Linear)
+                    .wrap_function(SamplerWrapFunction::Clamp),
+                resolution: [width as f32, height as f32],
+                buffer_size: [buffer_width as f32, buffer_height as f32],
+                area: area,
+                time: time,
+            };
+            if index + 1 == self.passes.len() {
+                self.draw_pass(surface, program, &uniforms);
+            } else {
+                self.draw_pass(
+                    &mut targets[(index + 1) % 2].as_surface(),
+                    program,
+                    &uniforms,
+                );
+            }
+        }
+    }
+    fn draw_pass<S: Surface, U: Uniforms>(&self, surface: &mut S, program: &Program, uniforms: &U) {
+        surface
+            .draw(
+                &self.vertex_buffer,
+                &self.index_buffer,
+                program,
+                uniforms,
+                &Default::default(),
+            )
+            .unwrap();
+    }
+    fn draw_buffer<S: Surface>(&self, surface: &mut S, mode: ScaleMode) {
+        surface.clear_color(0.0, 0.0, 0.0, 1.0);
+        let (width, height) = surface.get_dimensions();
+        if width == 0 || height == 0 {
+            return;
+        }
+        let placement = place(mode, (width, height), self.buffer_size());
+        let x = |pixels: i32| pixels as f32 * 2.0 / width as f32 - 1.0;
+        let y = |pixels: i32| pixels as f32 * 2.0 / height as f32 - 1.0;
+        let area = [
+            x(placement.left),
+            y(placement.bottom),
+            x(placement.left + placement.width as i32),
+            y(placement.bottom + placement.height as i32),
+        ];
         let uniforms = uniform! {
-            tex: &tex,
+            area: area,
+            tex: self.texture.sampled()
+                .minify_filter(MinifySamplerFilter::Nearest)
+                .magnify_filter(MagnifySamplerFilter::Nearest),
         };
-        let mut target = self.display.draw();
-        target
+        surface
             .draw(
-                &vertex_buffer,
-                &index_buffer,
-                &program,
+                &self.vertex_buffer,
+                &self.index_buffer,
+                &self.program,
                 &uniforms,
                 &Default::default(),
             )
             .unwrap();
-        target.finish().unwrap();
+    }
+    // How many textures have been made, for checking that frames reuse
+    // them.
+    pub fn textures_created(&self) -> usize {
+        self.textures_created.get()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Backend {
+    #[default]
+    OpenGl,
+    // Scales on the CPU and draws with plain X11, without shader passes.
+    // Needs the `software` feature.
+    Software,
+}
+
+// There is only ever one.
+#[allow(clippy::large_enum_variant)]
+enum Screen {
+    OpenGl {
+        display: Display,
+        renderer: Renderer,
+    },
+    #[cfg(feature = "software")]
+    Software(x11::SoftwareWindow),
+}
+
+pub struct FbNow {
+    pub events_loop: glutin::EventsLoop,
+    screen: Screen,
+    mode: ScaleMode,
+    fullscreen: bool,
+}
+
+impl FbNow {
+    // Draws with OpenGL; `with_backend` is where the software backend is
+    // picked.
+    pub fn new(window: glutin::WindowBuilder, buffer_width: u32, buffer_height: u32) -> FbNow {
+        FbNow::with_scale(window, buffer_width, buffer_height, DEFAULT_SCALE)
+    }
+    // Opens the window at `scale` times the buffer size, whatever size
+    // `window` asked for.
+    pub fn with_scale(
+        window: glutin::WindowBuilder,
+        buffer_width: u32,
+        buffer_height: u32,
+        scale: u32,
+    ) -> FbNow {
+        FbNow::with_backend(window, buffer_width, buffer_height, scale, Backend::OpenGl)
+    }
+    // With the `software` feature, `OpenGl` falls back to `Software` when
+    // there is no OpenGL.
+    pub fn with_backend(
+        window: glutin::WindowBuilder,
+        buffer_width: u32,
+        buffer_height: u32,
+        scale: u32,
+        backend: Backend,
+    ) -> FbNow {
+        let size = LogicalSize::new(
+            (buffer_width * scale) as f64,
+            (buffer_height * scale) as f64,
+        );
+        let window = window.with_dimensions(size);
+        let events_loop = glutin::EventsLoop::new();
+        let screen = match backend {
+            Backend::OpenGl => {
+                let context = glutin::ContextBuilder::new().with_vsync(true);
+                match glium::Display::new(window.clone(), context, &events_loop) {
+                    Ok(display) => {
+                        let renderer = Renderer::new(&display, buffer_width, buffer_height);
+                        Screen::OpenGl { display, renderer }
+                    }
+                    #[cfg(feature = "software")]
+                    Err(error) => {
+                        eprintln!("No OpenGL ({}), drawing in software", error);
+                        FbNow::software(window, &events_loop, buffer_width, buffer_height)
+                    }
+                    #[cfg(not(feature = "software"))]
+                    Err(error) => panic!("No OpenGL: {}", error),
+                }
+            }
+            #[cfg(feature = "software")]
+            Backend::Software => FbNow::software(window, &events_loop, buffer_width, buffer_height),
+            #[cfg(not(feature = "software"))]
+            Backend::Software => panic!("fb_now was built without the software feature"),
+        };
+        FbNow {
+            events_loop,
+            screen,
+            mode: ScaleMode::default(),
+            fullscreen: false,
+        }
+    }
+    #[cfg(feature = "software")]
+    fn software(
+        window: glutin::WindowBuilder,
+        events_loop: &glutin::EventsLoop,
+        buffer_width: u32,
+        buffer_height: u32,
+    ) -> Screen {
+        match x11::SoftwareWindow::new(window, events_loop, buffer_width, buffer_height) {
+            Ok(window) => Screen::Software(window),
+            Err(error) => panic!("Cannot open a window: {}", error),
+        }
+    }
+    pub fn backend(&self) -> Backend {
+        match self.screen {
+            Screen::OpenGl { .. } => Backend::OpenGl,
+            #[cfg(feature = "software")]
+            Screen::Software(_) => Backend::Software,
+        }
+    }
+    pub fn scale_mode(&self) -> ScaleMode {
+        self.mode
+    }
+    pub fn set_scale_mode(&mut self, mode: ScaleMode) {
+        self.mode = mode;
+    }
+    // The software backend has no shaders and ignores the passes.
+    pub fn set_passes(&mut self, passes: &[Pass]) -> Result<(), PassError> {
+        match self.screen {
+            Screen::OpenGl {
+                ref mut renderer, ..
+            } => renderer.set_passes(passes),
+            #[cfg(feature = "software")]
+            Screen::Software(_) => Ok(()),
+        }
+    }
+    // Call with the size from every `WindowEvent::Resized`; some platforms
+    // don't resize the GL surface with the window.
+    pub fn resize_window(&self, size: LogicalSize) {
+        match self.screen {
+            Screen::OpenGl { ref display, .. } => {
+                let window = display.gl_window();
+                let dpi = window.get_hidpi_factor();
+                window.resize(size.to_physical(dpi));
+            }
+            // The frame is sized to the window on every `present`.
+            #[cfg(feature = "software")]
+            Screen::Software(_) => (),
+        }
+    }
+    pub fn is_fullscreen(&self) -> bool {
+        self.fullscreen
+    }
+    // Fullscreen goes on the monitor the window is on.
+    pub fn toggle_fullscreen(&mut self) {
+        self.fullscreen = !self.fullscreen;
+        let fullscreen = self.fullscreen;
+        let toggle = |window: &glutin::Window| {
+            let monitor = if fullscreen {
+                Some(window.get_current_monitor())
+            } else {
+                None
+            };
+            window.set_fullscreen(monitor);
+        };
+        match self.screen {
+            Screen::OpenGl { ref display, .. } => toggle(display.gl_window().window()),
+            #[cfg(feature = "software")]
+            Screen::Software(ref software) => toggle(&software.window),
+        }
+    }
+    pub fn resize_buffer(&mut self, buffer_width: u32, buffer_height: u32) {
+        match self.screen {
+            Screen::OpenGl {
+                ref display,
+                ref mut renderer,
+            } => renderer.resize_buffer(display, buffer_width, buffer_height),
+            #[cfg(feature = "software")]
+            Screen::Software(ref software) => software
+                .blitter
+                .borrow_mut()
+                .resize_buffer(buffer_width, buffer_height),
+        }
+    }
+    // Uploads the pixels; they show up on the next `present`.
+    pub fn update_buffer(&self, buffer: &[u8]) -> Result<(), BufferError> {
+        match self.screen {
+            Screen::OpenGl { ref renderer, .. } => renderer.update_buffer(buffer),
+            #[cfg(feature = "software")]
+            Screen::Software(ref software) => software.blitter.borrow_mut().update_buffer(buffer),
+        }
+    }
+    // Uploads the pixels of `region` alone.
+    pub fn update_region(&self, region: Region, pixels: &[u8]) -> Result<(), BufferError> {
+        match self.screen {
+            Screen::OpenGl { ref renderer, .. } => renderer.update_region(region, pixels),
+            #[cfg(feature = "software")]
+            Screen::Software(ref software) => {
+                software.blitter.borrow_mut().update_region(region, pixels)
+            }
+        }
+    }
+    pub fn present(&self) {
+        match self.screen {
+            Screen::OpenGl {
+                ref display,
+                ref renderer,
+            } => {
+                let mut target = display.draw();
+                renderer.draw(&mut target, self.mode);
+                target.finish().unwrap();
+            }
+            #[cfg(feature = "software")]
+            Screen::Software(ref software) => software.present(self.mode),
+        }
     }
 }
@@ -0,0 +1,166 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/* INFO:
+    A pass is a GLSL 1.40 fragment shader run over the whole window after
+    the buffer is drawn, each pass reading what the one before wrote. It
+    gets `in vec2 v_tex_coords`, from 0 to 1 across the window with the
+    origin at the bottom left, writes `out vec4 f_color`, and can use any of:
+
+    uniform sampler2D source;   what the pass before wrote, linearly filtered
+    uniform vec2 resolution;    the window size in pixels
+    uniform vec2 buffer_size;   the buffer size in pixels
+    uniform vec4 area;          where the buffer is, as left, bottom, right
+                                and top in `v_tex_coords`
+    uniform float time;         seconds since the renderer was made
+
+    A file given to `Pass::from_file` is such a shader, whole.
+*/
+
+const SCANLINES: &str = "
+    #version 140
+    uniform sampler2D source;
+    uniform vec2 buffer_size;
+    uniform vec4 area;
+    in vec2 v_tex_coords;
+    out vec4 f_color;
+    void main() {
+        vec3 color = texture(source, v_tex_coords).rgb;
+        float row = (v_tex_coords.y - area.y) / (area.w - area.y) * buffer_size.y;
+        float edge = abs(fract(row) - 0.5) * 2.0;
+        f_color = vec4(color * (1.0 - 0.5 * edge * edge), 1.0);
+    }
+";
+
+// Darkens the first window pixel of every buffer pixel, once they are big
+// enough to leave something in between.
+const GRID: &str = "
+    #version 140
+    uniform sampler2D source;
+    uniform vec2 resolution;
+    uniform vec2 buffer_size;
+    uniform vec4 area;
+    in vec2 v_tex_coords;
+    out vec4 f_color;
+    void main() {
+        vec3 color = texture(source, v_tex_coords).rgb;
+        vec2 pixel = resolution * (area.zw - area.xy) / buffer_size;
+        vec2 cell = fract((v_tex_coords - area.xy) / (area.zw - area.xy) * buffer_size);
+        bool line = min(pixel.x, pixel.y) >= 3.0 && any(lessThan(cell * pixel, vec2(1.0)));
+        f_color = vec4(color * (line ? 0.6 : 1.0), 1.0);
+    }
+";
+
+// Adds a blur about a buffer pixel wide on top.
+const BLOOM: &str = "
+    #version 140
+    uniform sampler2D source;
+    uniform vec2 resolution;
+    uniform vec2 buffer_size;
+    uniform vec4 area;
+    in vec2 v_tex_coords;
+    out vec4 f_color;
+    void main() {
+        vec2 pixel = resolution * (area.zw - area.xy) / buffer_size;
+        vec2 spread = max(pixel * 0.5, vec2(1.0)) / resolution;
+        vec3 glow = vec3(0.0);
+        float total = 0.0;
+        for (int x = -2; x <= 2; x++) {
+            for (int y = -2; y <= 2; y++) {
+                float weight = exp(-0.5 * float(x * x + y * y));
+                glow += texture(source, v_tex_coords + vec2(x, y) * spread).rgb * weight;
+                total += weight;
+            }
+        }
+        vec3 color = texture(source, v_tex_coords).rgb;
+        f_color = vec4(min(color + 0.5 * glow / total, 1.0), 1.0);
+    }
+";
+
+// Bulges the buffer like the glass of a tube, leaving the corners black.
+const BARREL: &str = "
+    #version 140
+    uniform sampler2D source;
+    uniform vec4 area;
+    in vec2 v_tex_coords;
+    out vec4 f_color;
+    void main() {
+        vec2 size = area.zw - area.xy;
+        vec2 centered = (v_tex_coords - area.xy) / size * 2.0 - 1.0;
+        centered *= 1.0 + 0.06 * dot(centered, centered);
+        if (any(greaterThan(abs(centered), vec2(1.0)))) {
+            f_color = vec4(0.0, 0.0, 0.0, 1.0);
+            return;
+        }
+        f_color = vec4(texture(source, area.xy + (centered + 1.0) / 2.0 * size).rgb, 1.0);
+    }
+";
+
+// Dark corners and the hum bar that slowly rolls up a cheap TV.
+const TV: &str = "
+    #version 140
+    uniform sampler2D source;
+    uniform vec4 area;
+    uniform float time;
+    in vec2 v_tex_coords;
+    out vec4 f_color;
+    void main() {
+        vec3 color = texture(source, v_tex_coords).rgb;
+        vec2 centered = (v_tex_coords - area.xy) / (area.zw - area.xy) * 2.0 - 1.0;
+        float vignette = max(1.0 - 0.25 * dot(centered, centered), 0.0);
+        float hum = 1.0 - 0.04 * (0.5 + 0.5 * sin((v_tex_coords.y - time * 0.1) * 6.2832));
+        f_color = vec4(color * vignette * hum, 1.0);
+    }
+";
+
+pub const PASSES: [&str; 5] = ["scanlines", "grid", "bloom", "barrel", "tv"];
+
+// The COSMAC VIP hooked up to the family TV.
+const VIP: [&str; 4] = ["bloom", "scanlines", "tv", "barrel"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pass {
+    pub name: String,
+    pub source: String,
+}
+
+impl Pass {
+    pub fn builtin(name: &str) -> Option<Pass> {
+        let source = match name {
+            "scanlines" => SCANLINES,
+            "grid" => GRID,
+            "bloom" => BLOOM,
+            "barrel" => BARREL,
+            "tv" => TV,
+            _ => return None,
+        };
+        Some(Pass {
+            name: name.to_string(),
+            source: source.to_string(),
+        })
+    }
+    // Named after the file.
+    pub fn from_file(path: &Path) -> io::Result<Pass> {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(Pass {
+            name,
+            source: fs::read_to_string(path)?,
+        })
+    }
+}
+
+// `vip`, or any of the built-in passes on its own.
+pub fn preset(name: &str) -> Option<Vec<Pass>> {
+    match name {
+        "vip" => Some(
+            VIP.iter()
+                .map(|name| Pass::builtin(name).unwrap())
+                .collect(),
+        ),
+        _ => Pass::builtin(name).map(|pass| vec![pass]),
+    }
+}
@@ -0,0 +1,75 @@
+use super::{check_length, check_region, place, BufferError, Region, ScaleMode};
+
+// The framebuffer drawn by the CPU instead of OpenGL, for machines without a
+// GLSL 1.40 context. It keeps the pixels and scales them into a frame the
+// size of the window.
+pub struct Blitter {
+    width: u32,
+    height: u32,
+    buffer: Vec<u8>,
+}
+
+impl Blitter {
+    pub fn new(buffer_width: u32, buffer_height: u32) -> Blitter {
+        Blitter {
+            width: buffer_width,
+            height: buffer_height,
+            buffer: vec![0; (buffer_width * buffer_height * 3) as usize],
+        }
+    }
+    pub fn buffer_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+    pub fn resize_buffer(&mut self, buffer_width: u32, buffer_height: u32) {
+        if self.buffer_size() != (buffer_width, buffer_height) {
+            *self = Blitter::new(buffer_width, buffer_height);
+        }
+    }
+    // `buffer` is RGB, three bytes per pixel, row by row from the top.
+    pub fn update_buffer(&mut self, buffer: &[u8]) -> Result<(), BufferError> {
+        check_length(buffer, self.width, self.height)?;
+        self.buffer.copy_from_slice(buffer);
+        Ok(())
+    }
+    // Writes `pixels`, RGB and row by row, into `region` alone.
+    pub fn update_region(&mut self, region: Region, pixels: &[u8]) -> Result<(), BufferError> {
+        check_region(region, pixels, self.buffer_size())?;
+        let row = region.width as usize * 3;
+        if row == 0 || region.height == 0 {
+            return Ok(());
+        }
+        for (y, source) in (region.y..).zip(pixels.chunks(row)) {
+            let start = (y * self.width + region.x) as usize * 3;
+            self.buffer[start..start + row].copy_from_slice(source);
+        }
+        Ok(())
+    }
+    // Fills `frame`, `window` pixels wide and high and row by row from the
+    // top, with black and the buffer placed by `mode`. Pixels are 0x00RRGGBB.
+    pub fn draw(&self, frame: &mut [u32], window: (u32, u32), mode: ScaleMode) {
+        let (width, height) = window;
+        for pixel in frame.iter_mut() {
+            *pixel = 0;
+        }
+        if width == 0 || height == 0 {
+            return;
+        }
+        let placement = place(mode, window, self.buffer_size());
+        // `place` counts from the bottom.
+        let top = height as i32 - placement.bottom - placement.height as i32;
+        let rows = top.max(0)..(top + placement.height as i32).min(height as i32);
+        let columns =
+            placement.left.max(0)..(placement.left + placement.width as i32).min(width as i32);
+        for y in rows {
+            let source_y = (y - top) as u64 * self.height as u64 / placement.height as u64;
+            let source = &self.buffer[(source_y * self.width as u64 * 3) as usize..];
+            let row = &mut frame[(y as u32 * width) as usize..];
+            for x in columns.clone() {
+                let source_x =
+                    (x - placement.left) as u64 * self.width as u64 / placement.width as u64;
+                let rgb = &source[source_x as usize * 3..];
+                row[x as usize] = (rgb[0] as u32) << 16 | (rgb[1] as u32) << 8 | rgb[2] as u32;
+            }
+        }
+    }
+}
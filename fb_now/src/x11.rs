@@ -0,0 +1,165 @@
+extern crate x11_dl;
+
+use self::x11_dl::xlib::{self, Xlib};
+use super::glutin::os::unix::WindowExt;
+use super::glutin::{EventsLoop, Window, WindowBuilder};
+use super::software::Blitter;
+use super::ScaleMode;
+use std::cell::RefCell;
+use std::os::raw::{c_char, c_int, c_ulong};
+use std::{mem, ptr, slice};
+
+// A plain X11 window that the blitter's frames are copied into with
+// `XPutImage`, so nothing needs OpenGL. The frames are 0x00RRGGBB words, so
+// only 24-bit TrueColor visuals stored 32 bits to a pixel can show them.
+pub struct SoftwareWindow {
+    pub window: Window,
+    xlib: Xlib,
+    display: *mut xlib::Display,
+    visual: *mut xlib::Visual,
+    depth: c_int,
+    drawable: c_ulong,
+    gc: xlib::GC,
+    pub blitter: RefCell<Blitter>,
+    frame: RefCell<Vec<u32>>,
+}
+
+impl SoftwareWindow {
+    pub fn new(
+        window: WindowBuilder,
+        events_loop: &EventsLoop,
+        buffer_width: u32,
+        buffer_height: u32,
+    ) -> Result<SoftwareWindow, String> {
+        let window = window
+            .build(events_loop)
+            .map_err(|error| error.to_string())?;
+        let (display, drawable) = match (window.get_xlib_display(), window.get_xlib_window()) {
+            (Some(display), Some(drawable)) => (display as *mut xlib::Display, drawable),
+            _ => return Err("the software backend only draws on X11".to_string()),
+        };
+        let xlib = Xlib::open().map_err(|error| error.to_string())?;
+        let (visual, depth) = unsafe { check_visual(&xlib, display, drawable)? };
+        let gc = unsafe { (xlib.XCreateGC)(display, drawable, 0, ptr::null_mut()) };
+        Ok(SoftwareWindow {
+            window,
+            xlib,
+            display,
+            visual,
+            depth,
+            drawable,
+            gc,
+            blitter: RefCell::new(Blitter::new(buffer_width, buffer_height)),
+            frame: RefCell::new(Vec::new()),
+        })
+    }
+    pub fn present(&self, mode: ScaleMode) {
+        let size = match self.window.get_inner_size() {
+            Some(size) => size.to_physical(self.window.get_hidpi_factor()),
+            None => return,
+        };
+        let (width, height) = (size.width.round() as u32, size.height.round() as u32);
+        if width == 0 || height == 0 {
+            return;
+        }
+        let mut frame = self.frame.borrow_mut();
+        frame.resize((width * height) as usize, 0);
+        self.blitter
+            .borrow()
+            .draw(&mut frame, (width, height), mode);
+        unsafe {
+            let image = (self.xlib.XCreateImage)(
+                self.display,
+                self.visual,
+                self.depth as u32,
+                xlib::ZPixmap,
+                0,
+                frame.as_mut_ptr() as *mut c_char,
+                width,
+                height,
+                32,
+                0,
+            );
+            if image.is_null() {
+                return;
+            }
+            // The words are in our byte order; Xlib swaps them if the
+            // server's differs.
+            (*image).byte_order = if cfg!(target_endian = "little") {
+                xlib::LSBFirst
+            } else {
+                xlib::MSBFirst
+            };
+            (self.xlib.XPutImage)(
+                self.display,
+                self.drawable,
+                self.gc,
+                image,
+                0,
+                0,
+                0,
+                0,
+                width,
+                height,
+            );
+            // The pixels are the frame's, not for Xlib to free.
+            (*image).data = ptr::null_mut();
+            (self.xlib.XDestroyImage)(image);
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+}
+
+// The window's visual and depth, if frames of 0x00RRGGBB words can be put
+// on it as they are.
+unsafe fn check_visual(
+    xlib: &Xlib,
+    display: *mut xlib::Display,
+    drawable: c_ulong,
+) -> Result<(*mut xlib::Visual, c_int), String> {
+    let mut attributes: xlib::XWindowAttributes = mem::zeroed();
+    if (xlib.XGetWindowAttributes)(display, drawable, &mut attributes) == 0 {
+        return Err("cannot read the window's visual".to_string());
+    }
+    let (visual, depth) = (attributes.visual, attributes.depth);
+    let masks = (
+        (*visual).red_mask,
+        (*visual).green_mask,
+        (*visual).blue_mask,
+    );
+    if (*visual).class != xlib::TrueColor
+        || (depth != 24 && depth != 32)
+        || masks != (0xFF_0000, 0xFF00, 0xFF)
+    {
+        return Err(format!(
+            "the software backend needs 0xRRGGBB TrueColor, not class {} at depth {}",
+            (*visual).class,
+            depth
+        ));
+    }
+    let mut count = 0;
+    let formats = (xlib.XListPixmapFormats)(display, &mut count);
+    if formats.is_null() {
+        return Err("cannot read the pixmap formats".to_string());
+    }
+    let bits_per_pixel = slice::from_raw_parts(formats, count as usize)
+        .iter()
+        .find(|format| format.depth == depth)
+        .map(|format| format.bits_per_pixel);
+    (xlib.XFree)(formats as *mut _);
+    if bits_per_pixel != Some(32) {
+        return Err(format!(
+            "the software backend needs 32 bits per pixel at depth {}",
+            depth
+        ));
+    }
+    Ok((visual, depth))
+}
+
+impl Drop for SoftwareWindow {
+    fn drop(&mut self) {
+        unsafe {
+            (self.xlib.XFreeGC)(self.display, self.gc);
+        }
+    }
+}
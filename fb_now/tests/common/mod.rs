@@ -0,0 +1,158 @@
+// A headless OpenGL context for the tests and benchmarks: OSMesa through
+// glutin where it is installed, else Mesa's surfaceless EGL platform, which
+// renders with llvmpipe on machines without a GPU. Without either the tests
+// print why and pass without checking anything.
+#![allow(dead_code)]
+
+extern crate libloading;
+
+use self::libloading::Library;
+use fb_now::glutin::HeadlessRendererBuilder;
+use glium::backend::{Backend, Context, Facade};
+use glium::debug::DebugCallbackBehavior;
+use glium::{HeadlessRenderer, SwapBuffersError};
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+use std::rc::Rc;
+
+pub fn headless() -> Option<Rc<Context>> {
+    if let Ok(context) = HeadlessRendererBuilder::new(64, 64).build() {
+        if let Ok(renderer) = HeadlessRenderer::new(context) {
+            return Some(renderer.get_context().clone());
+        }
+    }
+    match Egl::new() {
+        Ok(egl) => unsafe {
+            Context::new(egl, true, DebugCallbackBehavior::Ignore)
+                .map_err(|error| eprintln!("Skipped, no OpenGL 3.1: {:?}", error))
+                .ok()
+        },
+        Err(error) => {
+            eprintln!("Skipped, no headless OpenGL context: {}", error);
+            None
+        }
+    }
+}
+
+type Display = *mut c_void;
+type EglContext = *mut c_void;
+
+const PLATFORM_SURFACELESS_MESA: u32 = 0x31DD;
+const OPENGL_API: u32 = 0x30A2;
+const CONTEXT_MAJOR_VERSION: i32 = 0x3098;
+const CONTEXT_MINOR_VERSION: i32 = 0x30FB;
+const CONTEXT_OPENGL_PROFILE_MASK: i32 = 0x30FD;
+const CONTEXT_OPENGL_CORE_PROFILE_BIT: i32 = 1;
+const NONE: i32 = 0x3038;
+
+// An OpenGL 3.2 core context with no surface at all; everything is drawn
+// into textures.
+struct Egl {
+    display: Display,
+    context: EglContext,
+    get_proc_address: unsafe extern "C" fn(*const c_char) -> *const c_void,
+    make_current: unsafe extern "C" fn(Display, *mut c_void, *mut c_void, EglContext) -> u32,
+    get_current_context: unsafe extern "C" fn() -> EglContext,
+    destroy_context: unsafe extern "C" fn(Display, EglContext) -> u32,
+    _library: Library,
+}
+
+impl Egl {
+    fn new() -> Result<Egl, String> {
+        unsafe {
+            let library = Library::new("libEGL.so.1").map_err(|error| error.to_string())?;
+            macro_rules! symbol {
+                ($name:expr) => {
+                    *library.get($name).map_err(|error| error.to_string())?
+                };
+            }
+            let get_platform_display: unsafe extern "C" fn(
+                u32,
+                *mut c_void,
+                *const isize,
+            ) -> Display = symbol!(b"eglGetPlatformDisplay\0");
+            let initialize: unsafe extern "C" fn(Display, *mut i32, *mut i32) -> u32 =
+                symbol!(b"eglInitialize\0");
+            let bind_api: unsafe extern "C" fn(u32) -> u32 = symbol!(b"eglBindAPI\0");
+            let create_context: unsafe extern "C" fn(
+                Display,
+                *mut c_void,
+                EglContext,
+                *const i32,
+            ) -> EglContext = symbol!(b"eglCreateContext\0");
+            let display =
+                get_platform_display(PLATFORM_SURFACELESS_MESA, ptr::null_mut(), ptr::null());
+            if display.is_null() || initialize(display, ptr::null_mut(), ptr::null_mut()) == 0 {
+                return Err("no surfaceless EGL display".to_string());
+            }
+            if bind_api(OPENGL_API) == 0 {
+                return Err("EGL has no desktop OpenGL".to_string());
+            }
+            let attributes = [
+                CONTEXT_MAJOR_VERSION,
+                3,
+                CONTEXT_MINOR_VERSION,
+                2,
+                CONTEXT_OPENGL_PROFILE_MASK,
+                CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                NONE,
+            ];
+            let context = create_context(
+                display,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                attributes.as_ptr(),
+            );
+            if context.is_null() {
+                return Err("no configless OpenGL 3.2 context".to_string());
+            }
+            let get_proc_address = symbol!(b"eglGetProcAddress\0");
+            let make_current = symbol!(b"eglMakeCurrent\0");
+            let get_current_context = symbol!(b"eglGetCurrentContext\0");
+            let destroy_context = symbol!(b"eglDestroyContext\0");
+            Ok(Egl {
+                display,
+                context,
+                get_proc_address,
+                make_current,
+                get_current_context,
+                destroy_context,
+                _library: library,
+            })
+        }
+    }
+}
+
+unsafe impl Backend for Egl {
+    fn swap_buffers(&self) -> Result<(), SwapBuffersError> {
+        Ok(())
+    }
+    unsafe fn get_proc_address(&self, symbol: &str) -> *const c_void {
+        let mut name = symbol.as_bytes().to_vec();
+        name.push(0);
+        (self.get_proc_address)(name.as_ptr() as *const c_char)
+    }
+    fn get_framebuffer_dimensions(&self) -> (u32, u32) {
+        (64, 64)
+    }
+    fn is_current(&self) -> bool {
+        unsafe { (self.get_current_context)() == self.context }
+    }
+    unsafe fn make_current(&self) {
+        (self.make_current)(self.display, ptr::null_mut(), ptr::null_mut(), self.context);
+    }
+}
+
+impl Drop for Egl {
+    fn drop(&mut self) {
+        unsafe {
+            (self.make_current)(
+                self.display,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+            (self.destroy_context)(self.display, self.context);
+        }
+    }
+}
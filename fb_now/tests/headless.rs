@@ -0,0 +1,101 @@
+extern crate fb_now;
+extern crate glium;
+
+mod common;
+
+use fb_now::{preset, Region, Renderer, ScaleMode};
+use glium::texture::texture2d::Texture2d;
+
+// Ten seconds' worth
+const FRAMES: u32 = 600;
+
+#[test]
+fn frames_reuse_the_texture() {
+    let display = match common::headless() {
+        Some(display) => display,
+        None => return,
+    };
+    let renderer = Renderer::new(&display, 64, 32);
+    let target = Texture2d::empty(&display, 64, 32).unwrap();
+    for frame in 0..FRAMES {
+        renderer.update_buffer(&[frame as u8; 64 * 32 * 3]).unwrap();
+        renderer.draw(&mut target.as_surface(), ScaleMode::Stretch);
+    }
+    assert_eq!(renderer.textures_created(), 1);
+    let pixels: Vec<Vec<(u8, u8, u8, u8)>> = target.read();
+    let shade = (FRAMES - 1) as u8;
+    assert_eq!(pixels[0][0], (shade, shade, shade, 0xFF));
+}
+
+#[test]
+fn passes_reuse_their_textures() {
+    let display = match common::headless() {
+        Some(display) => display,
+        None => return,
+    };
+    let mut renderer = Renderer::new(&display, 64, 32);
+    renderer.set_passes(&preset("scanlines").unwrap()).unwrap();
+    let target = Texture2d::empty(&display, 64, 32).unwrap();
+    for _ in 0..FRAMES {
+        renderer.update_buffer(&[0xFF; 64 * 32 * 3]).unwrap();
+        renderer.draw(&mut target.as_surface(), ScaleMode::Stretch);
+    }
+    // The buffer's and the two the passes take turns with.
+    assert_eq!(renderer.textures_created(), 3);
+}
+
+#[test]
+fn only_a_new_size_replaces_the_texture() {
+    let display = match common::headless() {
+        Some(display) => display,
+        None => return,
+    };
+    let mut renderer = Renderer::new(&display, 64, 32);
+    renderer.resize_buffer(&display, 64, 32);
+    assert_eq!(renderer.textures_created(), 1);
+    renderer.resize_buffer(&display, 64, 64);
+    assert_eq!(renderer.textures_created(), 2);
+    assert_eq!(renderer.buffer_size(), (64, 64));
+    renderer.update_buffer(&[0; 64 * 64 * 3]).unwrap();
+}
+
+#[test]
+fn fit_letterboxes_in_black() {
+    let display = match common::headless() {
+        Some(display) => display,
+        None => return,
+    };
+    let renderer = Renderer::new(&display, 64, 32);
+    renderer.update_buffer(&[0xFF; 64 * 32 * 3]).unwrap();
+    let target = Texture2d::empty(&display, 64, 64).unwrap();
+    renderer.draw(&mut target.as_surface(), ScaleMode::Fit);
+    let pixels: Vec<Vec<(u8, u8, u8, u8)>> = target.read();
+    assert_eq!(pixels[0][0], (0, 0, 0, 0xFF));
+    assert_eq!(pixels[32][0], (0xFF, 0xFF, 0xFF, 0xFF));
+    assert_eq!(pixels[63][63], (0, 0, 0, 0xFF));
+}
+
+#[test]
+fn a_region_only_changes_its_pixels() {
+    let display = match common::headless() {
+        Some(display) => display,
+        None => return,
+    };
+    let renderer = Renderer::new(&display, 64, 32);
+    renderer.update_buffer(&[0xFF; 64 * 32 * 3]).unwrap();
+    let region = Region {
+        x: 0,
+        y: 0,
+        width: 2,
+        height: 1,
+    };
+    renderer.update_region(region, &[0; 2 * 3]).unwrap();
+    let target = Texture2d::empty(&display, 64, 32).unwrap();
+    renderer.draw(&mut target.as_surface(), ScaleMode::Stretch);
+    // Read bottom row first, so the buffer's top row comes last.
+    let pixels: Vec<Vec<(u8, u8, u8, u8)>> = target.read();
+    assert_eq!(pixels[31][0], (0, 0, 0, 0xFF));
+    assert_eq!(pixels[31][1], (0, 0, 0, 0xFF));
+    assert_eq!(pixels[31][2], (0xFF, 0xFF, 0xFF, 0xFF));
+    assert_eq!(pixels[30][0], (0xFF, 0xFF, 0xFF, 0xFF));
+}
@@ -0,0 +1,237 @@
+extern crate fb_now;
+extern crate glium;
+extern crate png;
+
+mod common;
+
+use fb_now::{preset, Pass, Renderer, ScaleMode, PASSES};
+use glium::backend::Context;
+use glium::texture::texture2d::Texture2d;
+use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+fn shader(name: &str) -> Pass {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/shaders")
+        .join(name);
+    Pass::from_file(&path).unwrap()
+}
+
+type Image = Vec<Vec<(u8, u8, u8, u8)>>;
+
+fn reference_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/references")
+        .join(name)
+}
+
+// An RGBA image from tests/references, bottom row first like `read`.
+fn reference(name: &str) -> Image {
+    let decoder = png::Decoder::new(File::open(reference_path(name)).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).unwrap();
+    assert_eq!(info.color_type, png::ColorType::Rgba);
+    let mut image: Image = data[..info.buffer_size()]
+        .chunks(info.line_size)
+        .map(|row| {
+            row.chunks(4)
+                .map(|pixel| (pixel[0], pixel[1], pixel[2], pixel[3]))
+                .collect()
+        })
+        .collect();
+    image.reverse();
+    image
+}
+
+// Writes `image` over the reference instead, for regenerate.sh.
+fn bless(image: &Image, name: &str) {
+    let file = BufWriter::new(File::create(reference_path(name)).unwrap());
+    let mut encoder = png::Encoder::new(file, image[0].len() as u32, image.len() as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let data: Vec<u8> = image
+        .iter()
+        .rev()
+        .flat_map(|row| row.iter().flat_map(|&(r, g, b, a)| vec![r, g, b, a]))
+        .collect();
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(&data)
+        .unwrap();
+}
+
+const WHITE: [u8; 64 * 32 * 3] = [0xFF; 64 * 32 * 3];
+
+// White and black squares 4 pixels across, to show what a pass moves.
+fn checkerboard() -> Vec<u8> {
+    (0..64 * 32)
+        .flat_map(|pixel| {
+            let (x, y) = (pixel % 64, pixel / 64);
+            let shade = if (x / 4 + y / 4) % 2 == 0 { 0xFF } else { 0 };
+            vec![shade; 3]
+        })
+        .collect()
+}
+
+// A `width`x`height` window showing the 64x32 RGB `buffer` through
+// `passes`.
+fn render(
+    display: &Rc<Context>,
+    (width, height): (u32, u32),
+    mode: ScaleMode,
+    passes: &[Pass],
+    buffer: &[u8],
+) -> Image {
+    let mut renderer = Renderer::new(display, 64, 32);
+    renderer.set_passes(passes).unwrap();
+    renderer.update_buffer(buffer).unwrap();
+    let target = Texture2d::empty(display, width, height).unwrap();
+    renderer.draw_at(&mut target.as_surface(), mode, 0.0);
+    target.read()
+}
+
+// Shader arithmetic may round either way.
+fn assert_close(image: &Image, reference: &Image) {
+    assert_eq!(image[0].len(), reference[0].len());
+    assert_eq!(image.len(), reference.len());
+    for (y, (row, expected)) in image.iter().zip(reference).enumerate() {
+        for (x, (pixel, expected)) in row.iter().zip(expected).enumerate() {
+            let close = |a: u8, b: u8| (a as i32 - b as i32).abs() <= 2;
+            assert!(
+                close(pixel.0, expected.0)
+                    && close(pixel.1, expected.1)
+                    && close(pixel.2, expected.2),
+                "({}, {}) is {:?}, not {:?}",
+                x,
+                y,
+                pixel,
+                expected
+            );
+        }
+    }
+}
+
+// Compares `image` with the reference `name`, or replaces the reference when
+// FB_NOW_BLESS is set.
+fn check(image: &Image, name: &str) {
+    if env::var_os("FB_NOW_BLESS").is_some() {
+        bless(image, name);
+    } else {
+        assert_close(image, &reference(name));
+    }
+}
+
+#[test]
+fn a_copy_pass_changes_nothing() {
+    let display = match common::headless() {
+        Some(display) => display,
+        None => return,
+    };
+    let plain = render(&display, (64, 64), ScaleMode::Fit, &[], &WHITE);
+    check(&plain, "fit.png");
+    let copied = render(
+        &display,
+        (64, 64),
+        ScaleMode::Fit,
+        &[shader("copy.glsl")],
+        &WHITE,
+    );
+    assert_close(&copied, &plain);
+}
+
+#[test]
+fn scanlines_darken_between_rows() {
+    let display = match common::headless() {
+        Some(display) => display,
+        None => return,
+    };
+    // Each buffer row covers four window rows, the outer two darker than
+    // the inner two.
+    let image = render(
+        &display,
+        (64, 128),
+        ScaleMode::Stretch,
+        &preset("scanlines").unwrap(),
+        &WHITE,
+    );
+    let column: Vec<u8> = image[..4].iter().map(|row| row[0].0).collect();
+    assert!(column[0] < column[1] && column[1] == column[2] && column[2] > column[3]);
+    check(&image, "scanlines.png");
+}
+
+#[test]
+fn barrel_leaves_the_corners_black() {
+    let display = match common::headless() {
+        Some(display) => display,
+        None => return,
+    };
+    let image = render(
+        &display,
+        (128, 128),
+        ScaleMode::Stretch,
+        &preset("barrel").unwrap(),
+        &checkerboard(),
+    );
+    assert_eq!(image[0][0], (0, 0, 0, 0xFF));
+    assert_eq!(image[127][127], (0, 0, 0, 0xFF));
+    check(&image, "barrel.png");
+}
+
+#[test]
+fn passes_get_the_time() {
+    let display = match common::headless() {
+        Some(display) => display,
+        None => return,
+    };
+    let mut renderer = Renderer::new(&display, 64, 32);
+    renderer.set_passes(&[shader("clock.glsl")]).unwrap();
+    let target = Texture2d::empty(&display, 64, 64).unwrap();
+    renderer.draw_at(&mut target.as_surface(), ScaleMode::Fit, 10.5);
+    let image: Image = target.read();
+    check(&image, "clock.png");
+}
+
+#[test]
+fn every_preset_compiles() {
+    let display = match common::headless() {
+        Some(display) => display,
+        None => return,
+    };
+    let mut renderer = Renderer::new(&display, 64, 32);
+    for name in PASSES.iter().chain(&["vip"]) {
+        renderer.set_passes(&preset(name).unwrap()).unwrap();
+    }
+}
+
+#[test]
+fn a_broken_pass_keeps_the_old_ones() {
+    let display = match common::headless() {
+        Some(display) => display,
+        None => return,
+    };
+    let mut renderer = Renderer::new(&display, 64, 32);
+    renderer.set_passes(&preset("barrel").unwrap()).unwrap();
+    let broken = Pass {
+        name: "broken".to_string(),
+        source: "#version 140\nvoid main() { nonsense }".to_string(),
+    };
+    let error = renderer.set_passes(&[broken]).unwrap_err();
+    assert_eq!(error.pass, "broken");
+    renderer.update_buffer(&[0xFF; 64 * 32 * 3]).unwrap();
+    let target = Texture2d::empty(&display, 64, 64).unwrap();
+    renderer.draw_at(&mut target.as_surface(), ScaleMode::Stretch, 0.0);
+    let image: Image = target.read();
+    let barrel = render(
+        &display,
+        (64, 64),
+        ScaleMode::Stretch,
+        &preset("barrel").unwrap(),
+        &WHITE,
+    );
+    assert_close(&image, &barrel);
+}
@@ -0,0 +1,8 @@
+#!/bin/sh
+# Renders the reference images again with llvmpipe, Mesa's software OpenGL,
+# which is what the tests compare against. Look the new images over before
+# committing them.
+set -e
+cd "$(dirname "$0")"
+LIBGL_ALWAYS_SOFTWARE=1 GALLIUM_DRIVER=llvmpipe FB_NOW_BLESS=1 \
+    cargo test -p fb_now --test passes
@@ -0,0 +1,81 @@
+extern crate fb_now;
+
+use fb_now::{place, preset, Placement, ScaleMode, PASSES};
+
+fn placement(left: i32, bottom: i32, width: u32, height: u32) -> Placement {
+    Placement {
+        left,
+        bottom,
+        width,
+        height,
+    }
+}
+
+#[test]
+fn stretch_covers_the_window() {
+    let window = (800, 600);
+    assert_eq!(
+        place(ScaleMode::Stretch, window, (64, 32)),
+        placement(0, 0, 800, 600)
+    );
+}
+
+#[test]
+fn fit_keeps_the_shape_with_bars() {
+    // Bars above and below, then at the sides
+    assert_eq!(
+        place(ScaleMode::Fit, (800, 600), (64, 32)),
+        placement(0, 100, 800, 400)
+    );
+    assert_eq!(
+        place(ScaleMode::Fit, (1000, 400), (64, 32)),
+        placement(100, 0, 800, 400)
+    );
+}
+
+#[test]
+fn integer_uses_whole_multiples() {
+    assert_eq!(
+        place(ScaleMode::Integer, (800, 600), (64, 32)),
+        placement(16, 108, 768, 384)
+    );
+    // Never below one screen pixel per buffer pixel
+    assert_eq!(
+        place(ScaleMode::Integer, (32, 16), (64, 32)),
+        placement(-16, -8, 64, 32)
+    );
+}
+
+#[test]
+fn fill_crops_what_overflows() {
+    assert_eq!(
+        place(ScaleMode::Fill, (800, 600), (64, 32)),
+        placement(-200, 0, 1200, 600)
+    );
+}
+
+#[test]
+fn modes_cycle_and_parse() {
+    let mut mode = ScaleMode::default();
+    assert_eq!(mode, ScaleMode::Fit);
+    for _ in 0..4 {
+        assert_eq!(ScaleMode::from_name(mode.name()), Some(mode));
+        mode = mode.next();
+    }
+    assert_eq!(mode, ScaleMode::Fit);
+    assert_eq!(ScaleMode::from_name("zoom"), None);
+}
+
+#[test]
+fn presets_name_their_passes() {
+    let vip: Vec<String> = preset("vip")
+        .unwrap()
+        .into_iter()
+        .map(|pass| pass.name)
+        .collect();
+    assert_eq!(vip, ["bloom", "scanlines", "tv", "barrel"]);
+    for name in PASSES.iter() {
+        assert_eq!(preset(name).unwrap()[0].name, *name);
+    }
+    assert_eq!(preset("crt"), None);
+}
@@ -0,0 +1,6 @@
+#version 140
+uniform float time;
+out vec4 f_color;
+void main() {
+    f_color = vec4(fract(time), 0.0, 0.0, 1.0);
+}
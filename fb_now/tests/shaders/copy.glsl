@@ -0,0 +1,7 @@
+#version 140
+uniform sampler2D source;
+in vec2 v_tex_coords;
+out vec4 f_color;
+void main() {
+    f_color = texture(source, v_tex_coords);
+}
@@ -0,0 +1,150 @@
+extern crate fb_now;
+
+use fb_now::{Blitter, BufferError, Region, ScaleMode};
+
+const WHITE: u32 = 0x00FF_FFFF;
+
+fn frame(blitter: &Blitter, window: (u32, u32), mode: ScaleMode) -> Vec<u32> {
+    let mut frame = vec![0x1234_5678; (window.0 * window.1) as usize];
+    blitter.draw(&mut frame, window, mode);
+    frame
+}
+
+#[test]
+fn pixels_are_packed_rgb() {
+    let mut blitter = Blitter::new(2, 1);
+    blitter
+        .update_buffer(&[0x12, 0x34, 0x56, 0xAB, 0xCD, 0xEF])
+        .unwrap();
+    assert_eq!(
+        frame(&blitter, (2, 1), ScaleMode::Stretch),
+        [0x0012_3456, 0x00AB_CDEF]
+    );
+}
+
+#[test]
+fn stretch_repeats_every_pixel() {
+    // A checkerboard, doubled across and tripled down
+    let mut blitter = Blitter::new(2, 2);
+    blitter
+        .update_buffer(&[0xFF, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF])
+        .unwrap();
+    let frame = frame(&blitter, (4, 6), ScaleMode::Stretch);
+    for y in 0..6 {
+        for x in 0..4 {
+            let lit = (x / 2 + y / 3) % 2 == 0;
+            assert_eq!(
+                frame[y * 4 + x],
+                if lit { WHITE } else { 0 },
+                "({}, {})",
+                x,
+                y
+            );
+        }
+    }
+}
+
+#[test]
+fn fit_letterboxes_in_black() {
+    let mut blitter = Blitter::new(64, 32);
+    blitter.update_buffer(&[0xFF; 64 * 32 * 3]).unwrap();
+    let frame = frame(&blitter, (64, 64), ScaleMode::Fit);
+    // Bars of 16 rows above and below
+    assert!(frame[..64 * 16].iter().all(|&pixel| pixel == 0));
+    assert!(frame[64 * 16..64 * 48].iter().all(|&pixel| pixel == WHITE));
+    assert!(frame[64 * 48..].iter().all(|&pixel| pixel == 0));
+}
+
+#[test]
+fn fill_crops_what_overflows() {
+    // The left and right columns fall outside the window.
+    let mut blitter = Blitter::new(4, 1);
+    blitter
+        .update_buffer(&[1, 1, 1, 2, 2, 2, 3, 3, 3, 4, 4, 4])
+        .unwrap();
+    let frame = frame(&blitter, (2, 1), ScaleMode::Fill);
+    assert_eq!(frame, [0x0002_0202, 0x0003_0303]);
+}
+
+#[test]
+fn only_a_new_size_replaces_the_buffer() {
+    let mut blitter = Blitter::new(64, 32);
+    blitter.update_buffer(&[0xFF; 64 * 32 * 3]).unwrap();
+    blitter.resize_buffer(64, 32);
+    assert_eq!(frame(&blitter, (64, 32), ScaleMode::Stretch)[0], WHITE);
+    blitter.resize_buffer(128, 64);
+    assert_eq!(blitter.buffer_size(), (128, 64));
+    assert_eq!(frame(&blitter, (64, 32), ScaleMode::Stretch)[0], 0);
+}
+
+#[test]
+fn an_empty_window_draws_nothing() {
+    let blitter = Blitter::new(64, 32);
+    assert_eq!(frame(&blitter, (0, 0), ScaleMode::Fit), []);
+}
+
+#[test]
+fn a_buffer_of_the_wrong_size_is_refused() {
+    let mut blitter = Blitter::new(2, 1);
+    blitter.update_buffer(&[0xFF; 6]).unwrap();
+    for &length in &[0, 5, 7] {
+        assert_eq!(
+            blitter.update_buffer(&vec![0; length]),
+            Err(BufferError::Length {
+                length,
+                expected: 6
+            })
+        );
+    }
+    // and left as it was
+    assert_eq!(frame(&blitter, (2, 1), ScaleMode::Stretch), [WHITE, WHITE]);
+}
+
+#[test]
+fn a_region_leaves_the_rest_alone() {
+    let mut blitter = Blitter::new(3, 2);
+    blitter.update_buffer(&[0xFF; 3 * 2 * 3]).unwrap();
+    let region = Region {
+        x: 1,
+        y: 0,
+        width: 2,
+        height: 2,
+    };
+    blitter
+        .update_region(region, &[1, 1, 1, 2, 2, 2, 3, 3, 3, 4, 4, 4])
+        .unwrap();
+    assert_eq!(
+        frame(&blitter, (3, 2), ScaleMode::Stretch),
+        [
+            WHITE,
+            0x0001_0101,
+            0x0002_0202,
+            WHITE,
+            0x0003_0303,
+            0x0004_0404
+        ]
+    );
+}
+
+#[test]
+fn a_region_must_fit_the_buffer() {
+    let mut blitter = Blitter::new(3, 2);
+    let region = Region {
+        x: 2,
+        y: 1,
+        width: 2,
+        height: 1,
+    };
+    assert_eq!(
+        blitter.update_region(region, &[0; 6]),
+        Err(BufferError::OutOfBounds(region))
+    );
+    let region = Region { x: 2, ..region };
+    assert_eq!(
+        blitter.update_region(Region { width: 1, ..region }, &[0; 6]),
+        Err(BufferError::Length {
+            length: 6,
+            expected: 3
+        })
+    );
+}